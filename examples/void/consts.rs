@@ -1,5 +1,2 @@
 // Settings
 pub const APP_SIZE: (f32, f32) = (1280.0, 720.0);
-
-// Icon names
-pub const ICON_SHOPPING_CART: u16 = 0xe8cc;
@@ -1,6 +1,6 @@
 use crate::{consts, models::event::Event, widgets::counter_button::CounterButton};
-use flut::{app::App, widgets::button::Button};
-use std::{cell::RefCell, mem, rc::Rc};
+use flut::{app::App, include_font, models::icon_glyph::IconGlyph, widgets::button::Button};
+use std::{cell::RefCell, env, mem, rc::Rc};
 use winit::{
   application::ApplicationHandler, dpi::LogicalPosition, event::WindowEvent,
   event_loop::ActiveEventLoop, window::WindowId,
@@ -8,6 +8,7 @@ use winit::{
 
 // Settings
 const SHOP_BUTTON_POSITION: (f32, f32, f32) = (8.0, 8.0, 0.002);
+const ATLAS_CACHE_FILE_NAME: &str = "void_atlas_cache.bin";
 
 pub struct Game {
   app: Option<App>,
@@ -27,8 +28,11 @@ impl Game {
       .text("SHOP")
       .color((0, 0, 255, 255))
       .text_color((255, 255, 255, 255))
-      .icon_font_path("assets/void/fonts/MaterialSymbolsOutlined-Regular.ttf")
-      .icon_codepoint(consts::ICON_SHOPPING_CART)
+      .icon_font_key(include_font!(
+        "MaterialSymbolsOutlined",
+        "../../assets/void/fonts/MaterialSymbolsOutlined-Regular.ttf"
+      ))
+      .icon_glyph(IconGlyph::from("shopping_cart"))
       .call();
 
     let counter_button = CounterButton::new(&events);
@@ -74,6 +78,7 @@ impl ApplicationHandler for Game {
       .call();
 
     let mut renderer = app.get_renderer();
+    renderer.load_atlas_cache(env::temp_dir().join(ATLAS_CACHE_FILE_NAME));
     self.shop_button.init(&mut renderer);
     self.counter_button.init(&mut renderer);
     self.app = Some(app);
@@ -96,8 +101,7 @@ impl ApplicationHandler for Game {
         };
 
         let mut renderer = app.get_renderer();
-        let window = renderer.get_window();
-        let LogicalPosition { x, y } = mouse_position.to_logical(window.scale_factor());
+        let LogicalPosition { x, y } = mouse_position.to_logical(renderer.get_scale_factor());
         self.shop_button.on_mouse_moved((x, y), &mut renderer);
         self.counter_button.on_mouse_moved((x, y), &mut renderer);
       }
@@ -120,6 +124,16 @@ impl ApplicationHandler for Game {
           .counter_button
           .on_mouse_input(input_state, button, &mut renderer);
       }
+      WindowEvent::ScaleFactorChanged {
+        scale_factor,
+        inner_size_writer: _,
+      } => {
+        let Some(app) = self.app.take() else {
+          return;
+        };
+
+        self.app = Some(app.set_scale_factor(scale_factor));
+      }
       WindowEvent::RedrawRequested => {
         let Some(mut app) = self.app.take() else {
           return;
@@ -147,7 +161,11 @@ impl ApplicationHandler for Game {
   }
 
   fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
-    if let Some(app) = self.app.take() {
+    if let Some(mut app) = self.app.take() {
+      if let Err(err) = app.get_renderer().save_atlas_cache() {
+        eprintln!("Failed to save atlas cache: {err}");
+      }
+
       app.drop();
     }
   }
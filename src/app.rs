@@ -1,10 +1,11 @@
 use crate::{
   app_loop::AppLoop,
   audio,
-  models::{audio_req::AudioReq, model_capacities::ModelCapacities},
+  models::{audio_req::AudioReq, glyph_mode::GlyphMode, model_capacities::ModelCapacities},
   renderer::{Created, Creating, Renderer},
   renderer_ref::RendererRef,
 };
+use image::RgbaImage;
 use optarg2chain::optarg_impl;
 use std::{
   borrow::Cow,
@@ -37,13 +38,21 @@ impl App {
     #[optarg((800_f64, 600_f64))] size: (f64, f64),
     #[optarg_default] model_capacities: ModelCapacities,
     #[optarg((512, 512))] glyph_atlas_size: (u16, u16),
+    #[optarg_default] glyph_mode: GlyphMode,
     #[optarg_default] show_fps: bool,
   ) -> Self {
     let (audio_tx, audio_rx) = mpsc::channel();
     thread::spawn(|| audio::main(audio_rx));
 
-    let renderer =
-      Renderer::new(event_loop, &title, size, model_capacities, glyph_atlas_size).try_into();
+    let renderer = Renderer::new(
+      event_loop,
+      &title,
+      size,
+      model_capacities,
+      glyph_atlas_size,
+      glyph_mode,
+    )
+    .try_into();
 
     let app_loop = AppLoop::new(title, show_fps);
 
@@ -54,6 +63,34 @@ impl App {
     }
   }
 
+  #[optarg_method(AppNewHeadlessBuilder, call)]
+  pub fn new_headless(
+    #[optarg((800_f64, 600_f64))] size: (f64, f64),
+    #[optarg(1_f64)] scale_factor: f64,
+    #[optarg_default] model_capacities: ModelCapacities,
+    #[optarg((512, 512))] glyph_atlas_size: (u16, u16),
+    #[optarg_default] glyph_mode: GlyphMode,
+  ) -> Self {
+    let (audio_tx, audio_rx) = mpsc::channel();
+    thread::spawn(|| audio::main(audio_rx));
+
+    let renderer = Ok(Renderer::new_headless(
+      size,
+      scale_factor,
+      model_capacities,
+      glyph_atlas_size,
+      glyph_mode,
+    ));
+
+    let app_loop = AppLoop::new(Cow::default(), false);
+
+    Self {
+      audio_tx,
+      renderer,
+      app_loop,
+    }
+  }
+
   #[must_use]
   #[inline]
   pub const fn get_audio_tx(&self) -> &Sender<AudioReq> {
@@ -88,12 +125,43 @@ impl App {
     }
   }
 
+  /// Rasterizes text again and rebuilds the render targets for the new scale factor, call it on
+  /// `WindowEvent::ScaleFactorChanged`
+  pub fn set_scale_factor(self, scale_factor: f64) -> Self {
+    let renderer = match self.renderer {
+      Ok(renderer) => renderer.set_scale_factor(scale_factor),
+      Err(mut renderer) => {
+        renderer.set_scale_factor(scale_factor);
+        Err(renderer)
+      }
+    };
+
+    Self {
+      audio_tx: self.audio_tx,
+      renderer,
+      app_loop: self.app_loop,
+    }
+  }
+
+  /// Draws a frame of a headless app and reads it back, or `None` for an app with a window
+  pub fn render_to_image(&mut self) -> Option<RgbaImage> {
+    let Ok(ref mut renderer) = self.renderer else {
+      return None;
+    };
+
+    renderer.render_to_image()
+  }
+
   pub fn request_redraw_if_visible(&self) {
     let window = match self.renderer {
       Ok(ref renderer) => renderer.get_window(),
       Err(ref renderer) => renderer.get_window(),
     };
 
+    let Some(window) = window else {
+      return;
+    };
+
     if !window.is_minimized().unwrap_or_default() && window.is_visible().unwrap_or(true) {
       window.request_redraw();
     }
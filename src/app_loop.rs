@@ -40,13 +40,13 @@ impl AppLoop {
       self.frame_count += 1;
 
       if self.total_frame_time >= 1.0 {
-        let window = renderer.get_window();
-
-        window.set_title(&format!(
-          "{title} | {fps:.2} FPS",
-          title = self.title,
-          fps = 1.0 / (self.total_frame_time / self.frame_count as f32)
-        ));
+        if let Some(window) = renderer.get_window() {
+          window.set_title(&format!(
+            "{title} | {fps:.2} FPS",
+            title = self.title,
+            fps = 1.0 / (self.total_frame_time / self.frame_count as f32)
+          ));
+        }
 
         self.total_frame_time = 0.0;
         self.frame_count = 0;
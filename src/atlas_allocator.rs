@@ -0,0 +1,238 @@
+use crate::collections::lru_cache::LruCache;
+use etagere::{AllocId, Allocation, BucketedAtlasAllocator, Size};
+use std::hash::Hash;
+
+/// Allocates room for glyphs across the pages of one atlas, evicting glyphs no text uses and adding
+/// pages up to a limit when every page is full
+pub struct AtlasAllocator<Key> {
+  page_size: Size,
+  max_page_count: u32,
+  pages: Vec<BucketedAtlasAllocator>,
+  /// Allocations of glyphs no text uses, glyphs without ink have none
+  unused_allocs: LruCache<Key, Option<(u32, AllocId)>>,
+}
+
+impl<Key: Clone + Eq + Hash> AtlasAllocator<Key> {
+  /// Creates an allocator with one empty page
+  /// - `page_size`: width and height of each page in pixels
+  /// - `max_page_count`: most pages the atlas may have
+  #[must_use]
+  pub fn new(page_size: (u16, u16), max_page_count: u32) -> Self {
+    let (page_width, page_height) = page_size;
+    let page_size = Size::new(page_width.into(), page_height.into());
+
+    Self {
+      page_size,
+      max_page_count,
+      pages: vec![BucketedAtlasAllocator::new(page_size)],
+      unused_allocs: LruCache::new(),
+    }
+  }
+
+  #[must_use]
+  #[inline]
+  pub const fn get_page_count(&self) -> u32 {
+    self.pages.len() as u32
+  }
+
+  /// Allocates room for a glyph, or `None` when it is bigger than a page or every page is full of
+  /// glyphs in use
+  /// - `size`: width and height of the glyph in pixels
+  /// - `evicted_keys`: where the keys of glyphs evicted to make room are pushed
+  pub fn allocate(&mut self, size: Size, evicted_keys: &mut Vec<Key>) -> Option<(u32, Allocation)> {
+    if size.width > self.page_size.width || size.height > self.page_size.height {
+      return None;
+    }
+
+    let mut unused_evict_count = 1_usize;
+
+    loop {
+      if let Some(page_alloc) = self
+        .pages
+        .iter_mut()
+        .enumerate()
+        .find_map(|(page, allocator)| allocator.allocate(size).map(|alloc| (page as u32, alloc)))
+      {
+        return Some(page_alloc);
+      }
+
+      for i in 0..unused_evict_count {
+        let Some((unused_key, unused_alloc)) = self.unused_allocs.evict_one() else {
+          if i > 0 {
+            break;
+          }
+
+          // Every glyph in the atlas is in use, so add a page instead
+          if self.get_page_count() >= self.max_page_count {
+            return None;
+          }
+
+          let mut allocator = BucketedAtlasAllocator::new(self.page_size);
+          let alloc = allocator.allocate(size)?;
+          self.pages.push(allocator);
+          return Some((self.get_page_count() - 1, alloc));
+        };
+
+        if let Some((unused_page, unused_alloc_id)) = unused_alloc {
+          self.pages[unused_page as usize].deallocate(unused_alloc_id);
+        }
+
+        evicted_keys.push(unused_key);
+      }
+
+      unused_evict_count <<= 1_usize;
+    }
+  }
+
+  /// Marks a glyph no text uses anymore, so that it gets evicted when the atlas runs out of room
+  /// - `key`: glyph to mark
+  /// - `alloc`: page and allocation of the glyph, `None` when it has no ink
+  #[inline]
+  pub fn release(&mut self, key: Key, alloc: Option<(u32, AllocId)>) {
+    self.unused_allocs.insert(key, alloc);
+  }
+
+  /// Marks a released glyph as used again, so that it is no longer evicted
+  #[inline]
+  pub fn reuse(&mut self, key: &Key) {
+    self.unused_allocs.remove(key);
+  }
+
+  /// Frees every allocation, keeping the pages
+  #[inline]
+  pub fn clear(&mut self) {
+    self
+      .pages
+      .iter_mut()
+      .for_each(BucketedAtlasAllocator::clear);
+    self.unused_allocs.clear();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::AtlasAllocator;
+  use etagere::Size;
+
+  const PAGE_SIZE: (u16, u16) = (32, 32);
+
+  /// Size of a glyph that fills a whole page
+  const PAGE_ALLOC_SIZE: Size = Size::new(32_i32, 32_i32);
+
+  #[test]
+  fn glyph_bigger_than_page_is_not_allocated() {
+    let mut allocator = AtlasAllocator::<u32>::new(PAGE_SIZE, 4);
+    let mut evicted_keys = vec![];
+
+    assert!(
+      allocator
+        .allocate(Size::new(33_i32, 8_i32), &mut evicted_keys)
+        .is_none()
+    );
+
+    assert_eq!(allocator.get_page_count(), 1);
+  }
+
+  #[test]
+  fn page_is_added_when_full() {
+    let mut allocator = AtlasAllocator::<u32>::new(PAGE_SIZE, 4);
+    let mut evicted_keys = vec![];
+
+    let (page, _alloc) = allocator
+      .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+      .unwrap();
+
+    assert_eq!(page, 0);
+
+    let (page, _alloc) = allocator
+      .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+      .unwrap();
+
+    assert_eq!(page, 1);
+    assert_eq!(allocator.get_page_count(), 2);
+    assert!(evicted_keys.is_empty());
+  }
+
+  #[test]
+  fn page_count_is_capped() {
+    let mut allocator = AtlasAllocator::<u32>::new(PAGE_SIZE, 2);
+    let mut evicted_keys = vec![];
+
+    for _page in 0..2_u32 {
+      assert!(
+        allocator
+          .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+          .is_some()
+      );
+    }
+
+    assert!(
+      allocator
+        .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+        .is_none()
+    );
+
+    assert_eq!(allocator.get_page_count(), 2);
+  }
+
+  #[test]
+  fn unused_glyph_is_evicted_before_page_is_added() {
+    let mut allocator = AtlasAllocator::new(PAGE_SIZE, 4);
+    let mut evicted_keys = vec![];
+
+    let (page, alloc) = allocator
+      .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+      .unwrap();
+
+    allocator.release(7_u32, Some((page, alloc.id)));
+    allocator.release(8_u32, None);
+
+    let (page, _alloc) = allocator
+      .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+      .unwrap();
+
+    assert_eq!(page, 0);
+    assert_eq!(allocator.get_page_count(), 1);
+    assert_eq!(evicted_keys, [7]);
+  }
+
+  #[test]
+  fn reused_glyph_is_not_evicted() {
+    let mut allocator = AtlasAllocator::new(PAGE_SIZE, 4);
+    let mut evicted_keys = vec![];
+
+    let (page, alloc) = allocator
+      .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+      .unwrap();
+
+    allocator.release(7_u32, Some((page, alloc.id)));
+    allocator.reuse(&7_u32);
+
+    let (page, _alloc) = allocator
+      .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+      .unwrap();
+
+    assert_eq!(page, 1);
+    assert!(evicted_keys.is_empty());
+  }
+
+  #[test]
+  fn clear_frees_every_page() {
+    let mut allocator = AtlasAllocator::<u32>::new(PAGE_SIZE, 2);
+    let mut evicted_keys = vec![];
+
+    for _page in 0..2_u32 {
+      allocator.allocate(PAGE_ALLOC_SIZE, &mut evicted_keys);
+    }
+
+    allocator.clear();
+
+    assert!(
+      allocator
+        .allocate(PAGE_ALLOC_SIZE, &mut evicted_keys)
+        .is_some()
+    );
+
+    assert_eq!(allocator.get_page_count(), 2);
+  }
+}
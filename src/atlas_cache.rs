@@ -0,0 +1,289 @@
+use rustc_hash::FxHashMap;
+use std::{fs, io, iter, path::Path};
+
+/// Bytes every atlas cache file starts with
+const MAGIC: &[u8; 8] = b"FLUTATLS";
+
+/// Version of the file layout, files of any other version are ignored
+const VERSION: u32 = 2;
+
+/// Identifies a rasterized glyph across launches, floats are stored as their bits
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CachedGlyphKey {
+  /// Hash of the bytes of the font file, which changes whenever the font does
+  pub font_hash: u64,
+  pub font_index: u32,
+  pub glyph_id: u32,
+  pub font_size: u32,
+  pub dilation: u32,
+  pub blur_radius: u32,
+  pub foreground_color: u32,
+  /// Scale factor the glyph was hinted at
+  pub scale_factor: u32,
+  pub sdf: bool,
+}
+
+/// Bounds and pixels of a rasterized glyph, where empty pixels mean it has no ink
+#[derive(Clone, Debug, PartialEq)]
+pub struct CachedGlyph {
+  pub size: (u32, u32),
+  pub bearing: (f32, f32),
+  pub color: bool,
+  pub pixels: Box<[u8]>,
+}
+
+struct ByteReader<'bytes> {
+  bytes: &'bytes [u8],
+}
+
+impl<'bytes> ByteReader<'bytes> {
+  fn take(&mut self, len: usize) -> Option<&'bytes [u8]> {
+    let (taken_bytes, rest_bytes) = self.bytes.split_at_checked(len)?;
+    self.bytes = rest_bytes;
+    Some(taken_bytes)
+  }
+
+  fn read_u8(&mut self) -> Option<u8> {
+    self.take(1).map(|bytes| bytes[0])
+  }
+
+  fn read_u32(&mut self) -> Option<u32> {
+    let bytes = self.take(4)?;
+
+    Some(
+      bytes
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| (value << 8_u32) | u32::from(byte)),
+    )
+  }
+
+  fn read_u64(&mut self) -> Option<u64> {
+    let low = self.read_u32()?;
+    let high = self.read_u32()?;
+    Some((u64::from(high) << 32_u32) | u64::from(low))
+  }
+
+  fn read_glyph(&mut self) -> Option<(CachedGlyphKey, CachedGlyph)> {
+    let glyph_key = CachedGlyphKey {
+      font_hash: self.read_u64()?,
+      font_index: self.read_u32()?,
+      glyph_id: self.read_u32()?,
+      font_size: self.read_u32()?,
+      dilation: self.read_u32()?,
+      blur_radius: self.read_u32()?,
+      foreground_color: self.read_u32()?,
+      scale_factor: self.read_u32()?,
+      sdf: self.read_u8()? != 0,
+    };
+
+    let size = (self.read_u32()?, self.read_u32()?);
+    let bearing = (
+      f32::from_bits(self.read_u32()?),
+      f32::from_bits(self.read_u32()?),
+    );
+    let color = self.read_u8()? != 0;
+    let pixel_count = self.read_u32()?;
+    let pixels = self.take(pixel_count as usize)?.into();
+
+    Some((
+      glyph_key,
+      CachedGlyph {
+        size,
+        bearing,
+        color,
+        pixels,
+      },
+    ))
+  }
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+  bytes.extend((0..4_u32).map(|byte_index| (value >> (byte_index << 3_u32)) as u8));
+}
+
+fn push_u64(bytes: &mut Vec<u8>, value: u64) {
+  push_u32(bytes, value as u32);
+  push_u32(bytes, (value >> 32_u32) as u32);
+}
+
+fn parse(bytes: &[u8], settings_hash: u64) -> Option<FxHashMap<CachedGlyphKey, CachedGlyph>> {
+  let mut reader = ByteReader { bytes };
+
+  if reader.take(MAGIC.len())? != MAGIC
+    || reader.read_u32()? != VERSION
+    || reader.read_u64()? != settings_hash
+  {
+    return None;
+  }
+
+  let glyph_count = reader.read_u32()?;
+
+  let glyphs = iter::repeat_with(|| reader.read_glyph())
+    .take(glyph_count as usize)
+    .collect::<Option<FxHashMap<_, _>>>()?;
+
+  reader.bytes.is_empty().then_some(glyphs)
+}
+
+/// Reads the glyphs cached in a file, or none when it is missing, corrupt or was written with other
+/// settings
+/// - `path`: file written by `write`
+/// - `settings_hash`: hash of the settings the glyphs have to be rasterized with
+#[must_use]
+pub fn read(path: &Path, settings_hash: u64) -> FxHashMap<CachedGlyphKey, CachedGlyph> {
+  let bytes = match fs::read(path) {
+    Ok(bytes) => bytes,
+    Err(err) if err.kind() == io::ErrorKind::NotFound => return FxHashMap::default(),
+    Err(err) => {
+      eprintln!(
+        "Failed to read atlas cache {path}: {err}",
+        path = path.display()
+      );
+      return FxHashMap::default();
+    }
+  };
+
+  parse(&bytes, settings_hash).unwrap_or_else(|| {
+    eprintln!("Ignoring stale atlas cache {path}", path = path.display());
+    FxHashMap::default()
+  })
+}
+
+/// Writes glyphs into a file that `read` loads back
+/// - `path`: file to create or replace
+/// - `settings_hash`: hash of the settings the glyphs were rasterized with
+/// - `glyphs`: glyphs to write
+pub fn write<
+  'glyph,
+  Glyphs: ExactSizeIterator<Item = (&'glyph CachedGlyphKey, &'glyph CachedGlyph)>,
+>(
+  path: &Path,
+  settings_hash: u64,
+  glyphs: Glyphs,
+) -> io::Result<()> {
+  let mut bytes = MAGIC.to_vec();
+  push_u32(&mut bytes, VERSION);
+  push_u64(&mut bytes, settings_hash);
+  push_u32(&mut bytes, glyphs.len() as u32);
+
+  bytes.extend(glyphs.flat_map(|(glyph_key, glyph)| {
+    let mut glyph_bytes = vec![];
+    push_u64(&mut glyph_bytes, glyph_key.font_hash);
+
+    for value in [
+      glyph_key.font_index,
+      glyph_key.glyph_id,
+      glyph_key.font_size,
+      glyph_key.dilation,
+      glyph_key.blur_radius,
+      glyph_key.foreground_color,
+      glyph_key.scale_factor,
+    ] {
+      push_u32(&mut glyph_bytes, value);
+    }
+
+    glyph_bytes.push(u8::from(glyph_key.sdf));
+    let (width, height) = glyph.size;
+    let (bearing_x, bearing_y) = glyph.bearing;
+
+    for value in [width, height, bearing_x.to_bits(), bearing_y.to_bits()] {
+      push_u32(&mut glyph_bytes, value);
+    }
+
+    glyph_bytes.push(u8::from(glyph.color));
+    push_u32(&mut glyph_bytes, glyph.pixels.len() as u32);
+    glyph_bytes.extend(&glyph.pixels);
+    glyph_bytes
+  }));
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{CachedGlyph, CachedGlyphKey};
+  use rustc_hash::FxHashMap;
+  use std::{env, fs, path::PathBuf};
+
+  const SETTINGS_HASH: u64 = 0x0123_4567_89AB_CDEF;
+
+  fn make_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("flut_atlas_cache_test_{name}.bin"))
+  }
+
+  fn make_glyphs() -> FxHashMap<CachedGlyphKey, CachedGlyph> {
+    let glyph_key = CachedGlyphKey {
+      font_hash: 0xFEDC_BA98_7654_3210,
+      font_index: 0,
+      glyph_id: 42,
+      font_size: 16.0_f32.to_bits(),
+      dilation: 0,
+      blur_radius: 0,
+      foreground_color: 0,
+      scale_factor: 1.0_f32.to_bits(),
+      sdf: false,
+    };
+
+    let glyph = CachedGlyph {
+      size: (2, 1),
+      bearing: (0.5, -8.0),
+      color: false,
+      pixels: (0..12).collect(),
+    };
+
+    let invisible_glyph_key = CachedGlyphKey {
+      glyph_id: 3,
+      ..glyph_key
+    };
+
+    let invisible_glyph = CachedGlyph {
+      size: (0, 0),
+      bearing: (0.0, 0.0),
+      color: false,
+      pixels: Box::default(),
+    };
+
+    FxHashMap::from_iter([(glyph_key, glyph), (invisible_glyph_key, invisible_glyph)])
+  }
+
+  #[test]
+  fn round_trips() {
+    let path = make_path("round_trip");
+    let glyphs = make_glyphs();
+
+    super::write(&path, SETTINGS_HASH, glyphs.iter()).unwrap();
+    assert_eq!(super::read(&path, SETTINGS_HASH), glyphs);
+    fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn ignores_other_settings() {
+    let path = make_path("other_settings");
+
+    super::write(&path, SETTINGS_HASH, make_glyphs().iter()).unwrap();
+    assert!(super::read(&path, !SETTINGS_HASH).is_empty());
+    fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn ignores_corrupt_file() {
+    let path = make_path("corrupt");
+
+    super::write(&path, SETTINGS_HASH, make_glyphs().iter()).unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+
+    assert!(super::read(&path, SETTINGS_HASH).is_empty());
+    fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn is_empty_without_file() {
+    assert!(super::read(&make_path("missing"), SETTINGS_HASH).is_empty());
+  }
+}
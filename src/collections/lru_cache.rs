@@ -38,6 +38,12 @@ impl<K, V> LruCache<K, V> {
       next_seq: 0,
     }
   }
+
+  #[inline]
+  pub fn clear(&mut self) {
+    self.seq_to_key.clear();
+    self.key_to_value.clear();
+  }
 }
 
 impl<K: Clone + Eq + Hash, V> LruCache<K, V> {
@@ -0,0 +1,1300 @@
+use font_kit::{
+  canvas::{Canvas, Format, RasterizationOptions},
+  font::Font,
+  hinting::HintingOptions,
+};
+use image::{
+  DynamicImage, ImageFormat, RgbaImage,
+  imageops::{self, FilterType},
+};
+use pathfinder_geometry::{
+  rect::{RectF, RectI},
+  transform2d::{Matrix2x2F, Transform2F},
+  vector::{Vector2F, Vector2I},
+};
+use rustybuzz::ttf_parser::{
+  Face, GlyphId, RasterGlyphImage, RasterImageFormat, RgbaColor, Transform,
+  colr::{ClipBox, ColorStop, CompositeMode, GradientExtend, Paint, Painter},
+};
+
+/// Colour stops of a COLR gradient sorted by offset, and how the gradient goes on past them
+struct ColorLine {
+  stops: Box<[ColorStop]>,
+  extend: GradientExtend,
+}
+
+impl ColorLine {
+  fn new<Stops: Iterator<Item = ColorStop>>(stops: Stops, extend: GradientExtend) -> Self {
+    let mut stops = stops.collect::<Box<[_]>>();
+    stops.sort_by(|stop, other_stop| stop.stop_offset.total_cmp(&other_stop.stop_offset));
+    Self { stops, extend }
+  }
+
+  /// Premultiplied colour at `offset` along the line, extending the span from the first stop to the
+  /// last one past both ends
+  fn calc_color(&self, offset: f32) -> [f32; 4] {
+    let (Some(first_stop), Some(last_stop)) = (self.stops.first(), self.stops.last()) else {
+      return [0.0; 4];
+    };
+
+    let offset_span = last_stop.stop_offset - first_stop.stop_offset;
+
+    let offset = if offset_span > 0.0 {
+      let unit_offset = (offset - first_stop.stop_offset) / offset_span;
+
+      let unit_offset = match self.extend {
+        GradientExtend::Pad => unit_offset.clamp(0.0, 1.0),
+        GradientExtend::Repeat => unit_offset - unit_offset.floor(),
+        GradientExtend::Reflect => {
+          let period_offset = unit_offset.rem_euclid(2.0);
+
+          if period_offset > 1.0 {
+            2.0 - period_offset
+          } else {
+            period_offset
+          }
+        }
+      };
+
+      unit_offset.mul_add(offset_span, first_stop.stop_offset)
+    } else {
+      offset
+    };
+
+    let next_stop_index = self
+      .stops
+      .partition_point(|stop| stop.stop_offset <= offset);
+
+    match (
+      next_stop_index
+        .checked_sub(1)
+        .and_then(|stop_index| self.stops.get(stop_index)),
+      self.stops.get(next_stop_index),
+    ) {
+      (Some(stop), Some(next_stop)) => {
+        let weight = (offset - stop.stop_offset) / (next_stop.stop_offset - stop.stop_offset);
+        lerp_color(
+          premultiply(stop.color),
+          premultiply(next_stop.color),
+          weight,
+        )
+      }
+      (Some(stop), None) | (None, Some(stop)) => premultiply(stop.color),
+      (None, None) => [0.0; 4],
+    }
+  }
+}
+
+/// Geometry of a COLR gradient in the font units of the paint it belongs to
+#[derive(Clone, Copy)]
+enum GradientShape {
+  /// Colour changes along the line from `start` to `end`
+  Linear { start: Vector2F, end: Vector2F },
+  /// Colour changes along the circles swept from the start circle to the end circle
+  Radial {
+    start_center: Vector2F,
+    start_radius: f32,
+    end_center: Vector2F,
+    end_radius: f32,
+  },
+  /// Colour changes counter-clockwise around `center`, from `start_angle` to `end_angle` in degrees
+  Sweep {
+    center: Vector2F,
+    start_angle: f32,
+    end_angle: f32,
+  },
+}
+
+impl GradientShape {
+  /// Linear gradient from `start` towards `end`, turned so that its colours run parallel to the line
+  /// from `start` to `rotation_point`
+  fn new_linear(start: Vector2F, end: Vector2F, rotation_point: Vector2F) -> Self {
+    let rotation_vector = rotation_point - start;
+    let normal = Vector2F::new(rotation_vector.y(), -rotation_vector.x());
+    let normal_square_length = normal.square_length();
+
+    // A gradient without rotation is drawn nowhere, as the spec asks
+    let end = if normal_square_length > 0.0 {
+      start + normal * ((end - start).dot(normal) / normal_square_length)
+    } else {
+      start
+    };
+
+    Self::Linear { start, end }
+  }
+
+  /// Offset along the colour line at `point` in font units, or `None` where nothing is drawn
+  fn calc_offset(self, point: Vector2F) -> Option<f32> {
+    match self {
+      Self::Linear { start, end } => {
+        let direction = end - start;
+        let square_length = direction.square_length();
+        (square_length > 0.0).then(|| (point - start).dot(direction) / square_length)
+      }
+      Self::Radial {
+        start_center,
+        start_radius,
+        end_center,
+        end_radius,
+      } => {
+        // Solves |point - center(offset)| = radius(offset) for the biggest offset whose radius is
+        // not negative
+        let center_delta = end_center - start_center;
+        let point_delta = point - start_center;
+        let radius_delta = end_radius - start_radius;
+        let quad_a = radius_delta.mul_add(-radius_delta, center_delta.square_length());
+        let half_quad_b = start_radius.mul_add(radius_delta, point_delta.dot(center_delta));
+        let quad_c = start_radius.mul_add(-start_radius, point_delta.square_length());
+        let is_drawn = |offset: f32| offset.mul_add(radius_delta, start_radius) >= 0.0;
+
+        if quad_a.abs() <= f32::EPSILON {
+          // Circles that grow as fast as they move only touch each point once
+          if half_quad_b == 0.0 {
+            return None;
+          }
+
+          let offset = quad_c / (2.0 * half_quad_b);
+          return is_drawn(offset).then_some(offset);
+        }
+
+        let discriminant = half_quad_b.mul_add(half_quad_b, -quad_a * quad_c);
+
+        if discriminant < 0.0 {
+          return None;
+        }
+
+        let offset = (half_quad_b + discriminant.sqrt()) / quad_a;
+        let other_offset = (half_quad_b - discriminant.sqrt()) / quad_a;
+
+        [offset.max(other_offset), offset.min(other_offset)]
+          .into_iter()
+          .find(|&offset| is_drawn(offset))
+      }
+      Self::Sweep {
+        center,
+        start_angle,
+        end_angle,
+      } => {
+        let angle_span = end_angle - start_angle;
+
+        if angle_span == 0.0 {
+          return None;
+        }
+
+        let point_delta = point - center;
+
+        let angle = point_delta
+          .y()
+          .atan2(point_delta.x())
+          .to_degrees()
+          .rem_euclid(360.0);
+
+        Some((angle - start_angle) / angle_span)
+      }
+    }
+  }
+}
+
+/// How a COLR glyph fills an area
+enum ColorPaint {
+  Solid(RgbaColor),
+  Gradient(GradientShape, ColorLine),
+}
+
+impl ColorPaint {
+  fn new(paint: &Paint<'_>) -> Self {
+    match *paint {
+      Paint::Solid(color) => Self::Solid(color),
+      Paint::LinearGradient(ref gradient) => Self::Gradient(
+        GradientShape::new_linear(
+          Vector2F::new(gradient.x0, gradient.y0),
+          Vector2F::new(gradient.x1, gradient.y1),
+          Vector2F::new(gradient.x2, gradient.y2),
+        ),
+        ColorLine::new(gradient.stops(0, &[]), gradient.extend),
+      ),
+      Paint::RadialGradient(ref gradient) => Self::Gradient(
+        GradientShape::Radial {
+          start_center: Vector2F::new(gradient.x0, gradient.y0),
+          start_radius: gradient.r0,
+          end_center: Vector2F::new(gradient.x1, gradient.y1),
+          end_radius: gradient.r1,
+        },
+        ColorLine::new(gradient.stops(0, &[]), gradient.extend),
+      ),
+      // Angles are stored in half turns
+      Paint::SweepGradient(ref gradient) => Self::Gradient(
+        GradientShape::Sweep {
+          center: Vector2F::new(gradient.center_x, gradient.center_y),
+          start_angle: gradient.start_angle * 180.0,
+          end_angle: gradient.end_angle * 180.0,
+        },
+        ColorLine::new(gradient.stops(0, &[]), gradient.extend),
+      ),
+    }
+  }
+
+  /// Colours the paint is made of, in order
+  fn get_colors(&self) -> Vec<RgbaColor> {
+    match *self {
+      Self::Solid(color) => vec![color],
+      Self::Gradient(_shape, ref color_line) => {
+        color_line.stops.iter().map(|stop| stop.color).collect()
+      }
+    }
+  }
+}
+
+/// Step of drawing a COLR glyph, transforms are in font units
+enum PaintOp {
+  /// Fills the outline of a glyph, or everything the clips leave when there is none
+  Fill {
+    glyph_id: Option<GlyphId>,
+    paint: ColorPaint,
+    transform: Transform,
+  },
+  /// Clips everything drawn until the clip is popped to the outline of a glyph
+  PushClip {
+    glyph_id: Option<GlyphId>,
+    transform: Transform,
+  },
+  /// Clips everything drawn until the clip is popped to a box
+  PushClipBox {
+    clip_box: ClipBox,
+    transform: Transform,
+  },
+  PopClip,
+  /// Draws everything until the layer is popped on its own, then composites it onto what is below
+  PushLayer(CompositeMode),
+  PopLayer,
+}
+
+/// Records the steps of drawing a COLR glyph, both version 0 layers and version 1 paint graphs
+struct PaintRecorder {
+  outline_glyph_id: Option<GlyphId>,
+  /// Transforms pushed so far, each combined with the ones before it
+  transforms: Vec<Transform>,
+  ops: Vec<PaintOp>,
+}
+
+impl PaintRecorder {
+  fn get_transform(&self) -> Transform {
+    self.transforms.last().copied().unwrap_or_default()
+  }
+}
+
+impl<'font> Painter<'font> for PaintRecorder {
+  fn outline_glyph(&mut self, glyph_id: GlyphId) {
+    self.outline_glyph_id = Some(glyph_id);
+  }
+
+  fn paint(&mut self, paint: Paint<'font>) {
+    self.ops.push(PaintOp::Fill {
+      glyph_id: self.outline_glyph_id.take(),
+      paint: ColorPaint::new(&paint),
+      transform: self.get_transform(),
+    });
+  }
+
+  fn push_clip(&mut self) {
+    self.ops.push(PaintOp::PushClip {
+      glyph_id: self.outline_glyph_id.take(),
+      transform: self.get_transform(),
+    });
+  }
+
+  fn push_clip_box(&mut self, clipbox: ClipBox) {
+    self.ops.push(PaintOp::PushClipBox {
+      clip_box: clipbox,
+      transform: self.get_transform(),
+    });
+  }
+
+  fn pop_clip(&mut self) {
+    self.ops.push(PaintOp::PopClip);
+  }
+
+  fn push_layer(&mut self, mode: CompositeMode) {
+    self.ops.push(PaintOp::PushLayer(mode));
+  }
+
+  fn pop_layer(&mut self) {
+    self.ops.push(PaintOp::PopLayer);
+  }
+
+  fn push_transform(&mut self, transform: Transform) {
+    self
+      .transforms
+      .push(Transform::combine(self.get_transform(), transform));
+  }
+
+  fn pop_transform(&mut self) {
+    self.transforms.pop();
+  }
+}
+
+/// Draws the steps of a COLR glyph into premultiplied RGBA pixels
+struct ColorCanvas<'font> {
+  font: &'font Font,
+  font_size: f32,
+  /// Pixels per font unit
+  font_scale: f32,
+  canvas_size: Vector2I,
+  /// Raster position of the top left corner of the canvas
+  canvas_origin: Vector2I,
+  /// Raster position of the center of each pixel
+  pixel_points: Box<[Vector2F]>,
+  /// Coverage of each pixel left by the clips pushed so far
+  clips: Vec<Box<[f32]>>,
+  /// Layers pushed so far, with the mode each is composited onto the one below with
+  layers: Vec<(Box<[[f32; 4]]>, CompositeMode)>,
+}
+
+impl<'font> ColorCanvas<'font> {
+  fn new(font: &'font Font, font_size: f32, font_scale: f32, bounds: RectI, margin: i32) -> Self {
+    let canvas_size = bounds.size() + Vector2I::splat(margin << 1_i32);
+    let canvas_origin = bounds.origin() - Vector2I::splat(margin);
+
+    let pixel_points = (0_i32..canvas_size.y())
+      .flat_map(|y| (0_i32..canvas_size.x()).map(move |x| Vector2I::new(x, y)))
+      .map(|pixel| (pixel + canvas_origin).to_f32() + Vector2F::splat(0.5))
+      .collect::<Box<[_]>>();
+
+    let pixel_count = pixel_points.len();
+
+    Self {
+      font,
+      font_size,
+      font_scale,
+      canvas_size,
+      canvas_origin,
+      pixel_points,
+      clips: vec![],
+      layers: vec![(
+        vec![[0.0; 4]; pixel_count].into_boxed_slice(),
+        CompositeMode::SourceOver,
+      )],
+    }
+  }
+
+  /// Coverage of each pixel by the outline of a glyph
+  fn calc_glyph_coverage(&self, glyph_id: GlyphId, raster_transform: Transform2F) -> Box<[f32]> {
+    let mut canvas = Canvas::new(self.canvas_size, Format::A8);
+    let canvas_transform = Transform2F::from_translation((-self.canvas_origin).to_f32());
+
+    self
+      .font
+      .rasterize_glyph(
+        &mut canvas,
+        u32::from(glyph_id.0),
+        self.font_size,
+        canvas_transform * raster_transform,
+        HintingOptions::None,
+        RasterizationOptions::GrayscaleAa,
+      )
+      .unwrap();
+
+    canvas
+      .pixels
+      .chunks_exact(canvas.stride)
+      .flat_map(|row| &row[..self.canvas_size.x() as usize])
+      .map(|&coverage| f32::from(coverage) / 255.0)
+      .collect()
+  }
+
+  /// Coverage of each pixel by a clip box
+  fn calc_clip_box_coverage(&self, clip_box: ClipBox, raster_transform: Transform2F) -> Box<[f32]> {
+    let inverse_transform = raster_transform.inverse();
+
+    self
+      .pixel_points
+      .iter()
+      .map(|&raster_point| {
+        let point = to_paint_point(inverse_transform, self.font_scale, raster_point);
+
+        let inside = (clip_box.x_min..=clip_box.x_max).contains(&point.x())
+          && (clip_box.y_min..=clip_box.y_max).contains(&point.y());
+
+        if inside { 1.0 } else { 0.0 }
+      })
+      .collect()
+  }
+
+  fn push_clip(&mut self, coverage: Box<[f32]>) {
+    let clip = match self.clips.last() {
+      Some(parent_clip) => parent_clip
+        .iter()
+        .zip(coverage)
+        .map(|(&parent_coverage, coverage)| parent_coverage * coverage)
+        .collect(),
+      None => coverage,
+    };
+
+    self.clips.push(clip);
+  }
+
+  fn fill(&mut self, glyph_id: Option<GlyphId>, paint: &ColorPaint, transform: Transform) {
+    let raster_transform = to_raster_transform(transform, self.font_scale);
+
+    // Paints squashed flat cover nothing
+    if raster_transform.matrix.det() == 0.0 {
+      return;
+    }
+
+    let glyph_coverage =
+      glyph_id.map(|glyph_id| self.calc_glyph_coverage(glyph_id, raster_transform));
+
+    let inverse_transform = raster_transform.inverse();
+    let clip = self.clips.last();
+    let &mut (ref mut layer, _mode) = self.layers.last_mut().unwrap();
+
+    for (pixel_index, (pixel, &raster_point)) in
+      layer.iter_mut().zip(&self.pixel_points).enumerate()
+    {
+      let clip_coverage = clip.map_or(1.0, |clip| clip[pixel_index]);
+      let coverage = glyph_coverage
+        .as_ref()
+        .map_or(clip_coverage, |glyph_coverage| {
+          clip_coverage * glyph_coverage[pixel_index]
+        });
+
+      if coverage <= 0.0 {
+        continue;
+      }
+
+      let color = match *paint {
+        ColorPaint::Solid(color) => premultiply(color),
+        ColorPaint::Gradient(shape, ref color_line) => {
+          let paint_point = to_paint_point(inverse_transform, self.font_scale, raster_point);
+
+          let Some(offset) = shape.calc_offset(paint_point) else {
+            continue;
+          };
+
+          color_line.calc_color(offset)
+        }
+      };
+
+      *pixel = composite(
+        CompositeMode::SourceOver,
+        color.map(|channel| channel * coverage),
+        *pixel,
+      );
+    }
+  }
+
+  /// Composites the top layer onto the one below it within the current clip, the bottom layer stays
+  fn pop_layer(&mut self) {
+    if self.layers.len() < 2 {
+      return;
+    }
+
+    let (src_layer, mode) = self.layers.pop().unwrap();
+    let clip = self.clips.last();
+    let &mut (ref mut dst_layer, _dst_mode) = self.layers.last_mut().unwrap();
+
+    for (pixel_index, (dst_pixel, &src_pixel)) in dst_layer.iter_mut().zip(&src_layer).enumerate() {
+      let coverage = clip.map_or(1.0, |clip| clip[pixel_index]);
+      let composited_pixel = composite(mode, src_pixel, *dst_pixel);
+      *dst_pixel = lerp_color(*dst_pixel, composited_pixel, coverage);
+    }
+  }
+
+  fn draw(&mut self, op: &PaintOp) {
+    match *op {
+      PaintOp::Fill {
+        glyph_id,
+        ref paint,
+        transform,
+      } => self.fill(glyph_id, paint, transform),
+      PaintOp::PushClip {
+        glyph_id,
+        transform,
+      } => {
+        let raster_transform = to_raster_transform(transform, self.font_scale);
+
+        // A clip without an outline leaves everything as it is
+        let coverage = glyph_id.map_or_else(
+          || vec![1.0; self.pixel_points.len()].into_boxed_slice(),
+          |glyph_id| self.calc_glyph_coverage(glyph_id, raster_transform),
+        );
+
+        self.push_clip(coverage);
+      }
+      PaintOp::PushClipBox {
+        clip_box,
+        transform,
+      } => {
+        let raster_transform = to_raster_transform(transform, self.font_scale);
+        let coverage = self.calc_clip_box_coverage(clip_box, raster_transform);
+        self.push_clip(coverage);
+      }
+      PaintOp::PopClip => {
+        self.clips.pop();
+      }
+      PaintOp::PushLayer(mode) => {
+        let pixel_count = self.pixel_points.len();
+
+        self
+          .layers
+          .push((vec![[0.0; 4]; pixel_count].into_boxed_slice(), mode));
+      }
+      PaintOp::PopLayer => self.pop_layer(),
+    }
+  }
+
+  /// Straight alpha RGBA pixels of everything drawn, compositing layers left pushed
+  fn into_pixels(mut self) -> Vec<u8> {
+    while self.layers.len() > 1 {
+      self.pop_layer();
+    }
+
+    let (layer, _mode) = self.layers.pop().unwrap();
+
+    layer
+      .iter()
+      .flat_map(|&[red, green, blue, alpha]| {
+        if alpha <= 0.0 {
+          return [0; 4];
+        }
+
+        [red / alpha, green / alpha, blue / alpha, alpha]
+          .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+      })
+      .collect()
+  }
+}
+
+/// Colour with straight alpha in sRGB as premultiplied channels from 0 to 1
+fn premultiply(color: RgbaColor) -> [f32; 4] {
+  let alpha = f32::from(color.alpha) / 255.0;
+
+  [
+    f32::from(color.red) / 255.0 * alpha,
+    f32::from(color.green) / 255.0 * alpha,
+    f32::from(color.blue) / 255.0 * alpha,
+    alpha,
+  ]
+}
+
+fn lerp_color(color: [f32; 4], other_color: [f32; 4], weight: f32) -> [f32; 4] {
+  let mut lerped_color = color;
+
+  for (channel, other_channel) in lerped_color.iter_mut().zip(other_color) {
+    *channel = (other_channel - *channel).mul_add(weight, *channel);
+  }
+
+  lerped_color
+}
+
+/// Luminosity of a colour with straight alpha
+fn calc_lum(color: [f32; 3]) -> f32 {
+  let [red, green, blue] = color;
+  0.11_f32.mul_add(blue, 0.3_f32.mul_add(red, 0.59 * green))
+}
+
+/// Moves each channel of a colour by the same amount so that it gets the luminosity `lum`, keeping
+/// its channels from 0 to 1
+fn set_lum(color: [f32; 3], lum: f32) -> [f32; 3] {
+  let lum_delta = lum - calc_lum(color);
+  let color = color.map(|channel| channel + lum_delta);
+  let lum = calc_lum(color);
+  let min_channel = color.into_iter().fold(f32::INFINITY, f32::min);
+  let max_channel = color.into_iter().fold(f32::NEG_INFINITY, f32::max);
+
+  color.map(|channel| {
+    if min_channel < 0.0 {
+      (channel - lum) * lum / (lum - min_channel) + lum
+    } else if max_channel > 1.0 {
+      (channel - lum) * (1.0 - lum) / (max_channel - lum) + lum
+    } else {
+      channel
+    }
+  })
+}
+
+/// Saturation of a colour with straight alpha
+fn calc_sat(color: [f32; 3]) -> f32 {
+  color.into_iter().fold(f32::NEG_INFINITY, f32::max)
+    - color.into_iter().fold(f32::INFINITY, f32::min)
+}
+
+/// Stretches the channels of a colour between its smallest and biggest channel so that it gets
+/// the saturation `sat`
+fn set_sat(color: [f32; 3], sat: f32) -> [f32; 3] {
+  let min_channel = color.into_iter().fold(f32::INFINITY, f32::min);
+  let color_sat = calc_sat(color);
+
+  if color_sat <= 0.0 {
+    return [0.0; 3];
+  }
+
+  color.map(|channel| (channel - min_channel) * sat / color_sat)
+}
+
+/// Blends two colours with straight alpha the way a separable or non-separable blend mode does, or
+/// `None` for Porter-Duff modes
+fn blend(mode: CompositeMode, src_color: [f32; 3], dst_color: [f32; 3]) -> Option<[f32; 3]> {
+  let separable_blend = |blend_channel: fn(f32, f32) -> f32| {
+    let mut blended_color = src_color;
+
+    for (src_channel, dst_channel) in blended_color.iter_mut().zip(dst_color) {
+      *src_channel = blend_channel(*src_channel, dst_channel);
+    }
+
+    blended_color
+  };
+
+  let screen = |src_channel: f32, dst_channel: f32| {
+    src_channel.mul_add(-dst_channel, src_channel + dst_channel)
+  };
+
+  let hard_light = |src_channel: f32, dst_channel: f32| {
+    if src_channel <= 0.5 {
+      dst_channel * 2.0 * src_channel
+    } else {
+      let src_channel = 2.0_f32.mul_add(src_channel, -1.0);
+      src_channel.mul_add(-dst_channel, src_channel + dst_channel)
+    }
+  };
+
+  let blended_color = match mode {
+    CompositeMode::Screen => separable_blend(screen),
+    CompositeMode::Overlay => separable_blend(|src_channel, dst_channel| {
+      if dst_channel <= 0.5 {
+        src_channel * 2.0 * dst_channel
+      } else {
+        let dst_channel = 2.0_f32.mul_add(dst_channel, -1.0);
+        dst_channel.mul_add(-src_channel, dst_channel + src_channel)
+      }
+    }),
+    CompositeMode::Darken => separable_blend(f32::min),
+    CompositeMode::Lighten => separable_blend(f32::max),
+    CompositeMode::ColorDodge => separable_blend(|src_channel, dst_channel| {
+      if dst_channel <= 0.0 {
+        0.0
+      } else if src_channel >= 1.0 {
+        1.0
+      } else {
+        (dst_channel / (1.0 - src_channel)).min(1.0)
+      }
+    }),
+    CompositeMode::ColorBurn => separable_blend(|src_channel, dst_channel| {
+      if dst_channel >= 1.0 {
+        1.0
+      } else if src_channel <= 0.0 {
+        0.0
+      } else {
+        1.0 - ((1.0 - dst_channel) / src_channel).min(1.0)
+      }
+    }),
+    CompositeMode::HardLight => separable_blend(hard_light),
+    CompositeMode::SoftLight => separable_blend(|src_channel, dst_channel| {
+      if src_channel <= 0.5 {
+        (2.0_f32.mul_add(-src_channel, 1.0) * dst_channel).mul_add(dst_channel - 1.0, dst_channel)
+      } else {
+        let dst_curve = if dst_channel <= 0.25 {
+          16.0_f32
+            .mul_add(dst_channel, -12.0)
+            .mul_add(dst_channel, 4.0)
+            * dst_channel
+        } else {
+          dst_channel.sqrt()
+        };
+
+        2.0_f32
+          .mul_add(src_channel, -1.0)
+          .mul_add(dst_curve - dst_channel, dst_channel)
+      }
+    }),
+    CompositeMode::Difference => {
+      separable_blend(|src_channel, dst_channel| (src_channel - dst_channel).abs())
+    }
+    CompositeMode::Exclusion => separable_blend(|src_channel, dst_channel| {
+      (2.0 * src_channel).mul_add(-dst_channel, src_channel + dst_channel)
+    }),
+    CompositeMode::Multiply => {
+      separable_blend(|src_channel, dst_channel| src_channel * dst_channel)
+    }
+    CompositeMode::Hue => set_lum(set_sat(src_color, calc_sat(dst_color)), calc_lum(dst_color)),
+    CompositeMode::Saturation => {
+      set_lum(set_sat(dst_color, calc_sat(src_color)), calc_lum(dst_color))
+    }
+    CompositeMode::Color => set_lum(src_color, calc_lum(dst_color)),
+    CompositeMode::Luminosity => set_lum(dst_color, calc_lum(src_color)),
+    CompositeMode::Clear
+    | CompositeMode::Source
+    | CompositeMode::Destination
+    | CompositeMode::SourceOver
+    | CompositeMode::DestinationOver
+    | CompositeMode::SourceIn
+    | CompositeMode::DestinationIn
+    | CompositeMode::SourceOut
+    | CompositeMode::DestinationOut
+    | CompositeMode::SourceAtop
+    | CompositeMode::DestinationAtop
+    | CompositeMode::Xor
+    | CompositeMode::Plus => return None,
+  };
+
+  Some(blended_color)
+}
+
+/// Composites a premultiplied colour onto another one with a COLR composite mode
+fn composite(mode: CompositeMode, src_color: [f32; 4], dst_color: [f32; 4]) -> [f32; 4] {
+  let [src_red, src_green, src_blue, src_alpha] = src_color;
+  let [dst_red, dst_green, dst_blue, dst_alpha] = dst_color;
+
+  // Porter-Duff modes weigh each colour as a whole
+  let (src_weight, dst_weight) = match mode {
+    CompositeMode::Clear => (0.0, 0.0),
+    CompositeMode::Source => (1.0, 0.0),
+    CompositeMode::Destination => (0.0, 1.0),
+    CompositeMode::SourceOver => (1.0, 1.0 - src_alpha),
+    CompositeMode::DestinationOver => (1.0 - dst_alpha, 1.0),
+    CompositeMode::SourceIn => (dst_alpha, 0.0),
+    CompositeMode::DestinationIn => (0.0, src_alpha),
+    CompositeMode::SourceOut => (1.0 - dst_alpha, 0.0),
+    CompositeMode::DestinationOut => (0.0, 1.0 - src_alpha),
+    CompositeMode::SourceAtop => (dst_alpha, 1.0 - src_alpha),
+    CompositeMode::DestinationAtop => (1.0 - dst_alpha, src_alpha),
+    CompositeMode::Xor => (1.0 - dst_alpha, 1.0 - src_alpha),
+    CompositeMode::Plus => {
+      let mut composited_color = src_color;
+
+      for (src_channel, dst_channel) in composited_color.iter_mut().zip(dst_color) {
+        *src_channel = (*src_channel + dst_channel).min(1.0);
+      }
+
+      return composited_color;
+    }
+    _ => {
+      // Blend modes mix the colours where both are drawn and keep each where only it is
+      let unpremultiply = |channel: f32, alpha: f32| {
+        if alpha > 0.0 { channel / alpha } else { 0.0 }
+      };
+
+      let src_straight_color =
+        [src_red, src_green, src_blue].map(|src_channel| unpremultiply(src_channel, src_alpha));
+
+      let dst_straight_color =
+        [dst_red, dst_green, dst_blue].map(|dst_channel| unpremultiply(dst_channel, dst_alpha));
+
+      let Some(blended_color) = blend(mode, src_straight_color, dst_straight_color) else {
+        unreachable!("Blending with Porter-Duff mode is not allowed");
+      };
+
+      let mut composited_color = [
+        0.0,
+        0.0,
+        0.0,
+        src_alpha.mul_add(-dst_alpha, src_alpha + dst_alpha),
+      ];
+      let both_alpha = src_alpha * dst_alpha;
+
+      for (((channel, src_channel), dst_channel), blended_channel) in composited_color
+        .iter_mut()
+        .zip([src_red, src_green, src_blue])
+        .zip([dst_red, dst_green, dst_blue])
+        .zip(blended_color)
+      {
+        *channel = both_alpha.mul_add(
+          blended_channel,
+          src_channel.mul_add(1.0 - dst_alpha, dst_channel * (1.0 - src_alpha)),
+        );
+      }
+
+      return composited_color;
+    }
+  };
+
+  let mut composited_color = src_color;
+
+  for (channel, dst_channel) in composited_color.iter_mut().zip(dst_color) {
+    *channel = channel.mul_add(src_weight, dst_channel * dst_weight);
+  }
+
+  composited_color
+}
+
+/// Unpacks a colour packed as `0xRRGGBBAA`
+const fn unpack_color(color: u32) -> RgbaColor {
+  RgbaColor {
+    red: (color >> 24_u32) as u8,
+    green: (color >> 16_u32) as u8,
+    blue: (color >> 8_u32) as u8,
+    alpha: color as u8,
+  }
+}
+
+/// Steps of drawing a COLR glyph, empty when the font has no colour for it
+fn record_paint_ops(face: &Face<'_>, glyph_id: u32, foreground_color: RgbaColor) -> Vec<PaintOp> {
+  let mut paint_recorder = PaintRecorder {
+    outline_glyph_id: None,
+    transforms: vec![],
+    ops: vec![],
+  };
+
+  if let Ok(glyph_id) = u16::try_from(glyph_id) {
+    face.paint_color_glyph(GlyphId(glyph_id), 0, foreground_color, &mut paint_recorder);
+  }
+
+  paint_recorder.ops
+}
+
+/// Colour bitmap of a glyph from the CBDT or sbix strike for `font_size`, when it is in a format
+/// that can be decoded
+fn find_raster_image<'font>(
+  face: &'font Face<'_>,
+  glyph_id: u32,
+  font_size: f32,
+) -> Option<RasterGlyphImage<'font>> {
+  let glyph_id = GlyphId(u16::try_from(glyph_id).ok()?);
+
+  face
+    .glyph_raster_image(glyph_id, font_size.ceil() as u16)
+    .filter(|raster_image| {
+      matches!(
+        raster_image.format,
+        RasterImageFormat::PNG | RasterImageFormat::BitmapPremulBgra32
+      )
+    })
+}
+
+/// Decodes a colour bitmap from `find_raster_image` into straight alpha RGBA pixels
+fn decode_raster_image(raster_image: &RasterGlyphImage<'_>) -> Option<RgbaImage> {
+  if raster_image.format == RasterImageFormat::PNG {
+    return image::load_from_memory_with_format(raster_image.data, ImageFormat::Png)
+      .ok()
+      .map(DynamicImage::into_rgba8);
+  }
+
+  let pixels = raster_image
+    .data
+    .chunks_exact(4)
+    .flat_map(|pixel| {
+      let &[blue, green, red, alpha] = pixel else {
+        unreachable!("Pixel of colour bitmap must have 4 channels");
+      };
+
+      [red, green, blue]
+        .map(|channel| {
+          if alpha == 0 {
+            0
+          } else {
+            (u32::from(channel) * 255)
+              .div_euclid(u32::from(alpha))
+              .min(255) as u8
+          }
+        })
+        .into_iter()
+        .chain([alpha])
+    })
+    .collect();
+
+  RgbaImage::from_raw(
+    u32::from(raster_image.width),
+    u32::from(raster_image.height),
+    pixels,
+  )
+}
+
+/// Point in font units of a paint drawn with `inverse_transform` at a raster position
+fn to_paint_point(
+  inverse_transform: Transform2F,
+  font_scale: f32,
+  raster_point: Vector2F,
+) -> Vector2F {
+  let point = inverse_transform * raster_point;
+  Vector2F::new(point.x(), -point.y()) * (1.0 / font_scale)
+}
+
+/// Turns a transform in font units with y going up into one in pixels with y going down
+fn to_raster_transform(transform: Transform, font_scale: f32) -> Transform2F {
+  Transform2F {
+    matrix: Matrix2x2F::row_major(transform.a, -transform.c, -transform.b, transform.d),
+    vector: Vector2F::new(transform.e * font_scale, -transform.f * font_scale),
+  }
+}
+
+fn calc_outline_raster_bounds(
+  font: &Font,
+  glyph_id: GlyphId,
+  font_size: f32,
+  transform: Transform2F,
+) -> RectI {
+  font
+    .raster_bounds(
+      u32::from(glyph_id.0),
+      font_size,
+      transform,
+      HintingOptions::None,
+      RasterizationOptions::GrayscaleAa,
+    )
+    .unwrap_or_default()
+}
+
+/// Pixel bounds of a clip box drawn with `transform`
+fn calc_clip_box_raster_bounds(clip_box: ClipBox, transform: Transform2F) -> RectF {
+  let corners = [
+    Vector2F::new(clip_box.x_min, clip_box.y_min),
+    Vector2F::new(clip_box.x_max, clip_box.y_min),
+    Vector2F::new(clip_box.x_min, clip_box.y_max),
+    Vector2F::new(clip_box.x_max, clip_box.y_max),
+  ];
+
+  corners
+    .into_iter()
+    .map(|corner| transform * Vector2F::new(corner.x(), -corner.y()))
+    .fold(None, |bounds: Option<RectF>, corner| {
+      Some(bounds.map_or_else(
+        || RectF::new(corner, Vector2F::zero()),
+        |bounds| bounds.union_point(corner),
+      ))
+    })
+    .unwrap_or_default()
+}
+
+/// Whether a COLR glyph has paints drawn in the text colour, which then has to be part of its key
+/// - `face`: face to read the colour tables of
+/// - `glyph_id`: glyph to check
+#[must_use]
+pub fn uses_foreground_color(face: &Face<'_>, glyph_id: u32) -> bool {
+  if face.tables().colr.is_none() {
+    return false;
+  }
+
+  let get_colors = |ops: Vec<PaintOp>| {
+    ops
+      .iter()
+      .flat_map(|op| match *op {
+        PaintOp::Fill { ref paint, .. } => paint.get_colors(),
+        _ => vec![],
+      })
+      .collect::<Vec<_>>()
+  };
+
+  let black_colors = get_colors(record_paint_ops(face, glyph_id, unpack_color(0x0000_00FF)));
+
+  let white_colors = get_colors(record_paint_ops(face, glyph_id, unpack_color(0xFFFF_FFFF)));
+
+  black_colors != white_colors
+}
+
+/// Pixel bounds of a colour glyph, or `None` when the glyph has no colour
+/// - `face`: face of `font`, used to read its colour tables
+/// - `font`: font the glyph belongs to
+/// - `glyph_id`: glyph to measure
+/// - `font_size`: pixels per em to measure at
+/// - `foreground_color`: colour of the paints drawn in the text colour, packed as `0xRRGGBBAA`
+#[must_use]
+pub fn raster_bounds(
+  face: &Face<'_>,
+  font: &Font,
+  glyph_id: u32,
+  font_size: f32,
+  foreground_color: u32,
+) -> Option<RectI> {
+  if let Some(raster_image) = find_raster_image(face, glyph_id, font_size) {
+    let image_scale = font_size / f32::from(raster_image.pixels_per_em);
+
+    // Raster image offsets point at the bottom left corner with y going up
+    let min = Vector2F::new(
+      f32::from(raster_image.x),
+      -f32::from(raster_image.y) - f32::from(raster_image.height),
+    ) * image_scale;
+
+    let size = Vector2F::new(
+      f32::from(raster_image.width),
+      f32::from(raster_image.height),
+    ) * image_scale;
+
+    return Some(RectI::new(min.floor().to_i32(), size.ceil().to_i32()));
+  }
+
+  let ops = record_paint_ops(face, glyph_id, unpack_color(foreground_color));
+
+  if !ops.iter().any(|op| matches!(*op, PaintOp::Fill { .. })) {
+    return None;
+  }
+
+  let font_scale = font_size / f32::from(face.units_per_em());
+
+  // Everything is drawn inside the outlines it fills or is clipped to, and inside the clip box of
+  // the whole glyph
+  let outline_bounds = ops
+    .iter()
+    .filter_map(|op| match *op {
+      PaintOp::Fill {
+        glyph_id: Some(glyph_id),
+        transform,
+        ..
+      }
+      | PaintOp::PushClip {
+        glyph_id: Some(glyph_id),
+        transform,
+      } => {
+        let transform = to_raster_transform(transform, font_scale);
+        Some(calc_outline_raster_bounds(font, glyph_id, font_size, transform).to_f32())
+      }
+      _ => None,
+    })
+    .filter(|outline_bounds| outline_bounds.width() > 0.0 && outline_bounds.height() > 0.0)
+    .reduce(RectF::union_rect);
+
+  let clip_box_bounds = match ops.first() {
+    Some(&PaintOp::PushClipBox {
+      clip_box,
+      transform,
+    }) => {
+      let raster_transform = to_raster_transform(transform, font_scale);
+      let clip_box = ClipBox {
+        x_min: clip_box.x_min * font_scale,
+        y_min: clip_box.y_min * font_scale,
+        x_max: clip_box.x_max * font_scale,
+        y_max: clip_box.y_max * font_scale,
+      };
+
+      Some(calc_clip_box_raster_bounds(clip_box, raster_transform))
+    }
+    _ => None,
+  };
+
+  let bounds = match (outline_bounds, clip_box_bounds) {
+    (Some(outline_bounds), Some(clip_box_bounds)) => outline_bounds.intersection(clip_box_bounds),
+    (Some(bounds), None) | (None, Some(bounds)) => Some(bounds),
+    (None, None) => None,
+  }?;
+
+  Some(bounds.round_out().to_i32())
+    .filter(|bounds| bounds.width() > 0_i32 && bounds.height() > 0_i32)
+}
+
+/// Rasterizes a colour glyph into straight alpha RGBA pixels
+///
+/// Colour bitmaps are read from PNG and BGRA strikes, and COLR glyphs are drawn with their solid
+/// and gradient paints, glyph and box clips, and composite modes.
+/// - `face`: face of `font`, used to read its colour tables
+/// - `font`: font the glyph belongs to
+/// - `glyph_id`: glyph to rasterize
+/// - `font_size`: pixels per em to rasterize at
+/// - `foreground_color`: colour of the paints drawn in the text colour, packed as `0xRRGGBBAA`
+/// - `bounds`: pixel bounds from `raster_bounds`
+/// - `margin`: empty pixels around the glyph
+#[must_use]
+pub fn rasterize(
+  face: &Face<'_>,
+  font: &Font,
+  glyph_id: u32,
+  font_size: f32,
+  foreground_color: u32,
+  bounds: RectI,
+  margin: i32,
+) -> Vec<u8> {
+  if let Some(raster_image) = find_raster_image(face, glyph_id, font_size) {
+    let canvas_size = bounds.size() + Vector2I::splat(margin << 1_i32);
+    let canvas_width = canvas_size.x() as usize;
+    let mut pixels = vec![0; canvas_width * canvas_size.y() as usize * 4];
+
+    let Some(image) = decode_raster_image(&raster_image) else {
+      eprintln!("Failed to decode colour glyph {glyph_id}");
+      return pixels;
+    };
+
+    let image = imageops::resize(
+      &image,
+      bounds.width() as u32,
+      bounds.height() as u32,
+      FilterType::Triangle,
+    );
+
+    let row_len = bounds.width() as usize * 4;
+
+    for (y, row) in image.chunks_exact(row_len).enumerate() {
+      let offset = ((y + margin as usize) * canvas_width + margin as usize) * 4;
+      pixels[offset..offset + row_len].copy_from_slice(row);
+    }
+
+    return pixels;
+  }
+
+  let font_scale = font_size / f32::from(face.units_per_em());
+  let mut canvas = ColorCanvas::new(font, font_size, font_scale, bounds, margin);
+
+  for op in record_paint_ops(face, glyph_id, unpack_color(foreground_color)) {
+    canvas.draw(&op);
+  }
+
+  canvas.into_pixels()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{ColorLine, GradientShape, composite, unpack_color};
+  use pathfinder_geometry::vector::Vector2F;
+  use rustybuzz::ttf_parser::{
+    RgbaColor,
+    colr::{ColorStop, CompositeMode, GradientExtend},
+  };
+
+  const RED: RgbaColor = unpack_color(0xFF00_00FF);
+  const BLUE: RgbaColor = unpack_color(0x0000_FFFF);
+
+  fn assert_color_eq(color: [f32; 4], expected_color: [f32; 4]) {
+    for (channel, expected_channel) in color.into_iter().zip(expected_color) {
+      assert!(
+        (channel - expected_channel).abs() < 1e-4,
+        "{color:?} != {expected_color:?}"
+      );
+    }
+  }
+
+  fn new_color_line(extend: GradientExtend) -> ColorLine {
+    ColorLine::new(
+      [
+        ColorStop {
+          stop_offset: 1.0,
+          color: BLUE,
+        },
+        ColorStop {
+          stop_offset: 0.0,
+          color: RED,
+        },
+      ]
+      .into_iter(),
+      extend,
+    )
+  }
+
+  #[test]
+  fn color_line_interpolates_sorted_stops() {
+    let color_line = new_color_line(GradientExtend::Pad);
+    assert_color_eq(color_line.calc_color(0.0), [1.0, 0.0, 0.0, 1.0]);
+    assert_color_eq(color_line.calc_color(0.25), [0.75, 0.0, 0.25, 1.0]);
+    assert_color_eq(color_line.calc_color(1.0), [0.0, 0.0, 1.0, 1.0]);
+  }
+
+  #[test]
+  fn color_line_extends_past_stops() {
+    let pad_color_line = new_color_line(GradientExtend::Pad);
+    assert_color_eq(pad_color_line.calc_color(-1.0), [1.0, 0.0, 0.0, 1.0]);
+    assert_color_eq(pad_color_line.calc_color(1.5), [0.0, 0.0, 1.0, 1.0]);
+
+    let repeat_color_line = new_color_line(GradientExtend::Repeat);
+    assert_color_eq(repeat_color_line.calc_color(1.25), [0.75, 0.0, 0.25, 1.0]);
+
+    let reflect_color_line = new_color_line(GradientExtend::Reflect);
+    assert_color_eq(reflect_color_line.calc_color(1.25), [0.25, 0.0, 0.75, 1.0]);
+  }
+
+  #[test]
+  fn color_line_interpolates_premultiplied() {
+    let color_line = ColorLine::new(
+      [
+        ColorStop {
+          stop_offset: 0.0,
+          color: RED,
+        },
+        ColorStop {
+          stop_offset: 1.0,
+          color: unpack_color(0x0000_FF00),
+        },
+      ]
+      .into_iter(),
+      GradientExtend::Pad,
+    );
+
+    assert_color_eq(color_line.calc_color(0.5), [0.5, 0.0, 0.0, 0.5]);
+  }
+
+  #[test]
+  fn linear_gradient_offset_follows_rotation() {
+    let shape = GradientShape::new_linear(
+      Vector2F::new(0.0, 0.0),
+      Vector2F::new(10.0, 10.0),
+      Vector2F::new(0.0, 10.0),
+    );
+
+    assert_eq!(shape.calc_offset(Vector2F::new(5.0, 0.0)), Some(0.5));
+    assert_eq!(shape.calc_offset(Vector2F::new(5.0, 7.0)), Some(0.5));
+  }
+
+  #[test]
+  fn radial_gradient_offset_grows_with_radius() {
+    let shape = GradientShape::Radial {
+      start_center: Vector2F::new(0.0, 0.0),
+      start_radius: 0.0,
+      end_center: Vector2F::new(0.0, 0.0),
+      end_radius: 10.0,
+    };
+
+    let offset = shape.calc_offset(Vector2F::new(0.0, 5.0)).unwrap();
+    assert!((offset - 0.5).abs() < 1e-4);
+    let offset = shape.calc_offset(Vector2F::new(20.0, 0.0)).unwrap();
+    assert!((offset - 2.0).abs() < 1e-4);
+  }
+
+  #[test]
+  fn sweep_gradient_offset_turns_counter_clockwise() {
+    let shape = GradientShape::Sweep {
+      center: Vector2F::new(0.0, 0.0),
+      start_angle: 0.0,
+      end_angle: 180.0,
+    };
+
+    let offset = shape.calc_offset(Vector2F::new(0.0, 1.0)).unwrap();
+    assert!((offset - 0.5).abs() < 1e-4);
+    let offset = shape.calc_offset(Vector2F::new(0.0, -1.0)).unwrap();
+    assert!((offset - 1.5).abs() < 1e-4);
+  }
+
+  #[test]
+  fn composite_porter_duff_modes() {
+    let src_color = [0.5, 0.0, 0.0, 0.5];
+    let dst_color = [0.0, 0.0, 1.0, 1.0];
+
+    assert_color_eq(
+      composite(CompositeMode::SourceOver, src_color, dst_color),
+      [0.5, 0.0, 0.5, 1.0],
+    );
+
+    assert_color_eq(
+      composite(CompositeMode::DestinationIn, src_color, dst_color),
+      [0.0, 0.0, 0.5, 0.5],
+    );
+
+    assert_color_eq(
+      composite(CompositeMode::Clear, src_color, dst_color),
+      [0.0; 4],
+    );
+
+    assert_color_eq(
+      composite(CompositeMode::Plus, src_color, dst_color),
+      [0.5, 0.0, 1.0, 1.0],
+    );
+  }
+
+  #[test]
+  fn composite_blend_modes() {
+    let src_color = [1.0, 0.5, 0.0, 1.0];
+    let dst_color = [0.5, 0.5, 0.5, 1.0];
+
+    assert_color_eq(
+      composite(CompositeMode::Multiply, src_color, dst_color),
+      [0.5, 0.25, 0.0, 1.0],
+    );
+
+    assert_color_eq(
+      composite(CompositeMode::Screen, src_color, dst_color),
+      [1.0, 0.75, 0.5, 1.0],
+    );
+
+    assert_color_eq(
+      composite(CompositeMode::Luminosity, dst_color, [0.2, 0.2, 0.2, 1.0]),
+      dst_color,
+    );
+
+    let color = composite(CompositeMode::Color, [0.0, 0.0, 0.0, 0.0], dst_color);
+    assert_color_eq(color, dst_color);
+  }
+}
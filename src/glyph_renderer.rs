@@ -1,73 +1,360 @@
 use crate::{
-  collections::lru_cache::LruCache,
-  consts,
+  atlas_allocator::AtlasAllocator,
+  atlas_cache::{self, CachedGlyph, CachedGlyphKey},
+  color_glyph, consts,
   model_sync::ModelSync,
   models::{
-    align::Align, font_key::FontKey, glyph::Glyph, glyph_key::GlyphKey, icon::Icon, text::Text,
+    align::Align, char_index::CharIndex, font_key::FontKey, font_metrics::FontMetrics,
+    glyph::Glyph, glyph_key::GlyphKey, glyph_mode::GlyphMode, icon::Icon, icon_glyph::IconGlyph,
+    overflow::Overflow, rect::Rect, rich_text::RichText, text::Text, text_metrics::TextMetrics,
+    text_span::TextSpan, vertical_anchor::VerticalAnchor,
   },
   sampled_image::SampledImage,
+  sdf,
   storage_buffer::StorageBuffer,
+  text_layout::{self, PlacedText},
 };
 use ash::vk;
-use etagere::{AllocId, BucketedAtlasAllocator, Size};
+use etagere::{AllocId, Allocation, Size};
 use font_kit::{
   canvas::{Canvas, Format, RasterizationOptions},
+  family_name::FamilyName,
   font::Font,
+  handle::Handle,
   hinting::HintingOptions,
+  properties::Properties,
   source::SystemSource,
 };
 use pathfinder_geometry::{
+  rect::RectI,
   transform2d::Transform2F,
   vector::{Vector2F, Vector2I},
 };
 use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet};
-use std::collections::VecDeque;
+use rustybuzz::{
+  Direction, GlyphBuffer, UnicodeBuffer,
+  ttf_parser::{GlyphId, cmap::Subtable},
+};
+use std::{
+  borrow::Cow, collections::VecDeque, hash::BuildHasher as _, io, mem, ops::Range, path::PathBuf,
+  slice, sync::Arc,
+};
+use unicode_bidi::{BidiInfo, Level};
 
 // Settings
 const GLYPH_MARGIN: i32 = 1;
 const RESOLUTION_SCALE: f32 = 2.0;
 
+/// Font size every glyph is rasterized at in `GlyphMode::Sdf`
+const SDF_FONT_SIZE: f32 = 48.0;
+
+/// Distance in atlas pixels from a glyph edge to where its distance field saturates
+const SDF_SPREAD: i32 = 6;
+
+/// Atlas page of glyphs filled without sampling the atlas, such as text decorations
+const SOLID_ATLAS_PAGE: u32 = u32::MAX;
+
+/// Bytes per pixel of the colour glyph atlas
+const COLOR_GLYPH_PIXEL_SIZE: usize = 4;
+
+/// Most bytes the pages of one glyph atlas may take, counting the copy of them each frame in
+/// flight reads from and their staging buffer
+const MAX_GLYPH_ATLAS_BYTES: u64 = 64 << 20;
+
+// Glyph flags
+const GLYPH_FLAG_SDF: u32 = 1;
+const GLYPH_FLAG_COLOR: u32 = 2;
+
+/// Char that stands in for the chars cut from text that overflows with an ellipsis
+const ELLIPSIS: char = '\u{2026}';
+
+/// Char that stands in for icons whose name is not found. As a noncharacter no font maps it, so it
+/// is drawn with the missing glyph of the icon font
+const MISSING_ICON_CHAR: char = '\u{FFFF}';
+
+/// Steps per atlas pixel that outline widths and shadow blurs are rounded to, so that nearby sizes
+/// share glyphs
+const EFFECT_RADIUS_STEPS: f32 = 4.0;
+
+// Depth offsets that put outlines and shadows behind the glyphs they belong to
+const OUTLINE_Z_OFFSET: f32 = 0.00002;
+const SHADOW_Z_OFFSET: f32 = 0.00004;
+
 #[derive(Clone, Copy)]
 enum GlyphMetrics {
   Visible {
     position: (i32, i32),
     size: (u32, u32),
     bearing: (f32, f32),
-    advance: (f32, f32),
+    page: u32,
     alloc_id: AllocId,
+    color: bool,
     ref_count: u32,
   },
   Invisible {
-    advance: (f32, f32),
     ref_count: u32,
   },
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+struct CachedFont {
+  /// Face parsed once from the font data for shaping and colour tables, which borrows it for as
+  /// long as the font stays cached
+  face: rustybuzz::Face<'static>,
+  font: Font,
+  /// Bytes of the font file the face borrows, shared with the font where its loader allows it
+  _font_data: Arc<dyn AsRef<[u8]>>,
+  font_index: u32,
+  /// Hash of the font file, which tells glyphs of an edited font file apart in the atlas cache
+  font_hash: u64,
+}
+
+impl CachedFont {
+  fn new(font: Font) -> Self {
+    let font_data: Arc<dyn AsRef<[u8]>> = font.copy_font_data().unwrap();
+    let font_bytes = (*font_data).as_ref();
+
+    let font_index = match font.handle() {
+      Some(Handle::Path { font_index, .. } | Handle::Memory { font_index, .. }) => font_index,
+      None => 0,
+    };
+
+    let font_hash = FxBuildHasher.hash_one(font_bytes);
+
+    // The font data stays where it is on the heap until the face borrowing it is dropped first
+    let face_bytes = unsafe { slice::from_raw_parts(font_bytes.as_ptr(), font_bytes.len()) };
+    let face = rustybuzz::Face::from_slice(face_bytes, font_index).unwrap();
+
+    Self {
+      face,
+      font,
+      _font_data: font_data,
+      font_index,
+      font_hash,
+    }
+  }
+
+  /// Face of the font, which may not outlive it
+  #[inline]
+  const fn get_face(&self) -> &rustybuzz::Face<'_> {
+    &self.face
+  }
+}
+
+/// Handle of a live text, which stays the same while its glyphs get laid out again
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TextId {
+  id: u32,
+}
+
+struct ShapedText {
+  glyphs: Box<[Glyph]>,
+  glyph_keys: Box<[GlyphKey]>,
+  metrics: TextMetrics,
+  /// Whether each char is laid out right to left
+  rtl_chars: Box<[bool]>,
+}
+
+struct TextEntry {
+  text: RichText,
   glyph_ids: Box<[u32]>,
   glyph_keys: Box<[GlyphKey]>,
   clipped: bool,
+  glyphs: Box<[Glyph]>,
+  metrics: TextMetrics,
+  rtl_chars: Box<[bool]>,
 }
 
+#[derive(Clone, Copy)]
 pub struct IconId {
-  glyph_id: u32,
-  glyph_key: GlyphKey,
-  clipped: bool,
+  text_id: TextId,
 }
 
 pub struct GlyphRenderer {
   glyph_atlas: SampledImage,
+  color_glyph_atlas: SampledImage,
   glyph_sync: ModelSync<Glyph>,
   clipped_glyph_sync: ModelSync<Glyph>,
   font_source: SystemSource,
-  font_cache: FxHashMap<FontKey, Font>,
-  glyph_allocator: BucketedAtlasAllocator,
+  font_cache: FxHashMap<FontKey, CachedFont>,
+  /// Installed fonts, listed once a char is first missing from the fonts of a text
+  system_font_handles: Option<Box<[Handle]>>,
+  /// Installed fonts loaded so far for chars missing from the fonts of a text, in the order found
+  system_fallback_font_keys: Vec<FontKey>,
+  /// Installed font found for each char missing from the fonts of a text, or `None` when no
+  /// installed font has it
+  char_fallback_font_keys: FxHashMap<char, Option<FontKey>>,
+  icon_name_chars: FxHashMap<(FontKey, Cow<'static, str>), char>,
+  glyph_atlas_size: (u16, u16),
+  glyph_allocator: AtlasAllocator<GlyphKey>,
+  color_glyph_allocator: AtlasAllocator<GlyphKey>,
   glyph_metrics_cache: FxHashMap<GlyphKey, GlyphMetrics>,
-  unused_glyph_metrics_cache: LruCache<GlyphKey, GlyphMetrics>,
-  text_sizes: FxHashMap<TextId, (f32, f32)>,
+  texts: FxHashMap<TextId, TextEntry>,
+  next_text_id: u32,
   changeset_queue: VecDeque<FxHashSet<GlyphKey>>,
   window_scale_factor: f32,
+  glyph_mode: GlyphMode,
+  /// File glyphs are cached in across launches, once it is loaded
+  atlas_cache_path: Option<PathBuf>,
+  /// Glyphs loaded from the atlas cache that have not been used yet
+  loaded_cached_glyphs: FxHashMap<CachedGlyphKey, CachedGlyph>,
+  /// Glyphs in the glyph metrics cache that were used since the atlas cache was loaded, which get
+  /// written back to it
+  used_cached_glyphs: FxHashMap<CachedGlyphKey, CachedGlyph>,
+}
+
+/// Crops a glyph to the horizontal range from the start to the end of `clip_range`, or `None` when
+/// nothing of it is left
+fn clip_glyph(glyph: Glyph, clip_range: Option<(f32, f32)>) -> Option<Glyph> {
+  let Some((clip_left, clip_right)) = clip_range else {
+    return Some(glyph);
+  };
+
+  let (glyph_x, glyph_y, glyph_z) = glyph.position;
+  let (glyph_width, glyph_height) = glyph.size;
+  let (atlas_x, atlas_y) = glyph.atlas_position;
+  let (atlas_width, atlas_height) = glyph.atlas_size;
+  let clipped_left = glyph_x.max(clip_left);
+  let clipped_right = (glyph_x + glyph_width).min(clip_right);
+
+  if clipped_right <= clipped_left {
+    return None;
+  }
+
+  let atlas_scale = atlas_width / glyph_width;
+
+  Some(Glyph {
+    position: (clipped_left, glyph_y, glyph_z),
+    size: (clipped_right - clipped_left, glyph_height),
+    atlas_position: (
+      (clipped_left - glyph_x).mul_add(atlas_scale, atlas_x),
+      atlas_y,
+    ),
+    atlas_size: ((clipped_right - clipped_left) * atlas_scale, atlas_height),
+    ..glyph
+  })
+}
+
+/// Moves a glyph by `offset`
+fn offset_glyph(glyph: Glyph, offset: (f32, f32, f32)) -> Glyph {
+  let (glyph_x, glyph_y, glyph_z) = glyph.position;
+  let (offset_x, offset_y, offset_z) = offset;
+
+  Glyph {
+    position: (glyph_x + offset_x, glyph_y + offset_y, glyph_z + offset_z),
+    ..glyph
+  }
+}
+
+/// Moves a rect laid out relative to a text to the position of the text
+fn offset_rect(rect: Rect, text_position: (f32, f32, f32)) -> Rect {
+  let (rect_x, rect_y) = rect.position;
+  let (text_x, text_y, _text_z) = text_position;
+
+  Rect {
+    position: (text_x + rect_x, text_y + rect_y),
+    ..rect
+  }
+}
+
+/// Most pages a glyph atlas may grow to, so that a copy of them for each frame in flight fits in
+/// the image array layer limit and the atlas stays within `MAX_GLYPH_ATLAS_BYTES`
+/// - `max_image_array_layers`: image array layer limit of the device
+/// - `page_bytes`: bytes of one page
+fn calc_max_glyph_atlas_page_count(max_image_array_layers: u32, page_bytes: u64) -> u32 {
+  let frame_count = consts::MAX_IN_FLIGHT_FRAME_COUNT as u32;
+  let max_layer_page_count = max_image_array_layers.div_euclid(frame_count);
+
+  // Every page is in the image and in the staging buffer once per frame in flight
+  let max_memory_page_count = MAX_GLYPH_ATLAS_BYTES
+    .checked_div(page_bytes * u64::from(frame_count) * 2)
+    .map_or(u32::MAX, |page_count| {
+      u32::try_from(page_count).unwrap_or(u32::MAX)
+    });
+
+  // The first page is always there
+  max_layer_page_count.min(max_memory_page_count).max(1)
+}
+
+/// Rounds an outline width or shadow blur in atlas pixels to one of `EFFECT_RADIUS_STEPS`
+fn quantize_effect_radius(radius: f32) -> f32 {
+  (radius * EFFECT_RADIUS_STEPS).round().max(0.0) / EFFECT_RADIUS_STEPS
+}
+
+/// Hash of the settings every glyph is rasterized with, atlas caches written with other settings
+/// are ignored
+fn calc_atlas_cache_settings_hash() -> u64 {
+  FxBuildHasher.hash_one((
+    RESOLUTION_SCALE.to_bits(),
+    GLYPH_MARGIN,
+    SDF_FONT_SIZE.to_bits(),
+    SDF_SPREAD,
+  ))
+}
+
+/// Char mapped to the glyph of a named icon, through the ligatures of the font or its glyph names
+/// - `face`: face of the icon font
+/// - `name`: name of the icon
+fn find_icon_char(face: &rustybuzz::Face<'_>, name: &str) -> Option<char> {
+  let mut unicode_buffer = UnicodeBuffer::new();
+  unicode_buffer.push_str(name);
+  unicode_buffer.guess_segment_properties();
+  let glyph_buffer = rustybuzz::shape(face, &[], unicode_buffer);
+
+  // A name that does not form a ligature may still be a glyph name
+  let glyph_id = match *glyph_buffer.glyph_infos() {
+    [ref glyph_info] if glyph_info.glyph_id != 0 => {
+      GlyphId(u16::try_from(glyph_info.glyph_id).ok()?)
+    }
+    _ => face.glyph_index_by_name(name)?,
+  };
+
+  face
+    .tables()
+    .cmap?
+    .subtables
+    .into_iter()
+    .filter(Subtable::is_unicode)
+    .find_map(|subtable| {
+      let mut ch = None;
+
+      subtable.codepoints(|codepoint| {
+        if ch.is_none() && subtable.glyph_index(codepoint) == Some(glyph_id) {
+          ch = char::from_u32(codepoint);
+        }
+      });
+
+      ch
+    })
+}
+
+/// Shapes a run of text in one font and direction, with its glyphs in visual order
+/// - `face`: face of the font the run is in
+/// - `text`: text of the run
+/// - `rtl`: whether the run goes right to left
+fn shape_run(face: &rustybuzz::Face<'_>, text: &str, rtl: bool) -> GlyphBuffer {
+  let mut unicode_buffer = UnicodeBuffer::new();
+  unicode_buffer.push_str(text);
+  unicode_buffer.guess_segment_properties();
+
+  unicode_buffer.set_direction(if rtl {
+    Direction::RightToLeft
+  } else {
+    Direction::LeftToRight
+  });
+
+  rustybuzz::shape(face, &[], unicode_buffer)
+}
+
+fn is_same_glyph(glyph: &Glyph, other_glyph: &Glyph) -> bool {
+  glyph.position == other_glyph.position
+    && glyph.color == other_glyph.color
+    && glyph.size == other_glyph.size
+    && glyph.atlas_position == other_glyph.atlas_position
+    && glyph.atlas_size == other_glyph.atlas_size
+    && glyph.paint_origin == other_glyph.paint_origin
+    && glyph.atlas_page == other_glyph.atlas_page
+    && glyph.flags == other_glyph.flags
+    && glyph.paint_id == other_glyph.paint_id
 }
 
 impl GlyphRenderer {
@@ -81,46 +368,81 @@ impl GlyphRenderer {
     glyph_capacity: usize,
     clipped_glyph_capacity: usize,
     glyph_atlas_size: (u16, u16),
-  ) -> (Self, vk::CommandBuffer) {
+    max_image_array_layers: u32,
+    glyph_mode: GlyphMode,
+  ) -> (Self, [vk::CommandBuffer; 2]) {
     let (glyph_atlas_width, glyph_atlas_height) = glyph_atlas_size;
+    let glyph_atlas_page_bytes = u64::from(glyph_atlas_width) * u64::from(glyph_atlas_height);
     let glyph_metrics_cache_capacity = (((glyph_atlas_width as usize * glyph_atlas_height as usize)
       >> 10_usize) as f32
       / RESOLUTION_SCALE
       / RESOLUTION_SCALE) as usize;
 
+    let glyph_atlas_extent = vk::Extent2D {
+      width: u32::from(glyph_atlas_width),
+      height: u32::from(glyph_atlas_height),
+    };
+
     let (glyph_atlas, transfer_command_buffer) = SampledImage::new(
       vk_device,
       vk_allocator,
       graphics_queue_family_index,
       transfer_queue_family_index,
       glyph_atlas_width as usize * glyph_atlas_height as usize,
-      vk::Extent2D {
-        width: u32::from(glyph_atlas_width),
-        height: u32::from(glyph_atlas_height),
-      },
+      glyph_atlas_extent,
+      1,
+      vk::Format::R8_UNORM,
+    );
+
+    let (color_glyph_atlas, color_transfer_command_buffer) = SampledImage::new(
+      vk_device,
+      vk_allocator,
+      graphics_queue_family_index,
+      transfer_queue_family_index,
+      glyph_atlas_width as usize * glyph_atlas_height as usize * COLOR_GLYPH_PIXEL_SIZE,
+      glyph_atlas_extent,
+      1,
+      vk::Format::R8G8B8A8_UNORM,
     );
 
     (
       Self {
         glyph_atlas,
+        color_glyph_atlas,
         glyph_sync: ModelSync::new(glyph_capacity),
         clipped_glyph_sync: ModelSync::new(clipped_glyph_capacity),
         font_source: SystemSource::new(),
         font_cache: FxHashMap::default(),
-        glyph_allocator: BucketedAtlasAllocator::new(Size::new(
-          glyph_atlas_width.into(),
-          glyph_atlas_height.into(),
-        )),
+        system_font_handles: None,
+        system_fallback_font_keys: vec![],
+        char_fallback_font_keys: FxHashMap::default(),
+        icon_name_chars: FxHashMap::default(),
+        glyph_atlas_size,
+        glyph_allocator: AtlasAllocator::new(
+          glyph_atlas_size,
+          calc_max_glyph_atlas_page_count(max_image_array_layers, glyph_atlas_page_bytes),
+        ),
+        color_glyph_allocator: AtlasAllocator::new(
+          glyph_atlas_size,
+          calc_max_glyph_atlas_page_count(
+            max_image_array_layers,
+            glyph_atlas_page_bytes * COLOR_GLYPH_PIXEL_SIZE as u64,
+          ),
+        ),
         glyph_metrics_cache: FxHashMap::with_capacity_and_hasher(
           glyph_metrics_cache_capacity,
           FxBuildHasher,
         ),
-        unused_glyph_metrics_cache: LruCache::with_capacity(glyph_metrics_cache_capacity),
-        text_sizes: FxHashMap::default(),
+        texts: FxHashMap::default(),
+        next_text_id: 0,
         changeset_queue: VecDeque::from_iter([FxHashSet::default()]),
         window_scale_factor,
+        glyph_mode,
+        atlas_cache_path: None,
+        loaded_cached_glyphs: FxHashMap::default(),
+        used_cached_glyphs: FxHashMap::default(),
       },
-      transfer_command_buffer,
+      [transfer_command_buffer, color_transfer_command_buffer],
     )
   }
 
@@ -134,6 +456,27 @@ impl GlyphRenderer {
     self.clipped_glyph_sync.get_model_count()
   }
 
+  #[inline]
+  pub(super) fn get_opaque_glyph_count(&self) -> usize {
+    self.glyph_sync.get_opaque_model_count()
+  }
+
+  #[inline]
+  pub(super) fn get_opaque_clipped_glyph_count(&self) -> usize {
+    self.clipped_glyph_sync.get_opaque_model_count()
+  }
+
+  /// Sort keys of the translucent glyphs, in the order they are drawn
+  pub(super) fn get_translucent_glyph_sort_keys(&self, clipped: bool) -> Vec<f32> {
+    let glyph_sync = if clipped {
+      &self.clipped_glyph_sync
+    } else {
+      &self.glyph_sync
+    };
+
+    glyph_sync.get_sort_keys(glyph_sync.get_opaque_model_count())
+  }
+
   #[inline]
   const fn get_glyph_sync(&mut self, clipped: bool) -> &mut ModelSync<Glyph> {
     if clipped {
@@ -143,59 +486,237 @@ impl GlyphRenderer {
     }
   }
 
+  /// Font size glyphs of text at `font_size` get cached and rasterized at
+  const fn calc_glyph_font_size(&self, font_size: f32) -> f32 {
+    match self.glyph_mode {
+      GlyphMode::Coverage => font_size,
+      GlyphMode::Sdf => SDF_FONT_SIZE,
+    }
+  }
+
+  /// Atlas pixels per unit of glyph font size
+  const fn calc_raster_scale(&self) -> f32 {
+    match self.glyph_mode {
+      GlyphMode::Coverage => self.window_scale_factor * RESOLUTION_SCALE,
+      GlyphMode::Sdf => 1.0,
+    }
+  }
+
+  const fn get_hinting_options(&self, glyph_font_size: f32) -> HintingOptions {
+    match self.glyph_mode {
+      GlyphMode::Coverage => HintingOptions::Vertical(glyph_font_size * self.window_scale_factor),
+      GlyphMode::Sdf => HintingOptions::None,
+    }
+  }
+
+  /// Key of a glyph in the atlas cache, which outlives the font keys and scale factor of a launch
+  fn to_cached_glyph_key(&self, glyph_key: &GlyphKey) -> CachedGlyphKey {
+    let cached_font = &self.font_cache[&glyph_key.font_key];
+
+    // Distance fields are rasterized the same way at every scale factor
+    let scale_factor = match self.glyph_mode {
+      GlyphMode::Coverage => self.window_scale_factor,
+      GlyphMode::Sdf => 0.0,
+    };
+
+    CachedGlyphKey {
+      font_hash: cached_font.font_hash,
+      font_index: cached_font.font_index,
+      glyph_id: glyph_key.glyph_id,
+      font_size: glyph_key.font_size.to_bits(),
+      dilation: glyph_key.dilation.to_bits(),
+      blur_radius: glyph_key.blur_radius.to_bits(),
+      foreground_color: glyph_key.foreground_color,
+      scale_factor: scale_factor.to_bits(),
+      sdf: self.glyph_mode == GlyphMode::Sdf,
+    }
+  }
+
+  /// Glyph from the atlas cache, which counts as used from now on
+  fn use_cached_glyph(&mut self, cached_glyph_key: &CachedGlyphKey) -> Option<&CachedGlyph> {
+    if let Some(cached_glyph) = self.loaded_cached_glyphs.remove(cached_glyph_key) {
+      self
+        .used_cached_glyphs
+        .insert(*cached_glyph_key, cached_glyph);
+    }
+
+    self.used_cached_glyphs.get(cached_glyph_key)
+  }
+
+  /// Allocator of the colour or coverage glyph atlas, glyphs without ink count as coverage glyphs
+  #[inline]
+  const fn get_glyph_allocator(&mut self, color: bool) -> &mut AtlasAllocator<GlyphKey> {
+    if color {
+      &mut self.color_glyph_allocator
+    } else {
+      &mut self.glyph_allocator
+    }
+  }
+
   #[inline]
-  pub(super) const fn get_glyph_atlas(&mut self) -> &mut SampledImage {
-    &mut self.glyph_atlas
+  const fn get_glyph_atlas_mut(&mut self, color: bool) -> &mut SampledImage {
+    if color {
+      &mut self.color_glyph_atlas
+    } else {
+      &mut self.glyph_atlas
+    }
+  }
+
+  /// Coverage and colour glyph atlases, in the order of their descriptor bindings
+  #[inline]
+  pub(super) const fn get_glyph_atlases(&self) -> [&SampledImage; 2] {
+    [&self.glyph_atlas, &self.color_glyph_atlas]
+  }
+
+  #[inline]
+  pub(super) const fn get_glyph_atlas_read_index(&self) -> usize {
+    self.glyph_atlas.get_read_index()
   }
 
+  #[inline]
+  pub(super) const fn done_glyph_atlas_write(&mut self) {
+    self.glyph_atlas.done_write();
+    self.color_glyph_atlas.done_write();
+  }
+
+  /// Uploads new glyphs and models, and returns whether the glyph atlas got replaced by a bigger
+  /// one
   pub(super) fn sync_to(
     &mut self,
     model_buffer: &StorageBuffer,
     vk_device: &ash::Device,
+    vk_allocator: &vk_mem::Allocator,
     model_buffer_offset: usize,
     clipped_model_buffer_offset: usize,
     graphics_queue_family_index: u32,
     transfer_queue_family_index: u32,
-  ) -> Box<[vk::CommandBuffer]> {
-    let all_changeset = self
-      .changeset_queue
-      .iter()
-      .flatten()
-      .cloned()
-      .collect::<Vec<_>>();
-
+  ) -> (Box<[vk::CommandBuffer]>, bool) {
     let mut transfer_command_buffers = vec![];
+    let mut glyph_atlas_grown = false;
+
+    for color in [false, true] {
+      let page_count = self.get_glyph_allocator(color).get_page_count();
+
+      if page_count <= self.get_glyph_atlas_mut(color).get_layer_count() {
+        continue;
+      }
+
+      let (glyph_atlas_width, glyph_atlas_height) = self.glyph_atlas_size;
 
-    if !all_changeset.is_empty() {
-      let mut regions = Vec::with_capacity(all_changeset.len());
-      let mut pixels = Vec::with_capacity(
-        ((all_changeset.len() << 10) as f32 * RESOLUTION_SCALE * RESOLUTION_SCALE) as usize,
+      let (pixel_size, format) = if color {
+        (COLOR_GLYPH_PIXEL_SIZE, vk::Format::R8G8B8A8_UNORM)
+      } else {
+        (1, vk::Format::R8_UNORM)
+      };
+
+      let (mut glyph_atlas, transfer_command_buffer) = SampledImage::new(
+        vk_device,
+        vk_allocator,
+        graphics_queue_family_index,
+        transfer_queue_family_index,
+        glyph_atlas_width as usize * glyph_atlas_height as usize * page_count as usize * pixel_size,
+        vk::Extent2D {
+          width: u32::from(glyph_atlas_width),
+          height: u32::from(glyph_atlas_height),
+        },
+        page_count,
+        format,
       );
 
-      for glyph_key in all_changeset {
-        let GlyphKey {
-          ref font_key,
-          ch,
-          font_size,
-        } = glyph_key;
+      // The old atlas may still be read by frames in flight
+      unsafe {
+        vk_device.device_wait_idle().unwrap();
+      }
 
-        let font = &self.font_cache[font_key];
+      // Both atlases share descriptor sets, so they have to stay on the same frame
+      let old_glyph_atlas = self.get_glyph_atlas_mut(color);
+      glyph_atlas.set_read_index(old_glyph_atlas.get_read_index());
+      mem::replace(old_glyph_atlas, glyph_atlas).drop(vk_device, vk_allocator);
+      transfer_command_buffers.push(transfer_command_buffer);
+      glyph_atlas_grown = true;
+
+      // The new atlas starts empty, so every glyph cached in it has to be rasterized again
+      let changeset = self.changeset_queue.back_mut().unwrap();
+
+      changeset.extend(
+        self
+          .glyph_metrics_cache
+          .iter()
+          .filter(|&(_glyph_key, glyph_metrics)| {
+            matches!(
+              *glyph_metrics,
+              GlyphMetrics::Visible { color: glyph_color, .. } if glyph_color == color
+            )
+          })
+          .map(|(glyph_key, _glyph_metrics)| glyph_key.clone()),
+      );
+    }
 
-        let glyph_id = font.glyph_for_char(ch).unwrap_or_else(|| {
-          eprintln!("Failed to find glyph for char '{ch}'");
-          font.glyph_for_char('?').unwrap()
-        });
+    let all_changeset = self
+      .changeset_queue
+      .iter()
+      .flatten()
+      .cloned()
+      .collect::<Vec<_>>();
 
-        let GlyphMetrics::Visible {
-          position: (glyph_x, glyph_y),
-          size: (glyph_width, glyph_height),
-          bearing: (bearing_x, bearing_y),
-          ..
-        } = self.glyph_metrics_cache[&glyph_key]
-        else {
-          unreachable!("Rasterizing invisible glyph is not allowed");
-        };
+    // Coverage and colour glyphs are uploaded to their own atlases
+    let mut regions = [vec![], vec![]];
+    let mut pixels = [vec![], vec![]];
+    let raster_scale = self.calc_raster_scale();
+
+    for glyph_key in all_changeset {
+      let GlyphKey {
+        ref font_key,
+        glyph_id,
+        font_size,
+        dilation,
+        blur_radius,
+        foreground_color,
+      } = glyph_key;
+
+      let cached_font = &self.font_cache[font_key];
+
+      let GlyphMetrics::Visible {
+        position: (glyph_x, glyph_y),
+        size: (glyph_width, glyph_height),
+        bearing: (bearing_x, bearing_y),
+        page,
+        color,
+        ..
+      } = self.glyph_metrics_cache[&glyph_key]
+      else {
+        unreachable!("Rasterizing invisible glyph is not allowed");
+      };
 
+      let cached_glyph_key = self
+        .atlas_cache_path
+        .is_some()
+        .then(|| self.to_cached_glyph_key(&glyph_key));
+
+      let cached_pixels = cached_glyph_key
+        .and_then(|cached_glyph_key| self.used_cached_glyphs.get(&cached_glyph_key))
+        .filter(|cached_glyph| !cached_glyph.pixels.is_empty())
+        .map(|cached_glyph| cached_glyph.pixels.to_vec());
+
+      let glyph_pixels = if let Some(cached_pixels) = cached_pixels {
+        cached_pixels
+      } else if color {
+        color_glyph::rasterize(
+          cached_font.get_face(),
+          &cached_font.font,
+          glyph_id,
+          font_size * raster_scale,
+          foreground_color,
+          RectI::new(
+            Vector2I::new(
+              (bearing_x * raster_scale).round() as i32,
+              (bearing_y * raster_scale).round() as i32,
+            ),
+            Vector2I::new(glyph_width.cast_signed(), glyph_height.cast_signed()),
+          ),
+          GLYPH_MARGIN,
+        )
+      } else {
         let mut canvas = Canvas::new(
           Vector2I::new(
             glyph_width.cast_signed() + (GLYPH_MARGIN << 1),
@@ -204,54 +725,83 @@ impl GlyphRenderer {
           Format::A8,
         );
 
-        font
+        cached_font
+          .font
           .rasterize_glyph(
             &mut canvas,
             glyph_id,
-            font_size * self.window_scale_factor * RESOLUTION_SCALE,
+            font_size * raster_scale,
             Transform2F::from_translation(Vector2F::new(
-              (bearing_x * self.window_scale_factor)
-                .mul_add(-RESOLUTION_SCALE, GLYPH_MARGIN as f32),
-              (bearing_y * self.window_scale_factor)
-                .mul_add(-RESOLUTION_SCALE, GLYPH_MARGIN as f32),
+              bearing_x.mul_add(-raster_scale, GLYPH_MARGIN as f32),
+              bearing_y.mul_add(-raster_scale, GLYPH_MARGIN as f32),
             )),
-            HintingOptions::Vertical(font_size * self.window_scale_factor),
+            self.get_hinting_options(font_size),
             RasterizationOptions::GrayscaleAa,
           )
           .unwrap();
 
-        regions.push(vk::BufferImageCopy2 {
-          buffer_offset: pixels.len() as u64,
-          buffer_row_length: canvas.stride as u32,
-          buffer_image_height: canvas.size.y() as u32,
-          image_subresource: vk::ImageSubresourceLayers {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            mip_level: 0,
-            base_array_layer: 0,
-            layer_count: 1,
-          },
-          image_offset: vk::Offset3D {
-            x: glyph_x - GLYPH_MARGIN,
-            y: glyph_y - GLYPH_MARGIN,
-            z: 0,
-          },
-          image_extent: vk::Extent3D {
-            width: glyph_width + (GLYPH_MARGIN << 1) as u32,
-            height: glyph_height + (GLYPH_MARGIN << 1) as u32,
-            depth: 1,
-          },
-          ..Default::default()
-        });
+        // Outlines and shadows stay plain coverage in every glyph mode
+        if dilation > 0.0 || blur_radius > 0.0 {
+          let glyph_pixels = sdf::dilate_coverage(&canvas.pixels, canvas.stride, dilation);
+          sdf::blur_coverage(&glyph_pixels, canvas.stride, blur_radius).into_vec()
+        } else if self.glyph_mode == GlyphMode::Sdf {
+          sdf::sd_coverage(&canvas.pixels, canvas.stride, SDF_SPREAD as f32).into_vec()
+        } else {
+          canvas.pixels
+        }
+      };
+
+      if let Some(cached_glyph_key) = cached_glyph_key {
+        self
+          .used_cached_glyphs
+          .entry(cached_glyph_key)
+          .or_insert_with(|| CachedGlyph {
+            size: (glyph_width, glyph_height),
+            bearing: (bearing_x, bearing_y),
+            color,
+            pixels: glyph_pixels.clone().into_boxed_slice(),
+          });
+      }
+
+      let atlas_index = usize::from(color);
+
+      regions[atlas_index].push(vk::BufferImageCopy2 {
+        buffer_offset: pixels[atlas_index].len() as u64,
+        buffer_row_length: glyph_width + (GLYPH_MARGIN << 1) as u32,
+        buffer_image_height: glyph_height + (GLYPH_MARGIN << 1) as u32,
+        image_subresource: vk::ImageSubresourceLayers {
+          aspect_mask: vk::ImageAspectFlags::COLOR,
+          mip_level: 0,
+          base_array_layer: page,
+          layer_count: 1,
+        },
+        image_offset: vk::Offset3D {
+          x: glyph_x - GLYPH_MARGIN,
+          y: glyph_y - GLYPH_MARGIN,
+          z: 0,
+        },
+        image_extent: vk::Extent3D {
+          width: glyph_width + (GLYPH_MARGIN << 1) as u32,
+          height: glyph_height + (GLYPH_MARGIN << 1) as u32,
+          depth: 1,
+        },
+        ..Default::default()
+      });
+
+      pixels[atlas_index].extend(glyph_pixels);
+    }
 
-        pixels.extend(canvas.pixels);
+    for (color, (regions, pixels)) in [false, true].into_iter().zip(regions.iter().zip(&pixels)) {
+      if regions.is_empty() {
+        continue;
       }
 
-      let transfer_command_buffer = self.glyph_atlas.write(
+      let transfer_command_buffer = self.get_glyph_atlas_mut(color).write(
         vk_device,
         graphics_queue_family_index,
         transfer_queue_family_index,
-        &pixels,
-        &regions,
+        pixels,
+        regions,
       );
 
       transfer_command_buffers.push(transfer_command_buffer);
@@ -260,7 +810,7 @@ impl GlyphRenderer {
     if let Some(transfer_command_buffer) =
       self
         .glyph_sync
-        .sync_to(model_buffer, vk_device, model_buffer_offset, false)
+        .sync_to(model_buffer, vk_device, model_buffer_offset)
     {
       transfer_command_buffers.push(transfer_command_buffer);
     }
@@ -268,7 +818,7 @@ impl GlyphRenderer {
     if let Some(transfer_command_buffer) =
       self
         .clipped_glyph_sync
-        .sync_to(model_buffer, vk_device, clipped_model_buffer_offset, true)
+        .sync_to(model_buffer, vk_device, clipped_model_buffer_offset)
     {
       transfer_command_buffers.push(transfer_command_buffer);
     }
@@ -278,297 +828,1488 @@ impl GlyphRenderer {
     }
 
     self.changeset_queue.push_back(FxHashSet::default());
-    transfer_command_buffers.into_boxed_slice()
+
+    (
+      transfer_command_buffers.into_boxed_slice(),
+      glyph_atlas_grown,
+    )
   }
 
-  pub(super) fn add_text(&mut self, text: &Text, clipped: bool) -> TextId {
-    let font = self
-      .font_cache
-      .entry(text.font_key.clone())
-      .or_insert_with_key(|font_key| match *font_key {
-        FontKey::Family {
-          ref font_family,
-          font_props,
-        } => self
-          .font_source
-          .select_best_match(font_family, &font_props)
-          .unwrap()
-          .load()
-          .unwrap(),
-        FontKey::Path(ref font_path) => Font::from_path(&**font_path, 0).unwrap(),
-      });
+  fn cache_font(&mut self, font_key: &FontKey) {
+    if self.font_cache.contains_key(font_key) {
+      return;
+    }
 
-    let mut glyph_position = text.position;
-    let mut text_height = 0.0;
+    let font = match *font_key {
+      FontKey::Family {
+        ref font_family,
+        font_props,
+      } => self
+        .font_source
+        .select_best_match(font_family, &font_props)
+        .unwrap()
+        .load()
+        .unwrap(),
+      FontKey::Path(ref font_path) => Font::from_path(&**font_path, 0).unwrap(),
+      // Fonts loaded from memory own their bytes, so they are copied once as the font is cached
+      FontKey::Bytes { ref font_data, .. } => {
+        Font::from_bytes(Arc::new(font_data.to_vec()), 0).unwrap()
+      }
+      FontKey::Fallback(_) => unreachable!("Loading fallback font key is not allowed"),
+    };
 
-    let (glyph_keys, glyphs): (Vec<_>, Vec<_>) = text
-      .text
-      .chars()
-      .map(|ch| {
-        let glyph_key = GlyphKey {
-          font_key: text.font_key.clone(),
-          ch,
-          font_size: text.font_size,
-        };
+    self
+      .font_cache
+      .insert(font_key.clone(), CachedFont::new(font));
+  }
 
-        if !self.glyph_metrics_cache.contains_key(&glyph_key) {
-          let glyph_id = font.glyph_for_char(ch).unwrap_or_else(|| {
-            eprintln!("Failed to find glyph for char '{ch}'");
-            font.glyph_for_char('?').unwrap()
-          });
+  fn resolve_font(&mut self, font_keys: &[FontKey], ch: char) -> Option<FontKey> {
+    for font_key in font_keys {
+      self.cache_font(font_key);
 
-          let advance = font.advance(glyph_id).unwrap();
+      if self.font_cache[font_key].font.glyph_for_char(ch).is_some() {
+        return Some(font_key.clone());
+      }
+    }
 
-          let advance = (
-            advance.x() / 2048.0 * text.font_size,
-            advance.y() / 2048.0 * text.font_size,
-          );
+    if ch.is_control() || ch.is_whitespace() {
+      return None;
+    }
 
-          let glyph_bounds = font
-            .raster_bounds(
-              glyph_id,
-              text.font_size * self.window_scale_factor * RESOLUTION_SCALE,
-              Transform2F::default(),
-              HintingOptions::Vertical(text.font_size * self.window_scale_factor),
-              RasterizationOptions::GrayscaleAa,
-            )
-            .unwrap();
-
-          let glyph_metrics = if glyph_bounds.width() > 0_i32 && glyph_bounds.height() > 0_i32 {
-            let mut unused_glyph_evict_count = 1_usize;
-
-            let glyph_alloc = loop {
-              if let Some(glyph_alloc) = self.glyph_allocator.allocate(Size::new(
-                glyph_bounds.width() + (GLYPH_MARGIN << 1_i32),
-                glyph_bounds.height() + (GLYPH_MARGIN << 1_i32),
-              )) {
-                break glyph_alloc;
-              }
-
-              for i in 0..unused_glyph_evict_count {
-                let Some((unused_glyph_key, glyph_metrics)) =
-                  self.unused_glyph_metrics_cache.evict_one()
-                else {
-                  assert!(i > 0, "Failed to allocate from glyph atlas");
-                  break;
-                };
-
-                self.glyph_metrics_cache.remove(&unused_glyph_key);
-
-                self.changeset_queue.iter_mut().for_each(|changeset| {
-                  changeset.remove(&unused_glyph_key);
-                });
-
-                if let GlyphMetrics::Visible {
-                  alloc_id: unused_alloc_id,
-                  ..
-                } = glyph_metrics
-                {
-                  self.glyph_allocator.deallocate(unused_alloc_id);
-                }
-              }
-
-              unused_glyph_evict_count <<= 1_usize;
-            };
+    if let Some(font_key) = self.char_fallback_font_keys.get(&ch) {
+      return font_key.clone();
+    }
 
-            let changeset = self.changeset_queue.back_mut().unwrap();
-            changeset.insert(glyph_key.clone());
+    // Installed fonts found for earlier chars likely cover the script of this one too
+    let font_key = self
+      .system_fallback_font_keys
+      .iter()
+      .find(|&font_key| self.font_cache[font_key].font.glyph_for_char(ch).is_some())
+      .cloned()
+      .or_else(|| self.load_system_fallback_font(ch));
 
-            GlyphMetrics::Visible {
-              position: (
-                glyph_alloc.rectangle.min.x + GLYPH_MARGIN,
-                glyph_alloc.rectangle.min.y + GLYPH_MARGIN,
-              ),
-              size: (
-                (glyph_alloc.rectangle.width() - (GLYPH_MARGIN << 1_i32)) as u32,
-                (glyph_alloc.rectangle.height() - (GLYPH_MARGIN << 1_i32)) as u32,
-              ),
-              bearing: (
-                glyph_bounds.min_x() as f32 / self.window_scale_factor / RESOLUTION_SCALE,
-                glyph_bounds.min_y() as f32 / self.window_scale_factor / RESOLUTION_SCALE,
-              ),
-              advance,
-              alloc_id: glyph_alloc.id,
-              ref_count: 0,
-            }
-          } else {
-            GlyphMetrics::Invisible {
-              advance,
-              ref_count: 0,
-            }
-          };
+    self.char_fallback_font_keys.insert(ch, font_key.clone());
+    font_key
+  }
 
-          self
-            .glyph_metrics_cache
-            .insert(glyph_key.clone(), glyph_metrics);
-        }
+  /// Loads the first installed font that has `ch` into the font cache, returning its key. Fonts
+  /// that do not have it are dropped without being cached
+  fn load_system_fallback_font(&mut self, ch: char) -> Option<FontKey> {
+    let font_source = &self.font_source;
+
+    let system_font_handles = self.system_font_handles.get_or_insert_with(|| {
+      // The default sans-serif font is searched first, so common chars look the same everywhere
+      let default_font_handle = font_source
+        .select_best_match(&[FamilyName::SansSerif], &Properties::default())
+        .ok();
+
+      default_font_handle
+        .into_iter()
+        .chain(font_source.all_fonts().unwrap_or_default())
+        .collect()
+    });
 
-        let (glyph, ref_count, (advance_x, advance_y)) =
-          match *self.glyph_metrics_cache.get_mut(&glyph_key).unwrap() {
-            GlyphMetrics::Visible {
-              position: (glyph_x, glyph_y),
-              size: (glyph_width, glyph_height),
-              bearing: (bearing_x, bearing_y),
-              advance,
-              alloc_id: _,
-              ref mut ref_count,
-            } => {
-              let glyph_width = glyph_width as f32 / self.window_scale_factor / RESOLUTION_SCALE;
-              let glyph_height = glyph_height as f32 / self.window_scale_factor / RESOLUTION_SCALE;
-              text_height = glyph_height.max(text_height);
-
-              (
-                Some(Glyph {
-                  position: (
-                    glyph_position.0 + bearing_x,
-                    glyph_position.1 + bearing_y,
-                    glyph_position.2,
-                  ),
-                  color: text.color,
-                  size: (glyph_width, glyph_height),
-                  atlas_position: (glyph_x as f32, glyph_y as f32),
-                }),
-                ref_count,
-                advance,
-              )
-            }
-            GlyphMetrics::Invisible {
-              advance,
-              ref mut ref_count,
-            } => (None, ref_count, advance),
-          };
+    let font = system_font_handles
+      .iter()
+      .filter_map(|font_handle| font_handle.load().ok())
+      .find(|font| font.glyph_for_char(ch).is_some())?;
 
-        if *ref_count == 0 {
-          self.unused_glyph_metrics_cache.remove(&glyph_key);
-        }
+    let font_key = FontKey::Family {
+      font_family: vec![FamilyName::Title(font.family_name())].into(),
+      font_props: font.properties(),
+    };
 
-        *ref_count += 1;
-        glyph_position.0 += advance_x;
-        glyph_position.1 += advance_y;
-        (glyph_key, glyph)
-      })
-      .unzip();
+    self
+      .font_cache
+      .entry(font_key.clone())
+      .or_insert_with(|| CachedFont::new(font));
 
-    let text_width = glyph_position.0 - text.position.0;
+    if !self.system_fallback_font_keys.contains(&font_key) {
+      self.system_fallback_font_keys.push(font_key.clone());
+    }
 
-    let glyph_offset_x = match text.align {
-      Align::Left => 0.0,
-      Align::Center => -text_width * 0.5,
-      Align::Right => -text_width,
-    };
+    Some(font_key)
+  }
 
-    let glyphs = glyphs
-      .into_iter()
-      .filter_map(|glyph| {
-        glyph.map(|glyph| {
-          let (glyph_x, glyph_y, glyph_z) = glyph.position;
+  /// Finds the char of a named icon in the first of the fonts that has it, which is remembered
+  fn resolve_icon_name(&mut self, font_key: &FontKey, name: Cow<'static, str>) -> char {
+    if let Some(&ch) = self.icon_name_chars.get(&(font_key.clone(), name.clone())) {
+      return ch;
+    }
 
-          Glyph {
-            position: (glyph_x + glyph_offset_x, glyph_y, glyph_z),
-            ..glyph
-          }
-        })
-      })
-      .collect::<Box<_>>();
+    let font_keys = font_key.flatten();
 
-    let glyph_ids = self.get_glyph_sync(clipped).bulk_add_models(glyphs);
+    let ch = font_keys.iter().find_map(|font_key| {
+      self.cache_font(font_key);
+      find_icon_char(self.font_cache[font_key].get_face(), &name)
+    });
 
-    let text_id = TextId {
-      glyph_ids,
-      glyph_keys: glyph_keys.into_boxed_slice(),
-      clipped,
-    };
+    let ch = ch.unwrap_or_else(|| {
+      eprintln!("Failed to find icon named \"{name}\"");
+      MISSING_ICON_CHAR
+    });
 
-    self
-      .text_sizes
-      .insert(text_id.clone(), (text_width, text_height));
+    self.icon_name_chars.insert((font_key.clone(), name), ch);
 
-    text_id
+    ch
   }
 
-  pub(super) fn remove_text(&mut self, text_id: TextId) {
-    self.text_sizes.remove(&text_id);
+  /// Text that draws the glyph of an icon
+  fn icon_to_text(&mut self, icon: Icon) -> Text {
+    let ch = match icon.glyph {
+      IconGlyph::Char(ch) => ch,
+      IconGlyph::Name(name) => self.resolve_icon_name(&icon.font_key, name),
+    };
 
-    let TextId {
-      glyph_ids,
-      glyph_keys,
-      clipped,
-    } = text_id;
+    Text {
+      position: icon.position,
+      color: icon.color,
+      font_size: icon.font_size,
+      font_key: icon.font_key,
+      vertical_anchor: icon.vertical_anchor,
+      text: ch.to_string().into(),
+      ..Default::default()
+    }
+  }
 
-    self.get_glyph_sync(clipped).bulk_remove_models(&glyph_ids);
+  /// Allocates room for a glyph in an atlas page, forgetting the unused glyphs evicted to make
+  /// room, or `None` when the glyph does not fit
+  fn alloc_glyph(&mut self, glyph_alloc_size: Size, color: bool) -> Option<(u32, Allocation)> {
+    let mut evicted_glyph_keys = vec![];
 
-    for glyph_key in glyph_keys {
-      let glyph_metrics = self.glyph_metrics_cache.get_mut(&glyph_key).unwrap();
+    let page_alloc = self
+      .get_glyph_allocator(color)
+      .allocate(glyph_alloc_size, &mut evicted_glyph_keys);
 
-      let ref_count = match *glyph_metrics {
-        GlyphMetrics::Visible {
-          ref mut ref_count, ..
-        }
-        | GlyphMetrics::Invisible {
-          ref mut ref_count, ..
-        } => ref_count,
-      };
+    for evicted_glyph_key in evicted_glyph_keys {
+      self.glyph_metrics_cache.remove(&evicted_glyph_key);
 
-      *ref_count -= 1;
+      if self.atlas_cache_path.is_some() {
+        let cached_glyph_key = self.to_cached_glyph_key(&evicted_glyph_key);
+        self.used_cached_glyphs.remove(&cached_glyph_key);
+      }
 
-      if *ref_count > 0 {
+      self.changeset_queue.iter_mut().for_each(|changeset| {
+        changeset.remove(&evicted_glyph_key);
+      });
+    }
+
+    if page_alloc.is_none() {
+      eprintln!(
+        "Glyph atlas has no room for a glyph of {width}x{height} pixels",
+        width = glyph_alloc_size.width,
+        height = glyph_alloc_size.height
+      );
+    }
+
+    page_alloc
+  }
+
+  /// Bounds of a glyph in atlas pixels, including the room its effects need, and whether it is a
+  /// colour glyph
+  fn calc_glyph_bounds(&self, glyph_key: &GlyphKey) -> (RectI, bool) {
+    let GlyphKey {
+      ref font_key,
+      glyph_id,
+      font_size,
+      dilation,
+      blur_radius,
+      foreground_color,
+    } = *glyph_key;
+
+    let raster_scale = self.calc_raster_scale();
+    let cached_font = &self.font_cache[font_key];
+    let effect = dilation > 0.0 || blur_radius > 0.0;
+
+    let color_glyph_bounds = if effect {
+      None
+    } else {
+      color_glyph::raster_bounds(
+        cached_font.get_face(),
+        &cached_font.font,
+        glyph_id,
+        font_size * raster_scale,
+        foreground_color,
+      )
+    };
+
+    let color = color_glyph_bounds.is_some();
+
+    let glyph_bounds = color_glyph_bounds.unwrap_or_else(|| {
+      cached_font
+        .font
+        .raster_bounds(
+          glyph_id,
+          font_size * raster_scale,
+          Transform2F::default(),
+          self.get_hinting_options(font_size),
+          RasterizationOptions::GrayscaleAa,
+        )
+        .unwrap()
+    });
+
+    // Outlines and shadows grow past the glyph and distance fields need room outside of it to fade
+    // out
+    let glyph_bounds = if glyph_bounds.width() <= 0_i32 || glyph_bounds.height() <= 0_i32 {
+      glyph_bounds
+    } else if effect {
+      let effect_extent = dilation.ceil() as i32 + sdf::calc_blur_extent(blur_radius).cast_signed();
+      glyph_bounds.contract(Vector2I::splat(-effect_extent))
+    } else if !color && self.glyph_mode == GlyphMode::Sdf {
+      glyph_bounds.contract(Vector2I::splat(-SDF_SPREAD))
+    } else {
+      glyph_bounds
+    };
+
+    (glyph_bounds, color)
+  }
+
+  fn cache_glyph(&mut self, glyph_key: &GlyphKey) {
+    let raster_scale = self.calc_raster_scale();
+
+    let cached_glyph_key = self
+      .atlas_cache_path
+      .is_some()
+      .then(|| self.to_cached_glyph_key(glyph_key));
+
+    let cached_bounds = cached_glyph_key
+      .and_then(|cached_glyph_key| self.use_cached_glyph(&cached_glyph_key))
+      .map(|cached_glyph| {
+        let (glyph_width, glyph_height) = cached_glyph.size;
+        let (bearing_x, bearing_y) = cached_glyph.bearing;
+
+        (
+          RectI::new(
+            Vector2I::new(
+              (bearing_x * raster_scale).round() as i32,
+              (bearing_y * raster_scale).round() as i32,
+            ),
+            Vector2I::new(glyph_width.cast_signed(), glyph_height.cast_signed()),
+          ),
+          cached_glyph.color,
+        )
+      });
+
+    // Glyphs from the atlas cache skip measuring
+    let (glyph_bounds, color) = cached_bounds.unwrap_or_else(|| self.calc_glyph_bounds(glyph_key));
+
+    let ink = glyph_bounds.width() > 0_i32 && glyph_bounds.height() > 0_i32;
+
+    // Glyphs that do not fit in the atlas are skipped as if they had no ink
+    let page_alloc = if ink {
+      self.alloc_glyph(
+        Size::new(
+          glyph_bounds.width() + (GLYPH_MARGIN << 1_i32),
+          glyph_bounds.height() + (GLYPH_MARGIN << 1_i32),
+        ),
+        color,
+      )
+    } else {
+      None
+    };
+
+    let glyph_metrics = if let Some((page, glyph_alloc)) = page_alloc {
+      let changeset = self.changeset_queue.back_mut().unwrap();
+      changeset.insert(glyph_key.clone());
+
+      GlyphMetrics::Visible {
+        position: (
+          glyph_alloc.rectangle.min.x + GLYPH_MARGIN,
+          glyph_alloc.rectangle.min.y + GLYPH_MARGIN,
+        ),
+        size: (
+          (glyph_alloc.rectangle.width() - (GLYPH_MARGIN << 1_i32)) as u32,
+          (glyph_alloc.rectangle.height() - (GLYPH_MARGIN << 1_i32)) as u32,
+        ),
+        bearing: (
+          glyph_bounds.min_x() as f32 / raster_scale,
+          glyph_bounds.min_y() as f32 / raster_scale,
+        ),
+        page,
+        alloc_id: glyph_alloc.id,
+        color,
+        ref_count: 0,
+      }
+    } else {
+      // Glyphs without ink are never rasterized, so they are cached as soon as they are measured
+      if !ink && let Some(cached_glyph_key) = cached_glyph_key {
+        self
+          .used_cached_glyphs
+          .entry(cached_glyph_key)
+          .or_insert_with(|| CachedGlyph {
+            size: (0, 0),
+            bearing: (0.0, 0.0),
+            color,
+            pixels: Box::default(),
+          });
+      }
+
+      GlyphMetrics::Invisible { ref_count: 0 }
+    };
+
+    self
+      .glyph_metrics_cache
+      .insert(glyph_key.clone(), glyph_metrics);
+  }
+
+  /// Model a glyph gets from `ref_glyph`, only telling where its ink is, without rasterizing or
+  /// referencing it
+  fn measure_glyph(&self, glyph_key: &GlyphKey, glyph_scale: f32) -> Option<Glyph> {
+    let raster_scale = self.calc_raster_scale();
+    let effect = glyph_key.dilation > 0.0 || glyph_key.blur_radius > 0.0;
+
+    let ((bearing_x, bearing_y), (glyph_width, glyph_height), color_glyph) =
+      match self.glyph_metrics_cache.get(glyph_key) {
+        Some(&GlyphMetrics::Visible {
+          size,
+          bearing,
+          color,
+          ..
+        }) => (bearing, size, color),
+        Some(&GlyphMetrics::Invisible { .. }) => return None,
+        None => {
+          let (glyph_bounds, color) = self.calc_glyph_bounds(glyph_key);
+
+          if glyph_bounds.width() <= 0_i32 || glyph_bounds.height() <= 0_i32 {
+            return None;
+          }
+
+          (
+            (
+              glyph_bounds.min_x() as f32 / raster_scale,
+              glyph_bounds.min_y() as f32 / raster_scale,
+            ),
+            (
+              glyph_bounds.width().cast_unsigned(),
+              glyph_bounds.height().cast_unsigned(),
+            ),
+            color,
+          )
+        }
+      };
+
+    let (glyph_width, glyph_height) = (glyph_width as f32, glyph_height as f32);
+
+    let flags = if color_glyph {
+      GLYPH_FLAG_COLOR
+    } else if !effect && self.glyph_mode == GlyphMode::Sdf {
+      GLYPH_FLAG_SDF
+    } else {
+      0
+    };
+
+    Some(Glyph {
+      position: (bearing_x * glyph_scale, bearing_y * glyph_scale, 0.0),
+      color: 0,
+      size: (
+        glyph_width / raster_scale * glyph_scale,
+        glyph_height / raster_scale * glyph_scale,
+      ),
+      atlas_position: (0.0, 0.0),
+      atlas_size: (glyph_width, glyph_height),
+      paint_origin: (0.0, 0.0),
+      atlas_page: SOLID_ATLAS_PAGE,
+      flags,
+      paint_id: 0,
+    })
+  }
+
+  /// Caches a glyph when needed and references it from a text, placing it relative to the pen
+  /// - `glyph_key`: glyph to reference
+  /// - `glyph_scale`: scale from the font size the glyph is rasterized at to the span font size
+  /// - `color`: colour the glyph is drawn with
+  /// - `glyph_keys`: glyphs referenced by the text so far
+  fn ref_glyph(
+    &mut self,
+    glyph_key: GlyphKey,
+    glyph_scale: f32,
+    color: u32,
+    glyph_keys: &mut Vec<GlyphKey>,
+  ) -> Option<Glyph> {
+    if !self.glyph_metrics_cache.contains_key(&glyph_key) {
+      self.cache_glyph(&glyph_key);
+    }
+
+    let raster_scale = self.calc_raster_scale();
+    let effect = glyph_key.dilation > 0.0 || glyph_key.blur_radius > 0.0;
+
+    let (glyph, color_glyph, ref_count) =
+      match *self.glyph_metrics_cache.get_mut(&glyph_key).unwrap() {
+        GlyphMetrics::Visible {
+          position: (glyph_x, glyph_y),
+          size: (glyph_width, glyph_height),
+          bearing: (bearing_x, bearing_y),
+          page,
+          alloc_id: _,
+          color: color_glyph,
+          ref mut ref_count,
+        } => {
+          let (glyph_width, glyph_height) = (glyph_width as f32, glyph_height as f32);
+
+          let flags = if color_glyph {
+            GLYPH_FLAG_COLOR
+          } else if !effect && self.glyph_mode == GlyphMode::Sdf {
+            GLYPH_FLAG_SDF
+          } else {
+            0
+          };
+
+          (
+            Some(Glyph {
+              position: (bearing_x * glyph_scale, bearing_y * glyph_scale, 0.0),
+              color,
+              size: (
+                glyph_width / raster_scale * glyph_scale,
+                glyph_height / raster_scale * glyph_scale,
+              ),
+              atlas_position: (glyph_x as f32, glyph_y as f32),
+              atlas_size: (glyph_width, glyph_height),
+              paint_origin: (0.0, 0.0),
+              atlas_page: page,
+              flags,
+              paint_id: 0,
+            }),
+            color_glyph,
+            ref_count,
+          )
+        }
+        GlyphMetrics::Invisible { ref mut ref_count } => (None, false, ref_count),
+      };
+
+    let unused = *ref_count == 0;
+    *ref_count += 1;
+
+    if unused {
+      self.get_glyph_allocator(color_glyph).reuse(&glyph_key);
+    }
+
+    glyph_keys.push(glyph_key);
+    glyph
+  }
+
+  /// Width of the ellipsis that stands in for the chars cut from an overflowing line
+  fn calc_ellipsis_width(&mut self, span: &TextSpan) -> f32 {
+    let font_keys = span.font_key.flatten();
+
+    let font_key = self
+      .resolve_font(&font_keys, ELLIPSIS)
+      .unwrap_or_else(|| font_keys[0].clone());
+
+    let font = &self.font_cache[&font_key].font;
+
+    font
+      .glyph_for_char(ELLIPSIS)
+      .and_then(|glyph_id| font.advance(glyph_id).ok())
+      .map_or(0.0, |advance| {
+        advance.x() * span.font_size / font.metrics().units_per_em as f32
+      })
+  }
+
+  /// Replaces the chars of each line wider than `max_width` that do not fit next to an ellipsis
+  /// with one, at the end of the line or in its middle, returning the elided char ranges too
+  fn elide_text(
+    &mut self,
+    text: &RichText,
+    advances: &[f32],
+    max_width: f32,
+  ) -> (RichText, Vec<Range<usize>>) {
+    let full_text = text
+      .spans
+      .iter()
+      .map(|span| &*span.text)
+      .collect::<String>();
+
+    let char_span_indices = text
+      .spans
+      .iter()
+      .enumerate()
+      .flat_map(|(span_index, span)| span.text.chars().map(move |_ch| span_index))
+      .collect::<Box<[_]>>();
+
+    let span_ellipsis_widths = text
+      .spans
+      .iter()
+      .map(|span| self.calc_ellipsis_width(span))
+      .collect::<Box<[_]>>();
+
+    let ellipsis_widths = char_span_indices
+      .iter()
+      .map(|&span_index| span_ellipsis_widths[span_index])
+      .collect::<Box<[_]>>();
+
+    let elided_ranges = text_layout::elide_lines(
+      &full_text,
+      advances,
+      max_width,
+      text.wrap,
+      text.overflow,
+      &ellipsis_widths,
+    );
+
+    // Each ellipsis joins the span of the first char it stands in for
+    let mut span_texts = vec![String::new(); text.spans.len()];
+    let mut elided_ranges_iter = elided_ranges.iter().peekable();
+
+    for (char_index, ch) in full_text.chars().enumerate() {
+      let span_text = &mut span_texts[char_span_indices[char_index]];
+
+      while elided_ranges_iter
+        .next_if(|elided_range| elided_range.end <= char_index)
+        .is_some()
+      {}
+
+      match elided_ranges_iter.peek() {
+        Some(elided_range) if elided_range.start == char_index => span_text.push(ELLIPSIS),
+        Some(elided_range) if elided_range.contains(&char_index) => (),
+        _ => span_text.push(ch),
+      }
+    }
+
+    let elided_text = RichText {
+      spans: text
+        .spans
+        .iter()
+        .zip(span_texts)
+        .map(|(span, span_text)| TextSpan {
+          text: span_text.into(),
+          ..span.clone()
+        })
+        .collect::<Vec<_>>()
+        .into(),
+      // Shaping may make the ellipsis a little wider than measured
+      overflow: Overflow::Clip,
+      ..text.clone()
+    };
+
+    (elided_text, elided_ranges)
+  }
+
+  /// Shapes and lays out rich text into glyph models, fitting lines wider than its max width the
+  /// way its overflow asks, referencing every glyph it uses
+  /// - `measure_only`: whether only its metrics are needed, which lays out no glyph models and
+  ///   neither rasterizes nor references glyphs
+  fn layout_text(&mut self, text: &RichText, measure_only: bool) -> ShapedText {
+    let shaped_text = self.layout_unfitted_text(text, measure_only);
+
+    let Some(max_width) = text.max_width else {
+      return shaped_text;
+    };
+
+    if shaped_text.metrics.width <= max_width {
+      return shaped_text;
+    }
+
+    let fitted_text = match text.overflow {
+      Overflow::Visible | Overflow::Clip => return shaped_text,
+      Overflow::Ellipsis | Overflow::MiddleEllipsis => {
+        let (elided_text, elided_ranges) =
+          self.elide_text(text, &shaped_text.metrics.advances, max_width);
+
+        self.release_glyphs(shaped_text.glyph_keys);
+
+        let ShapedText {
+          glyphs,
+          glyph_keys,
+          metrics,
+          rtl_chars,
+        } = self.layout_unfitted_text(&elided_text, measure_only);
+
+        // Carets and advances index the chars of the caller's text, not the ones laid out
+        let (metrics, rtl_chars) =
+          text_layout::unelide_metrics(&metrics, &rtl_chars, &elided_ranges);
+
+        return ShapedText {
+          glyphs,
+          glyph_keys,
+          metrics,
+          rtl_chars,
+        };
+      }
+      Overflow::ShrinkToFit { min_font_size } => {
+        let primary_font_size = text.spans.first().map_or(0.0, |span| span.font_size);
+
+        let font_scale = (max_width / shaped_text.metrics.width)
+          .max(min_font_size / primary_font_size)
+          .min(1.0);
+
+        RichText {
+          spans: text
+            .spans
+            .iter()
+            .map(|span| TextSpan {
+              font_size: span.font_size * font_scale,
+              ..span.clone()
+            })
+            .collect::<Vec<_>>()
+            .into(),
+          // Text that cannot shrink any further is clipped instead
+          overflow: Overflow::Clip,
+          ..text.clone()
+        }
+      }
+    };
+
+    self.release_glyphs(shaped_text.glyph_keys);
+    self.layout_unfitted_text(&fitted_text, measure_only)
+  }
+
+  /// Shapes and lays out rich text into glyph models as is, referencing every glyph it uses
+  /// - `measure_only`: as in `layout_text`
+  fn layout_unfitted_text(&mut self, text: &RichText, measure_only: bool) -> ShapedText {
+    let full_text = text
+      .spans
+      .iter()
+      .map(|span| &*span.text)
+      .collect::<String>();
+    let mut span_font_keys = Vec::with_capacity(text.spans.len());
+    let mut span_font_metrics = Vec::with_capacity(text.spans.len());
+
+    for span in text.spans.iter() {
+      let font_keys = span.font_key.flatten();
+      self.cache_font(&font_keys[0]);
+      let font_metrics = self.font_cache[&font_keys[0]].font.metrics();
+      span_font_metrics.push((
+        font_metrics,
+        span.font_size / font_metrics.units_per_em as f32,
+      ));
+      span_font_keys.push(font_keys);
+    }
+
+    let bidi_info = BidiInfo::new(&full_text, None);
+
+    // Chars stay in the current run while its span, bidi level and font cover them, so spaces and
+    // marks are shaped together with their neighbours
+    let mut font_runs = Vec::<(usize, usize, Level, FontKey)>::new();
+    let mut char_span_indices = Vec::with_capacity(full_text.len());
+    let mut span_start = 0;
+
+    for (span_index, span) in text.spans.iter().enumerate() {
+      let font_keys = &span_font_keys[span_index];
+
+      for (byte_index, ch) in span.text.char_indices() {
+        char_span_indices.push(span_index);
+        let level = bidi_info.levels[span_start + byte_index];
+
+        if let Some(&(_run_start, run_span_index, run_level, ref run_font_key)) = font_runs.last()
+          && run_span_index == span_index
+          && run_level == level
+          && (ch.is_control()
+            || self.font_cache[run_font_key]
+              .font
+              .glyph_for_char(ch)
+              .is_some())
+        {
+          continue;
+        }
+
+        let font_key = self
+          .resolve_font(font_keys, ch)
+          .unwrap_or_else(|| font_keys[0].clone());
+
+        if font_runs.last().is_none_or(
+          |&(_run_start, run_span_index, run_level, ref run_font_key)| {
+            run_span_index != span_index || run_level != level || *run_font_key != font_key
+          },
+        ) {
+          font_runs.push((span_start + byte_index, span_index, level, font_key));
+        }
+      }
+
+      span_start += span.text.len();
+    }
+
+    let chars = full_text.char_indices().collect::<Box<[_]>>();
+    let mut advances = vec![0.0; chars.len()];
+    let mut glyph_keys = Vec::with_capacity(chars.len());
+    let mut shaped_glyphs = Vec::with_capacity(chars.len());
+    let (text_x, text_y, text_z) = text.position;
+
+    for (run_index, &(run_start, run_span_index, run_level, ref run_font_key)) in
+      font_runs.iter().enumerate()
+    {
+      let run_end = font_runs
+        .get(run_index + 1)
+        .map_or(full_text.len(), |&(next_run_start, _, _, _)| next_run_start);
+
+      let span = &text.spans[run_span_index];
+      let face = self.font_cache[run_font_key].get_face();
+      let font_scale = span.font_size / face.units_per_em() as f32;
+      let glyph_font_size = self.calc_glyph_font_size(span.font_size);
+      let glyph_scale = span.font_size / glyph_font_size;
+      let raster_scale = self.calc_raster_scale();
+
+      let glyph_buffer = shape_run(
+        face,
+        full_text.get(run_start..run_end).unwrap(),
+        run_level.is_rtl(),
+      );
+
+      // Right to left runs come out in visual order, so they are flipped back to logical order
+      // until the lines are laid out. Colour glyphs drawn partly in the text colour are rasterized
+      // once per colour.
+      let mut run_glyphs = glyph_buffer
+        .glyph_infos()
+        .iter()
+        .zip(glyph_buffer.glyph_positions())
+        .map(|(glyph_info, glyph_pos)| {
+          let foreground = color_glyph::uses_foreground_color(face, glyph_info.glyph_id);
+          (glyph_info, glyph_pos, foreground)
+        })
+        .collect::<Vec<_>>();
+
+      if run_level.is_rtl() {
+        run_glyphs.reverse();
+      }
+
+      // Each shaped glyph belongs to the first char of its cluster, ligatures and marks advance
+      // the pen as a whole
+      for (glyph_info, glyph_pos, foreground) in run_glyphs {
+        let char_index = chars
+          .binary_search_by_key(
+            &(run_start + glyph_info.cluster as usize),
+            |&(byte_index, _ch)| byte_index,
+          )
+          .unwrap();
+
+        let (_byte_index, ch) = chars[char_index];
+
+        // Line breaks are handled by the text layout instead
+        if ch.is_control() {
+          continue;
+        }
+
+        let advance_x = glyph_pos.x_advance as f32 * font_scale;
+        advances[char_index] += advance_x;
+
+        // Chars no font covers keep glyph 0, which fonts draw as their missing glyph
+        let glyph_id = glyph_info.glyph_id;
+
+        // The alpha of the text applies to the whole glyph, so its foreground stays opaque
+        let glyph_key = GlyphKey {
+          font_key: run_font_key.clone(),
+          glyph_id,
+          font_size: glyph_font_size,
+          dilation: 0.0,
+          blur_radius: 0.0,
+          foreground_color: if foreground { span.color | 0xFF } else { 0 },
+        };
+
+        let glyph_offset = (
+          glyph_pos.x_offset as f32 * font_scale,
+          glyph_pos.y_offset as f32 * -font_scale,
+          text_z,
+        );
+
+        let glyph = if measure_only {
+          self.measure_glyph(&glyph_key, glyph_scale)
+        } else {
+          self.ref_glyph(glyph_key.clone(), glyph_scale, span.color, &mut glyph_keys)
+        };
+
+        let glyph = glyph.map(|glyph| Glyph {
+          paint_id: span.paint_id,
+          ..offset_glyph(glyph, glyph_offset)
+        });
+
+        // Outlines and shadows are wider copies of the glyph drawn behind it, in atlas pixels of
+        // its font size, which do not count towards the size of the text
+        let mut effect_glyphs = [None, None];
+
+        if !measure_only && glyph.is_some_and(|glyph| glyph.flags & GLYPH_FLAG_COLOR == 0) {
+          let (offset_x, offset_y, offset_z) = glyph_offset;
+          let effect_scale = raster_scale / glyph_scale;
+
+          let outline_width = span.decoration.outline.map_or(0.0, |outline| {
+            quantize_effect_radius(outline.width * effect_scale)
+          });
+
+          if let Some(shadow) = span.decoration.shadow {
+            let (shadow_x, shadow_y) = shadow.offset;
+
+            let shadow_key = GlyphKey {
+              dilation: outline_width,
+              blur_radius: quantize_effect_radius(shadow.blur_radius * effect_scale),
+              ..glyph_key.clone()
+            };
+
+            effect_glyphs[0] = self
+              .ref_glyph(shadow_key, glyph_scale, shadow.color, &mut glyph_keys)
+              .map(|glyph| {
+                offset_glyph(
+                  glyph,
+                  (
+                    offset_x + shadow_x,
+                    offset_y + shadow_y,
+                    offset_z + SHADOW_Z_OFFSET,
+                  ),
+                )
+              });
+          }
+
+          if let Some(outline) = span.decoration.outline
+            && outline_width > 0.0
+          {
+            let outline_key = GlyphKey {
+              dilation: outline_width,
+              ..glyph_key
+            };
+
+            effect_glyphs[1] = self
+              .ref_glyph(outline_key, glyph_scale, outline.color, &mut glyph_keys)
+              .map(|glyph| offset_glyph(glyph, (offset_x, offset_y, offset_z + OUTLINE_Z_OFFSET)));
+          }
+        }
+
+        shaped_glyphs.push((char_index, run_span_index, advance_x, glyph, effect_glyphs));
+      }
+    }
+
+    let lines = text_layout::break_lines(&full_text, &advances, text.max_width, text.wrap);
+    let primary_font_size = text.spans.first().map_or(0.0, |span| span.font_size);
+    let mut line_ys = Vec::<f32>::with_capacity(lines.len());
+
+    let (ascent, descent) = span_font_metrics.iter().fold(
+      (0.0, 0.0),
+      |(ascent, descent): (f32, f32), &(font_metrics, font_metrics_scale)| {
+        (
+          ascent.max(font_metrics.ascent * font_metrics_scale),
+          descent.max(-font_metrics.descent * font_metrics_scale),
+        )
+      },
+    );
+
+    // Each line is as tall as the biggest span on it, lines are stacked from the first baseline
+    // and then moved together so that the anchor of the text lands on its position
+    for line in &lines {
+      let line_font_size = char_span_indices[line.chars.clone()]
+        .iter()
+        .map(|&span_index| text.spans[span_index].font_size)
+        .reduce(f32::max)
+        .unwrap_or(primary_font_size);
+
+      line_ys.push(line_ys.last().map_or(0.0, |&prev_line_y| {
+        line_font_size.mul_add(text.line_height, prev_line_y)
+      }));
+    }
+
+    let last_line_y = line_ys.last().copied().unwrap_or_default();
+
+    let baseline_y = match text.vertical_anchor {
+      VerticalAnchor::Top => ascent,
+      VerticalAnchor::Middle => (ascent - descent - last_line_y) * 0.5,
+      VerticalAnchor::Baseline => 0.0,
+      VerticalAnchor::Bottom => -descent - last_line_y,
+    };
+
+    for line_y in &mut line_ys {
+      *line_y += baseline_y;
+    }
+
+    let mut glyphs = Vec::with_capacity(shaped_glyphs.len());
+    let mut decoration_glyphs = vec![];
+    let mut effect_glyphs = vec![];
+
+    let PlacedText {
+      lines: placed_lines,
+      carets,
+      rtl_chars,
+    } = text_layout::place_lines(&full_text, &advances, &lines, &line_ys, text.align);
+
+    for placed_line in placed_lines {
+      let rtl = placed_line.rtl;
+      let line_y = text_y + placed_line.y;
+      let mut glyph_x = text_x + placed_line.x;
+
+      // Clipped lines keep the part of them inside the box their max width spans from the text
+      // position
+      let clip_range = text
+        .max_width
+        .filter(|_max_width| text.overflow == Overflow::Clip)
+        .map(|max_width| {
+          let clip_left = match (text.align, rtl) {
+            (Align::Left, false) | (Align::Right, true) => text_x,
+            (Align::Center, _) => max_width.mul_add(-0.5, text_x),
+            (Align::Right, false) | (Align::Left, true) => text_x - max_width,
+          };
+
+          (clip_left, clip_left + max_width)
+        });
+
+      // Span index, start x and end x of each piece of the line that shares a span
+      let mut span_segments = Vec::<(usize, f32, f32)>::new();
+
+      for (run_chars, run_rtl) in placed_line.visual_runs {
+        let run_glyphs_start = shaped_glyphs.partition_point(
+          |&(char_index, _span_index, _advance_x, _glyph, _effect_glyphs)| {
+            char_index < run_chars.start
+          },
+        );
+
+        let run_glyphs_end = shaped_glyphs.partition_point(
+          |&(char_index, _span_index, _advance_x, _glyph, _effect_glyphs)| {
+            char_index < run_chars.end
+          },
+        );
+
+        let mut run_glyphs = shaped_glyphs[run_glyphs_start..run_glyphs_end].to_vec();
+
+        if run_rtl {
+          run_glyphs.reverse();
+        }
+
+        for (_char_index, span_index, advance_x, glyph, run_effect_glyphs) in run_glyphs {
+          glyphs.extend(
+            glyph.and_then(|glyph| {
+              clip_glyph(offset_glyph(glyph, (glyph_x, line_y, 0.0)), clip_range)
+            }),
+          );
+
+          effect_glyphs.extend(run_effect_glyphs.into_iter().flatten().filter_map(|glyph| {
+            clip_glyph(offset_glyph(glyph, (glyph_x, line_y, 0.0)), clip_range)
+          }));
+
+          match span_segments.last_mut() {
+            Some(&mut (segment_span_index, _segment_start, ref mut segment_end))
+              if segment_span_index == span_index =>
+            {
+              *segment_end += advance_x;
+            }
+            _ => span_segments.push((span_index, glyph_x, glyph_x + advance_x)),
+          }
+
+          glyph_x += advance_x;
+        }
+      }
+
+      for (span_index, segment_start, segment_end) in span_segments {
+        let span = &text.spans[span_index];
+        let (font_metrics, font_metrics_scale) = span_font_metrics[span_index];
+
+        let thickness = (font_metrics.underline_thickness * font_metrics_scale)
+          .max(1.0 / self.window_scale_factor);
+
+        // Offsets are above the baseline in font units
+        let decoration_offsets = [
+          span
+            .decoration
+            .underline
+            .then_some(font_metrics.underline_position),
+          span
+            .decoration
+            .strikethrough
+            .then_some(font_metrics.x_height * 0.5),
+        ];
+
+        decoration_glyphs.extend(
+          decoration_offsets
+            .into_iter()
+            .flatten()
+            .filter(|_offset| segment_end > segment_start)
+            .filter_map(|offset| {
+              clip_glyph(
+                Glyph {
+                  position: (
+                    segment_start,
+                    offset.mul_add(-font_metrics_scale, thickness.mul_add(-0.5, line_y)),
+                    text_z,
+                  ),
+                  color: span.color,
+                  size: (segment_end - segment_start, thickness),
+                  atlas_position: (0.0, 0.0),
+                  atlas_size: (0.0, 0.0),
+                  paint_origin: (0.0, 0.0),
+                  atlas_page: SOLID_ATLAS_PAGE,
+                  flags: 0,
+                  paint_id: span.paint_id,
+                },
+                clip_range,
+              )
+            }),
+        );
+      }
+    }
+
+    glyphs.extend(decoration_glyphs);
+
+    let text_width = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+
+    let text_width = match text.max_width {
+      Some(max_width) if text.overflow == Overflow::Clip => text_width.min(max_width),
+      _ => text_width,
+    };
+
+    let (text_left, text_top, text_bottom) = glyphs.iter().fold(
+      (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY),
+      |(text_left, text_top, text_bottom), glyph| {
+        let (glyph_x, glyph_y, _glyph_z) = glyph.position;
+        let (_glyph_width, glyph_height) = glyph.size;
+
+        // The fading edge of distance fields is not part of the ink
+        let glyph_inset = if glyph.flags & GLYPH_FLAG_SDF == 0 {
+          0.0
+        } else {
+          let (_atlas_width, atlas_height) = glyph.atlas_size;
+          SDF_SPREAD as f32 * glyph_height / atlas_height
+        };
+
+        (
+          text_left.min(glyph_x),
+          text_top.min(glyph_y + glyph_inset),
+          text_bottom.max(glyph_y + glyph_height - glyph_inset),
+        )
+      },
+    );
+
+    let text_height = (text_bottom - text_top).max(0.0);
+
+    // Outlines and shadows do not count towards the size of the text
+    glyphs.extend(effect_glyphs);
+
+    // Paints span the whole text instead of starting over at each glyph
+    if text_left.is_finite() && text_top.is_finite() {
+      for glyph in &mut glyphs {
+        glyph.paint_origin = (text_left, text_top);
+      }
+    }
+
+    ShapedText {
+      glyphs: glyphs.into_boxed_slice(),
+      glyph_keys: glyph_keys.into_boxed_slice(),
+      metrics: TextMetrics {
+        width: text_width,
+        height: text_height,
+        ascent,
+        descent,
+        baseline: if text_top.is_finite() {
+          text_y + baseline_y - text_top
+        } else {
+          0.0
+        },
+        advances: advances.into_boxed_slice(),
+        carets,
+      },
+      rtl_chars,
+    }
+  }
+
+  #[inline]
+  pub(super) fn add_text(&mut self, text: &Text, clipped: bool) -> TextId {
+    self.add_rich_text(&RichText::from(text.clone()), clipped)
+  }
+
+  pub(super) fn add_rich_text(&mut self, text: &RichText, clipped: bool) -> TextId {
+    let ShapedText {
+      glyphs,
+      glyph_keys,
+      metrics,
+      rtl_chars,
+    } = self.layout_text(text, false);
+
+    let glyph_ids = self.get_glyph_sync(clipped).bulk_add_models(glyphs.clone());
+    let text_id = TextId {
+      id: self.next_text_id,
+    };
+
+    self.next_text_id += 1;
+
+    self.texts.insert(
+      text_id,
+      TextEntry {
+        text: text.clone(),
+        glyph_ids,
+        glyph_keys,
+        clipped,
+        glyphs,
+        metrics,
+        rtl_chars,
+      },
+    );
+
+    text_id
+  }
+
+  #[inline]
+  pub(super) fn update_text(&mut self, text_id: TextId, text: &Text) {
+    self.update_rich_text(text_id, &RichText::from(text.clone()));
+  }
+
+  /// Lays out text again into the glyph models of an existing text, only uploading glyphs that
+  /// changed
+  pub(super) fn update_rich_text(&mut self, text_id: TextId, text: &RichText) {
+    let mut text_entry = self.texts.remove(&text_id).unwrap();
+
+    // New glyphs get referenced before old ones are released, so glyphs in both stay in use
+    let ShapedText {
+      glyphs,
+      glyph_keys,
+      metrics,
+      rtl_chars,
+    } = self.layout_text(text, false);
+
+    let old_glyph_keys = mem::replace(&mut text_entry.glyph_keys, glyph_keys);
+    self.release_glyphs(old_glyph_keys);
+    self.update_text_glyphs(&mut text_entry, glyphs);
+    text_entry.text = text.clone();
+    text_entry.metrics = metrics;
+    text_entry.rtl_chars = rtl_chars;
+    self.texts.insert(text_id, text_entry);
+  }
+
+  /// Moves the glyph models of a text over to `glyphs`, reusing its IDs and adding or removing
+  /// models when the glyph count changes
+  fn update_text_glyphs(&mut self, text_entry: &mut TextEntry, glyphs: Box<[Glyph]>) {
+    let reused_glyph_count = glyphs.len().min(text_entry.glyph_ids.len());
+    let (reused_glyphs, added_glyphs) = glyphs.split_at(reused_glyph_count);
+
+    let (changed_glyph_ids, changed_glyphs): (Vec<_>, Vec<_>) = text_entry
+      .glyph_ids
+      .iter()
+      .zip(reused_glyphs)
+      .zip(&text_entry.glyphs)
+      .filter(|&((_glyph_id, glyph), old_glyph)| !is_same_glyph(glyph, old_glyph))
+      .map(|((&glyph_id, &glyph), _old_glyph)| (glyph_id, glyph))
+      .unzip();
+
+    let mut glyph_ids = text_entry.glyph_ids.to_vec();
+    let glyph_sync = self.get_glyph_sync(text_entry.clipped);
+
+    if !changed_glyph_ids.is_empty() {
+      glyph_sync.bulk_update_models(&changed_glyph_ids, changed_glyphs.into_boxed_slice());
+    }
+
+    if !added_glyphs.is_empty() {
+      glyph_ids.extend(glyph_sync.bulk_add_models(added_glyphs.into()));
+    } else if glyph_ids.len() > reused_glyph_count {
+      glyph_sync.bulk_remove_models(&glyph_ids.split_off(reused_glyph_count));
+    } else {
+      // Same glyph count, so every ID got reused
+    }
+
+    text_entry.glyph_ids = glyph_ids.into_boxed_slice();
+    text_entry.glyphs = glyphs;
+  }
+
+  /// Rasterizes every glyph again at the new scale factor and lays out all live texts with them
+  pub(super) fn set_window_scale_factor(&mut self, window_scale_factor: f32) {
+    if window_scale_factor == self.window_scale_factor {
+      return;
+    }
+
+    self.window_scale_factor = window_scale_factor;
+    self.glyph_metrics_cache.clear();
+
+    // Cached glyphs count as unused again until the texts are laid out at the new scale factor
+    let used_cached_glyphs = mem::take(&mut self.used_cached_glyphs);
+    self.loaded_cached_glyphs.extend(used_cached_glyphs);
+    self.glyph_allocator.clear();
+    self.color_glyph_allocator.clear();
+
+    self.changeset_queue.iter_mut().for_each(FxHashSet::clear);
+    let texts = mem::take(&mut self.texts).into_iter().collect::<Vec<_>>();
+
+    for (text_id, mut text_entry) in texts {
+      let ShapedText {
+        glyphs,
+        glyph_keys,
+        metrics,
+        rtl_chars,
+      } = self.layout_text(&text_entry.text, false);
+
+      // The old glyph keys were released along with the cache they were counted in
+      text_entry.glyph_keys = glyph_keys;
+      self.update_text_glyphs(&mut text_entry, glyphs);
+      text_entry.metrics = metrics;
+      text_entry.rtl_chars = rtl_chars;
+      self.texts.insert(text_id, text_entry);
+    }
+  }
+
+  fn release_glyphs(&mut self, glyph_keys: Box<[GlyphKey]>) {
+    for glyph_key in glyph_keys {
+      let glyph_metrics = self.glyph_metrics_cache.get_mut(&glyph_key).unwrap();
+
+      let ref_count = match *glyph_metrics {
+        GlyphMetrics::Visible {
+          ref mut ref_count, ..
+        }
+        | GlyphMetrics::Invisible { ref mut ref_count } => ref_count,
+      };
+
+      *ref_count -= 1;
+
+      if *ref_count > 0 {
         continue;
       }
 
+      let (color, glyph_alloc) = match *glyph_metrics {
+        GlyphMetrics::Visible {
+          page,
+          alloc_id,
+          color,
+          ..
+        } => (color, Some((page, alloc_id))),
+        GlyphMetrics::Invisible { .. } => (false, None),
+      };
+
       self
-        .unused_glyph_metrics_cache
-        .insert(glyph_key, *glyph_metrics);
+        .get_glyph_allocator(color)
+        .release(glyph_key, glyph_alloc);
     }
   }
 
-  pub(super) fn add_icon(&mut self, icon: Icon, clipped: bool) -> IconId {
-    let TextId {
+  #[inline]
+  pub(super) fn measure_text(&mut self, text: &Text) -> TextMetrics {
+    self.measure_rich_text(&RichText::from(text.clone()))
+  }
+
+  /// Shapes and lays out text from glyph bounds alone, without rasterizing glyphs or adding glyph
+  /// models
+  #[inline]
+  pub(super) fn measure_rich_text(&mut self, text: &RichText) -> TextMetrics {
+    self.layout_text(text, true).metrics
+  }
+
+  #[inline]
+  pub(super) fn measure_icon(&mut self, icon: Icon) -> TextMetrics {
+    let text = self.icon_to_text(icon);
+    self.measure_text(&text)
+  }
+
+  /// Vertical metrics of the first font of `font_key` at `font_size`
+  pub(super) fn get_font_metrics(&mut self, font_key: &FontKey, font_size: f32) -> FontMetrics {
+    let font_keys = font_key.flatten();
+    self.cache_font(&font_keys[0]);
+    let font_metrics = self.font_cache[&font_keys[0]].font.metrics();
+    let font_metrics_scale = font_size / font_metrics.units_per_em as f32;
+
+    FontMetrics {
+      ascent: font_metrics.ascent * font_metrics_scale,
+      descent: -font_metrics.descent * font_metrics_scale,
+      line_gap: font_metrics.line_gap * font_metrics_scale,
+      cap_height: font_metrics.cap_height * font_metrics_scale,
+      x_height: font_metrics.x_height * font_metrics_scale,
+    }
+  }
+
+  /// Loads the glyphs earlier launches cached in `atlas_cache_path`, and keeps the glyphs used
+  /// from now on so that `save_atlas_cache` can write them back to it
+  pub(super) fn load_atlas_cache(&mut self, atlas_cache_path: PathBuf) {
+    let settings_hash = calc_atlas_cache_settings_hash();
+    let mut cached_glyphs = atlas_cache::read(&atlas_cache_path, settings_hash);
+
+    // Glyphs whose pixels do not fill their bounds are left to be rasterized again
+    cached_glyphs.retain(|_cached_glyph_key, cached_glyph| {
+      let (glyph_width, glyph_height) = cached_glyph.size;
+
+      let pixel_size = if cached_glyph.color {
+        COLOR_GLYPH_PIXEL_SIZE
+      } else {
+        1_usize
+      };
+
+      let pixel_count = if glyph_width == 0 || glyph_height == 0 {
+        0_usize
+      } else {
+        (glyph_width + (GLYPH_MARGIN << 1_i32) as u32) as usize
+          * (glyph_height + (GLYPH_MARGIN << 1_i32) as u32) as usize
+          * pixel_size
+      };
+
+      cached_glyph.pixels.len() == pixel_count
+    });
+
+    self.loaded_cached_glyphs = cached_glyphs;
+    self.used_cached_glyphs.clear();
+    self.atlas_cache_path = Some(atlas_cache_path);
+  }
+
+  /// Writes the glyphs used since `load_atlas_cache` that are still cached to its file, glyphs it
+  /// loaded that went unused are dropped
+  pub(super) fn save_atlas_cache(&self) -> io::Result<()> {
+    let Some(ref atlas_cache_path) = self.atlas_cache_path else {
+      return Ok(());
+    };
+
+    atlas_cache::write(
+      atlas_cache_path,
+      calc_atlas_cache_settings_hash(),
+      self.used_cached_glyphs.iter(),
+    )
+  }
+
+  pub(super) fn remove_text(&mut self, text_id: TextId) {
+    let TextEntry {
       glyph_ids,
       glyph_keys,
       clipped,
-    } = self.add_text(
-      &Text {
-        position: icon.position,
-        color: icon.color,
-        font_size: icon.font_size,
-        font_key: icon.font_key,
-        align: Align::Left,
-        text: String::from_utf16_lossy(&[icon.codepoint]).into(),
-      },
-      clipped,
-    );
+      ..
+    } = self.texts.remove(&text_id).unwrap();
+
+    self.get_glyph_sync(clipped).bulk_remove_models(&glyph_ids);
+    self.release_glyphs(glyph_keys);
+  }
+
+  pub(super) fn add_icon(&mut self, icon: Icon, clipped: bool) -> IconId {
+    let text = self.icon_to_text(icon);
 
     IconId {
-      glyph_id: glyph_ids[0],
-      glyph_key: glyph_keys[0].clone(),
-      clipped,
+      text_id: self.add_text(&text, clipped),
     }
   }
 
+  #[inline]
   pub(super) fn remove_icon(&mut self, icon_id: IconId) {
-    self.remove_text(TextId {
-      glyph_ids: Box::new([icon_id.glyph_id]),
-      glyph_keys: Box::new([icon_id.glyph_key]),
-      clipped: icon_id.clipped,
-    });
+    self.remove_text(icon_id.text_id);
   }
 
   #[inline]
-  pub(super) fn get_text_size(&self, text_id: &TextId) -> (f32, f32) {
-    self.text_sizes[text_id]
+  pub(super) fn get_text_size(&self, text_id: TextId) -> (f32, f32) {
+    let TextMetrics { width, height, .. } = self.texts[&text_id].metrics;
+    (width, height)
+  }
+
+  /// Index of the caret position closest to `point`, on the line closest to it
+  pub(super) fn text_hit_test(&self, text_id: TextId, point: (f32, f32)) -> CharIndex {
+    let TextEntry {
+      ref text,
+      ref metrics,
+      ref rtl_chars,
+      ..
+    } = self.texts[&text_id];
+
+    let (text_x, text_y, _text_z) = text.position;
+    let (point_x, point_y) = point;
+    text_layout::hit_test(metrics, rtl_chars, (point_x - text_x, point_y - text_y))
+  }
+
+  /// Thin rect spanning the line at the caret position before `char_index`
+  pub(super) fn get_caret_rect(&self, text_id: TextId, char_index: CharIndex) -> Rect {
+    let TextEntry {
+      ref text,
+      ref metrics,
+      ..
+    } = self.texts[&text_id];
+
+    offset_rect(text_layout::caret_rect(metrics, char_index), text.position)
+  }
+
+  /// Rects covering the chars in `char_range`, one for each visually contiguous piece of a line
+  pub(super) fn get_selection_rects(
+    &self,
+    text_id: TextId,
+    char_range: Range<CharIndex>,
+  ) -> Box<[Rect]> {
+    let TextEntry {
+      ref text,
+      ref metrics,
+      ref rtl_chars,
+      ..
+    } = self.texts[&text_id];
+
+    text_layout::selection_rects(metrics, rtl_chars, char_range)
+      .iter()
+      .map(|&rect| offset_rect(rect, text.position))
+      .collect()
   }
 
   #[inline]
-  pub(super) fn get_icon_size(&self, icon_id: &IconId) -> (f32, f32) {
-    self.get_text_size(&TextId {
-      glyph_ids: Box::new([icon_id.glyph_id]),
-      glyph_keys: Box::new([icon_id.glyph_key.clone()]),
-      clipped: icon_id.clipped,
-    })
+  pub(super) fn get_icon_size(&self, icon_id: IconId) -> (f32, f32) {
+    self.get_text_size(icon_id.text_id)
   }
 
   #[inline]
   pub(super) fn drop(self, vk_device: &ash::Device, vk_allocator: &vk_mem::Allocator) {
     self.glyph_atlas.drop(vk_device, vk_allocator);
+    self.color_glyph_atlas.drop(vk_device, vk_allocator);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{find_icon_char, shape_run};
+
+  static DEJAVU_SANS: &[u8] = include_bytes!("../tests/fonts/DejaVuSans.ttf");
+
+  fn calc_advance_sum(face: &rustybuzz::Face<'_>, text: &str) -> i32 {
+    shape_run(face, text, false)
+      .glyph_positions()
+      .iter()
+      .map(|glyph_pos| glyph_pos.x_advance)
+      .sum()
+  }
+
+  #[test]
+  fn kerned_pair_advances_less_than_its_chars() {
+    let face = rustybuzz::Face::from_slice(DEJAVU_SANS, 0).unwrap();
+
+    assert!(
+      calc_advance_sum(&face, "AV") < calc_advance_sum(&face, "A") + calc_advance_sum(&face, "V")
+    );
+  }
+
+  #[test]
+  fn ligature_shapes_to_one_glyph() {
+    let face = rustybuzz::Face::from_slice(DEJAVU_SANS, 0).unwrap();
+    let glyph_buffer = shape_run(&face, "fi", false);
+
+    assert_eq!(glyph_buffer.len(), 1);
+    assert_eq!(glyph_buffer.glyph_infos()[0].cluster, 0);
+  }
+
+  #[test]
+  fn rtl_run_comes_out_in_visual_order() {
+    let face = rustybuzz::Face::from_slice(DEJAVU_SANS, 0).unwrap();
+    let glyph_buffer = shape_run(&face, "\u{5D0}\u{5D1}", true);
+
+    let clusters = glyph_buffer
+      .glyph_infos()
+      .iter()
+      .map(|glyph_info| glyph_info.cluster)
+      .collect::<Vec<_>>();
+
+    assert_eq!(clusters, [2, 0]);
+  }
+
+  #[test]
+  fn icon_name_resolves_through_ligature() {
+    let face = rustybuzz::Face::from_slice(DEJAVU_SANS, 0).unwrap();
+    assert_eq!(find_icon_char(&face, "fi"), Some('\u{FB01}'));
+  }
+
+  #[test]
+  fn icon_name_resolves_through_glyph_name() {
+    let face = rustybuzz::Face::from_slice(DEJAVU_SANS, 0).unwrap();
+    assert_eq!(find_icon_char(&face, "Aring"), Some('\u{C5}'));
+  }
+
+  #[test]
+  fn unknown_icon_name_resolves_to_nothing() {
+    let face = rustybuzz::Face::from_slice(DEJAVU_SANS, 0).unwrap();
+    assert_eq!(find_icon_char(&face, "no_such_icon_name"), None);
+  }
+
+  #[test]
+  fn max_glyph_atlas_page_count_leaves_layers_for_every_frame() {
+    assert_eq!(super::calc_max_glyph_atlas_page_count(8, 1), 4);
+    assert_eq!(super::calc_max_glyph_atlas_page_count(2048, 1), 1024);
+  }
+
+  #[test]
+  fn max_glyph_atlas_page_count_stays_within_memory() {
+    // 512x512 coverage pages take 1 MiB each across frames in flight and staging
+    assert_eq!(super::calc_max_glyph_atlas_page_count(2048, 512 * 512), 64);
+    assert_eq!(
+      super::calc_max_glyph_atlas_page_count(2048, 512 * 512 * 4),
+      16
+    );
+  }
+
+  #[test]
+  fn max_glyph_atlas_page_count_keeps_first_page() {
+    assert_eq!(super::calc_max_glyph_atlas_page_count(1, 1), 1);
+    assert_eq!(
+      super::calc_max_glyph_atlas_page_count(2048, u64::MAX >> 4),
+      1
+    );
   }
 }
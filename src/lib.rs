@@ -114,16 +114,21 @@
 
 pub mod app;
 mod app_loop;
+mod atlas_allocator;
+mod atlas_cache;
 mod audio;
 pub mod collections;
+mod color_glyph;
 mod consts;
 mod glyph_renderer;
 mod model_sync;
 pub mod models;
+mod paint_sync;
 mod renderer;
 pub mod renderer_ref;
 mod sampled_image;
 pub mod sdf;
 mod storage_buffer;
+mod text_layout;
 pub mod utils;
 pub mod widgets;
@@ -5,6 +5,7 @@ use crate::{
   },
   consts,
   models::range::Range,
+  paint_sync,
   storage_buffer::StorageBuffer,
   utils,
 };
@@ -12,6 +13,22 @@ use ash::vk;
 use std::collections::VecDeque;
 use voracious_radix_sort::Radixable;
 
+// Translucent models are sorted after opaque ones so that they can be drawn from back to front
+const TRANSLUCENT_SORT_KEY: f32 = 2.0;
+
+/// Sort key of a model, which is translucent when its colour or the stops of its paint are
+/// - `z`: depth of the model
+/// - `color`: colour of the model
+/// - `paint_id`: paint of the model, or 0 when it has none
+#[inline]
+pub fn calc_sort_key(z: f32, color: u32, paint_id: u32) -> f32 {
+  if color & 0xFF == 0xFF && paint_id & paint_sync::TRANSLUCENT_PAINT_ID_FLAG == 0 {
+    z
+  } else {
+    TRANSLUCENT_SORT_KEY + 1.0 - z
+  }
+}
+
 pub struct ModelSync<Model> {
   models: SparseSet<Model>,
   changeset_queue: VecDeque<Vec<Range>>,
@@ -107,16 +124,31 @@ impl<Model: Clone> ModelSync<Model> {
 }
 
 impl<Model: Radixable<f32, Key = f32>> ModelSync<Model> {
+  #[inline]
+  pub(super) fn get_opaque_model_count(&self) -> usize {
+    self
+      .models
+      .get_items()
+      .partition_point(|model| model.key() < TRANSLUCENT_SORT_KEY)
+  }
+
+  /// Sort keys of the models from `start` on, in the order they are drawn
+  pub(super) fn get_sort_keys(&self, start: usize) -> Vec<f32> {
+    self.models.get_items()[start..]
+      .iter()
+      .map(Radixable::key)
+      .collect()
+  }
+
   pub(super) fn sync_to(
     &mut self,
     model_buffer: &StorageBuffer,
     vk_device: &ash::Device,
     model_buffer_offset: usize,
-    sort_models: bool,
   ) -> Option<vk::CommandBuffer> {
     let changeset = self.changeset_queue.back_mut().unwrap();
 
-    if sort_models && !changeset.is_empty() {
+    if !changeset.is_empty() {
       let SortResp { indices } = self.models.sort();
 
       changeset.extend(indices.into_iter().map(|index| Range {
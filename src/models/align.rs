@@ -1,5 +1,6 @@
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 pub enum Align {
+  #[default]
   Left,
   Center,
   Right,
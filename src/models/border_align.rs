@@ -0,0 +1,14 @@
+/// Where the border of a round rect is drawn relative to its edge
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
+pub enum BorderAlign {
+  /// The border is drawn inside the rect, which keeps its size
+  #[default]
+  Inner,
+
+  /// The border is centered on the edge of the rect
+  Center,
+
+  /// The border is drawn outside the rect, which grows by the border width
+  Outer,
+}
@@ -0,0 +1,3 @@
+/// Index of a char in a text, counted in chars rather than bytes, where the index after the last
+/// char is the end of the text
+pub type CharIndex = usize;
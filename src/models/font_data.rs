@@ -0,0 +1,49 @@
+use std::{ops::Deref, sync::Arc};
+
+/// Contents of a font file loaded from memory, which font keys share without copying
+#[derive(Clone, Debug)]
+pub enum FontData {
+  /// Font file embedded in the binary, such as with `include_font!`
+  Static(&'static [u8]),
+  Shared(Arc<[u8]>),
+}
+
+impl Deref for FontData {
+  type Target = [u8];
+
+  #[inline]
+  fn deref(&self) -> &[u8] {
+    match *self {
+      Self::Static(font_data) => font_data,
+      Self::Shared(ref font_data) => font_data,
+    }
+  }
+}
+
+impl From<&'static [u8]> for FontData {
+  #[inline]
+  fn from(font_data: &'static [u8]) -> Self {
+    Self::Static(font_data)
+  }
+}
+
+impl<const LEN: usize> From<&'static [u8; LEN]> for FontData {
+  #[inline]
+  fn from(font_data: &'static [u8; LEN]) -> Self {
+    Self::Static(font_data)
+  }
+}
+
+impl From<Arc<[u8]>> for FontData {
+  #[inline]
+  fn from(font_data: Arc<[u8]>) -> Self {
+    Self::Shared(font_data)
+  }
+}
+
+impl From<Vec<u8>> for FontData {
+  #[inline]
+  fn from(font_data: Vec<u8>) -> Self {
+    Self::Shared(font_data.into())
+  }
+}
@@ -1,3 +1,4 @@
+use crate::models::font_data::FontData;
 use font_kit::{
   family_name::FamilyName,
   properties::{Properties, Stretch, Weight},
@@ -7,13 +8,111 @@ use std::{
   hash::{Hash, Hasher},
 };
 
-#[derive(Clone, PartialEq)]
+/// Embeds a font file in the binary with `include_bytes!`, registered under a stable name. Font
+/// keys borrow the font file from the binary, which the font cache copies once when it loads it.
+/// - `name`: name the font is told apart from other fonts by
+/// - `path`: path of the font file, relative to the file calling this macro
+#[macro_export]
+macro_rules! include_font {
+  ($name:expr, $path:expr $(,)?) => {
+    $crate::models::font_key::FontKey::from_bytes($name, include_bytes!($path))
+  };
+}
+
+#[derive(Clone)]
 pub enum FontKey {
   Family {
     font_family: Cow<'static, [FamilyName]>,
     font_props: Properties,
   },
   Path(Cow<'static, str>),
+  /// Font loaded from memory, which is told apart from other fonts by its name only
+  Bytes {
+    name: Cow<'static, str>,
+    font_data: FontData,
+  },
+  /// Tries each font in order for every char, before falling back to an installed font with the
+  /// glyph. An empty list stands for the default font.
+  Fallback(Cow<'static, [Self]>),
+}
+
+impl Default for FontKey {
+  #[inline]
+  fn default() -> Self {
+    Self::Family {
+      font_family: (&[FamilyName::SansSerif]).into(),
+      font_props: Properties::default(),
+    }
+  }
+}
+
+impl FontKey {
+  /// Font loaded from memory, such as a font embedded with `include_font!`
+  /// - `name`: name the font is told apart from other fonts by, which must be unique
+  /// - `font_data`: contents of the font file, such as a `&'static [u8]` or `Arc<[u8]>`, which
+  ///   clones of the key share without copying
+  #[must_use]
+  #[inline]
+  pub fn from_bytes<Name: Into<Cow<'static, str>>, Data: Into<FontData>>(
+    name: Name,
+    font_data: Data,
+  ) -> Self {
+    Self::Bytes {
+      name: name.into(),
+      font_data: font_data.into(),
+    }
+  }
+
+  /// Fonts to try in order for each char, with nested fallback lists flattened. An empty fallback
+  /// list stands for the default font.
+  #[must_use]
+  pub fn flatten(&self) -> Vec<Self> {
+    fn flatten_into(font_key: &FontKey, font_keys: &mut Vec<FontKey>) {
+      if let FontKey::Fallback(ref fallback_font_keys) = *font_key {
+        for fallback_font_key in fallback_font_keys.iter() {
+          flatten_into(fallback_font_key, font_keys);
+        }
+      } else {
+        font_keys.push(font_key.clone());
+      }
+    }
+
+    let mut font_keys = vec![];
+    flatten_into(self, &mut font_keys);
+
+    if font_keys.is_empty() {
+      font_keys.push(Self::default());
+    }
+
+    font_keys
+  }
+}
+
+impl PartialEq for FontKey {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    match *self {
+      Self::Family {
+        ref font_family,
+        font_props,
+      } => matches!(
+        *other,
+        Self::Family {
+          font_family: ref other_font_family,
+          font_props: other_font_props,
+        } if font_family == other_font_family && font_props == other_font_props
+      ),
+      Self::Path(ref font_path) => {
+        matches!(*other, Self::Path(ref other_font_path) if font_path == other_font_path)
+      }
+      Self::Bytes { ref name, .. } => {
+        matches!(*other, Self::Bytes { name: ref other_name, .. } if name == other_name)
+      }
+      Self::Fallback(ref font_keys) => {
+        matches!(*other, Self::Fallback(ref other_font_keys) if font_keys == other_font_keys)
+      }
+    }
+  }
 }
 
 impl Hash for FontKey {
@@ -36,6 +135,8 @@ impl Hash for FontKey {
         stretch.to_bits().hash(state);
       }
       Self::Path(ref font_path) => font_path.hash(state),
+      Self::Bytes { ref name, .. } => name.hash(state),
+      Self::Fallback(ref font_keys) => font_keys.hash(state),
     }
   }
 }
@@ -0,0 +1,14 @@
+/// Vertical metrics of a font at a font size, in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FontMetrics {
+  /// Distance from the baseline to the top of the font, as a positive value
+  pub ascent: f32,
+  /// Distance from the baseline to the bottom of the font, as a positive value
+  pub descent: f32,
+  /// Extra gap the font suggests between lines
+  pub line_gap: f32,
+  /// Height of capital letters above the baseline
+  pub cap_height: f32,
+  /// Height of lowercase letters without ascenders above the baseline
+  pub x_height: f32,
+}
@@ -1,3 +1,4 @@
+use crate::model_sync;
 use std::cmp::Ordering;
 use voracious_radix_sort::Radixable;
 
@@ -8,19 +9,25 @@ pub struct Glyph {
   pub color: u32,
   pub size: (f32, f32),
   pub atlas_position: (f32, f32),
+  pub atlas_size: (f32, f32),
+  /// Top left corner of the text the glyph belongs to, which its paint is placed relative to
+  pub paint_origin: (f32, f32),
+  pub atlas_page: u32,
+  pub flags: u32,
+  pub paint_id: u32,
 }
 
 impl PartialEq for Glyph {
   #[inline]
   fn eq(&self, other: &Self) -> bool {
-    self.position.2 == other.position.2
+    self.key() == other.key()
   }
 }
 
 impl PartialOrd for Glyph {
   #[inline]
   fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-    self.position.2.partial_cmp(&other.position.2)
+    self.key().partial_cmp(&other.key())
   }
 }
 
@@ -29,7 +36,7 @@ impl Radixable<f32> for Glyph {
 
   #[inline]
   fn key(&self) -> Self::Key {
-    self.position.2
+    model_sync::calc_sort_key(self.position.2, self.color, self.paint_id)
   }
 }
 
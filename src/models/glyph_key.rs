@@ -4,8 +4,14 @@ use std::hash::{Hash, Hasher};
 #[derive(Clone, PartialEq)]
 pub struct GlyphKey {
   pub font_key: FontKey,
-  pub ch: char,
+  pub glyph_id: u32,
   pub font_size: f32,
+  /// Pixels the coverage grows outwards by, for outlines and the shadows of outlined text
+  pub dilation: f32,
+  /// Pixels the coverage is blurred by, for shadows
+  pub blur_radius: f32,
+  /// Text colour of colour glyphs with layers drawn in it, and 0 for every other glyph
+  pub foreground_color: u32,
 }
 
 impl Hash for GlyphKey {
@@ -13,13 +19,19 @@ impl Hash for GlyphKey {
   fn hash<H: Hasher>(&self, state: &mut H) {
     let Self {
       ref font_key,
-      ch,
+      glyph_id,
       font_size,
+      dilation,
+      blur_radius,
+      foreground_color,
     } = *self;
 
     font_key.hash(state);
-    ch.hash(state);
+    glyph_id.hash(state);
     font_size.to_bits().hash(state);
+    dilation.to_bits().hash(state);
+    blur_radius.to_bits().hash(state);
+    foreground_color.hash(state);
   }
 }
 
@@ -0,0 +1,10 @@
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum GlyphMode {
+  /// Glyphs are rasterized for each font size and scale factor, which looks sharpest
+  #[default]
+  Coverage,
+
+  /// Glyphs are rasterized once per font as signed distance fields and scaled freely, which suits
+  /// animated font sizes
+  Sdf,
+}
@@ -1,4 +1,4 @@
-use crate::models::font_key::FontKey;
+use crate::models::{font_key::FontKey, icon_glyph::IconGlyph, vertical_anchor::VerticalAnchor};
 
 #[derive(Clone)]
 pub struct Icon {
@@ -6,5 +6,6 @@ pub struct Icon {
   pub color: u32,
   pub font_size: f32,
   pub font_key: FontKey,
-  pub codepoint: u16,
+  pub vertical_anchor: VerticalAnchor,
+  pub glyph: IconGlyph,
 }
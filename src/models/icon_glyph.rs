@@ -0,0 +1,23 @@
+use std::borrow::Cow;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IconGlyph {
+  Char(char),
+  /// Icon looked up by name through the ligatures of its font, or its glyph names when it has no
+  /// such ligature, such as `shopping_cart`
+  Name(Cow<'static, str>),
+}
+
+impl From<char> for IconGlyph {
+  #[inline]
+  fn from(ch: char) -> Self {
+    Self::Char(ch)
+  }
+}
+
+impl From<&'static str> for IconGlyph {
+  #[inline]
+  fn from(name: &'static str) -> Self {
+    Self::Name(name.into())
+  }
+}
@@ -0,0 +1,105 @@
+use crate::{
+  model_sync::{self, ModelSync},
+  models::{Model, line_cap::LineCap, line_join::LineJoin},
+  renderer::Renderer,
+};
+use std::cmp::Ordering;
+use voracious_radix_sort::Radixable;
+
+/// Straight line segment, which joins the lines before and after it when they are given
+#[derive(Clone, Copy)]
+#[repr(C, align(8))]
+pub struct Line {
+  pub start: (f32, f32),
+  pub end: (f32, f32),
+  /// Offset from `start` to the point of the line before it, or zero to cap the start
+  pub prev_offset: (f32, f32),
+  /// Offset from `end` to the point of the line after it, or zero to cap the end
+  pub next_offset: (f32, f32),
+  pub z: f32,
+  pub thickness: f32,
+  pub color: u32,
+  pub cap: LineCap,
+  pub join: LineJoin,
+  /// Longest miter relative to half the thickness, longer miters are drawn as bevels
+  pub miter_limit: f32,
+  /// Length of each dash, or 0 for a solid line
+  pub dash_length: f32,
+  pub gap_length: f32,
+  /// Distance into the dash pattern at `start`
+  pub dash_offset: f32,
+  /// Paint that replaces the colour except for its alpha, or 0 to draw with the colour
+  pub paint_id: u32,
+  /// Offset from the top left corner of the bounds of `start` and `end` to the point the paint is
+  /// placed relative to, which lets the lines of a polyline share its bounds
+  pub paint_offset: (f32, f32),
+}
+
+impl Default for Line {
+  #[inline]
+  fn default() -> Self {
+    Self {
+      start: (0.0, 0.0),
+      end: (0.0, 0.0),
+      prev_offset: (0.0, 0.0),
+      next_offset: (0.0, 0.0),
+      z: 0.0,
+      thickness: 1.0,
+      color: 0x0000_00FF,
+      cap: LineCap::default(),
+      join: LineJoin::default(),
+      miter_limit: 4.0,
+      dash_length: 0.0,
+      gap_length: 0.0,
+      dash_offset: 0.0,
+      paint_id: 0,
+      paint_offset: (0.0, 0.0),
+    }
+  }
+}
+
+impl PartialEq for Line {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.key() == other.key()
+  }
+}
+
+impl PartialOrd for Line {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.key().partial_cmp(&other.key())
+  }
+}
+
+impl Radixable<f32> for Line {
+  type Key = f32;
+
+  #[inline]
+  fn key(&self) -> Self::Key {
+    model_sync::calc_sort_key(self.z, self.color, self.paint_id)
+  }
+}
+
+impl Model for Line {
+  #[inline]
+  fn get_vertex_count() -> usize {
+    6
+  }
+
+  #[inline]
+  fn get_sync<State>(renderer: &mut Renderer<State>) -> &mut ModelSync<Self>
+  where
+    Self: Sized,
+  {
+    renderer.get_line_sync()
+  }
+
+  #[inline]
+  fn get_clipped_sync<State>(renderer: &mut Renderer<State>) -> &mut ModelSync<Self>
+  where
+    Self: Sized,
+  {
+    renderer.get_clipped_line_sync()
+  }
+}
@@ -0,0 +1,14 @@
+/// How the ends of a line that don't join another line are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
+pub enum LineCap {
+  /// The line stops at its end point
+  #[default]
+  Butt,
+
+  /// The line ends in a half circle around its end point
+  Round,
+
+  /// The line goes on past its end point by half its thickness
+  Square,
+}
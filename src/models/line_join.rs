@@ -0,0 +1,14 @@
+/// How the outer corner between two joined lines is drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
+pub enum LineJoin {
+  /// The edges of both lines meet in a point, unless it is further than the miter limit allows
+  #[default]
+  Miter,
+
+  /// The corner is rounded by a circle around the joint
+  Round,
+
+  /// The corner is cut off straight
+  Bevel,
+}
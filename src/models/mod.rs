@@ -1,14 +1,37 @@
 pub mod align;
 pub mod audio_req;
+pub mod border_align;
+pub mod char_index;
+pub mod font_data;
 pub mod font_key;
+pub mod font_metrics;
 pub(super) mod glyph;
 pub(super) mod glyph_key;
+pub mod glyph_mode;
 pub mod icon;
+pub mod icon_glyph;
+pub mod line;
+pub mod line_cap;
+pub mod line_join;
 pub mod model_capacities;
+pub mod overflow;
+pub mod paint;
+pub mod paint_kind;
+pub mod polyline;
 pub(super) mod push_consts;
 pub mod range;
+pub mod rect;
+pub mod rich_text;
 pub mod round_rect;
+pub mod shadow;
 pub mod text;
+pub mod text_decoration;
+pub mod text_metrics;
+pub mod text_outline;
+pub mod text_shadow;
+pub mod text_span;
+pub mod vertical_anchor;
+pub mod wrap;
 
 use crate::{model_sync::ModelSync, renderer::Renderer};
 
@@ -1,12 +1,19 @@
-use crate::models::{glyph::Glyph, round_rect::RoundRect};
+use crate::models::{
+  glyph::Glyph, line::Line, paint::Paint, round_rect::RoundRect, shadow::Shadow,
+};
 use std::mem;
 
 #[derive(Clone, Copy)]
 pub struct ModelCapacities {
   pub round_rect_capacity: usize,
   pub clipped_round_rect_capacity: usize,
+  pub shadow_capacity: usize,
+  pub clipped_shadow_capacity: usize,
+  pub line_capacity: usize,
+  pub clipped_line_capacity: usize,
   pub glyph_capacity: usize,
   pub clipped_glyph_capacity: usize,
+  pub paint_capacity: usize,
 }
 
 impl Default for ModelCapacities {
@@ -15,8 +22,13 @@ impl Default for ModelCapacities {
     Self {
       round_rect_capacity: 1024,
       clipped_round_rect_capacity: 32,
+      shadow_capacity: 256,
+      clipped_shadow_capacity: 32,
+      line_capacity: 256,
+      clipped_line_capacity: 32,
       glyph_capacity: 1024,
       clipped_glyph_capacity: 32,
+      paint_capacity: 64,
     }
   }
 }
@@ -25,6 +37,9 @@ impl ModelCapacities {
   #[inline]
   pub(crate) const fn calc_bytes(self) -> usize {
     (self.round_rect_capacity + self.clipped_round_rect_capacity) * mem::size_of::<RoundRect>()
+      + (self.shadow_capacity + self.clipped_shadow_capacity) * mem::size_of::<Shadow>()
+      + (self.line_capacity + self.clipped_line_capacity) * mem::size_of::<Line>()
       + (self.glyph_capacity + self.clipped_glyph_capacity) * mem::size_of::<Glyph>()
+      + self.paint_capacity * mem::size_of::<Paint>()
   }
 }
@@ -0,0 +1,20 @@
+/// How a line wider than the max width of its text is fitted
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overflow {
+  /// The line is drawn in full past the max width
+  #[default]
+  Visible,
+
+  /// The line is cut off at the max width
+  Clip,
+
+  /// The end of the line is replaced with an ellipsis
+  Ellipsis,
+
+  /// The middle of the line is replaced with an ellipsis, keeping its start and end
+  MiddleEllipsis,
+
+  /// The whole text is scaled down to fit, but not below `min_font_size`, after which it is
+  /// clipped
+  ShrinkToFit { min_font_size: f32 },
+}
@@ -0,0 +1,36 @@
+use crate::models::paint_kind::PaintKind;
+
+/// Most colour stops a paint can have
+pub const MAX_PAINT_STOP_COUNT: usize = 4;
+
+/// Gradient that models reference by paint ID instead of filling with their colour
+///
+/// Its points are in logical pixels from the top left corner of the bounds of each model referencing
+/// it, so one paint fits every model of the same size. Text is painted across its whole bounds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C, align(8))]
+pub struct Paint {
+  pub kind: PaintKind,
+  /// Number of colour stops in use, from 1 to `MAX_PAINT_STOP_COUNT`
+  pub stop_count: u32,
+  /// Start of a linear gradient, or centre of a radial or conic gradient
+  pub start: (f32, f32),
+  /// End of a linear gradient, a point on the last circle of a radial gradient, or the direction a
+  /// conic gradient begins in
+  pub end: (f32, f32),
+  /// Positions of the colour stops in increasing order, from 0 at the start to 1 at the end
+  pub stop_offsets: [f32; MAX_PAINT_STOP_COUNT],
+  pub stop_colors: [u32; MAX_PAINT_STOP_COUNT],
+}
+
+impl Paint {
+  /// Whether any colour stop in use is translucent, which makes models filled with it translucent
+  #[must_use]
+  pub fn is_translucent(&self) -> bool {
+    self
+      .stop_colors
+      .iter()
+      .take(self.stop_count as usize)
+      .any(|&stop_color| stop_color & 0xFF != 0xFF)
+  }
+}
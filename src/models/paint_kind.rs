@@ -0,0 +1,14 @@
+/// How a paint maps positions to its colour stops
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
+pub enum PaintKind {
+  /// Colours change along the line from the start to the end
+  #[default]
+  LinearGradient,
+
+  /// Colours change with the distance from the start, reaching the last stop at the end
+  RadialGradient,
+
+  /// Colours change clockwise around the start, beginning in the direction of the end
+  ConicGradient,
+}
@@ -0,0 +1,121 @@
+use crate::models::{line::Line, line_cap::LineCap, line_join::LineJoin};
+use std::borrow::Cow;
+
+/// Connected lines sharing one style, added as one `Line` per segment through
+/// `RendererRef::bulk_add_models`
+#[derive(Clone)]
+pub struct Polyline {
+  pub points: Cow<'static, [(f32, f32)]>,
+  pub z: f32,
+  pub thickness: f32,
+  pub color: u32,
+  pub cap: LineCap,
+  pub join: LineJoin,
+  /// Longest miter relative to half the thickness, longer miters are drawn as bevels
+  pub miter_limit: f32,
+  /// Length of each dash, or 0 for a solid line
+  pub dash_length: f32,
+  pub gap_length: f32,
+  /// Distance into the dash pattern at the first point
+  pub dash_offset: f32,
+  /// Paint that replaces the colour except for its alpha, placed relative to the top left corner
+  /// of the bounds of the points, or 0 to draw with the colour
+  pub paint_id: u32,
+  /// Whether the last point joins back to the first
+  pub closed: bool,
+}
+
+impl Default for Polyline {
+  #[inline]
+  fn default() -> Self {
+    let line = Line::default();
+
+    Self {
+      points: Cow::default(),
+      z: line.z,
+      thickness: line.thickness,
+      color: line.color,
+      cap: line.cap,
+      join: line.join,
+      miter_limit: line.miter_limit,
+      dash_length: line.dash_length,
+      gap_length: line.gap_length,
+      dash_offset: line.dash_offset,
+      paint_id: line.paint_id,
+      closed: false,
+    }
+  }
+}
+
+impl Polyline {
+  /// Lines between each pair of consecutive points, joined to their neighbours and carrying the
+  /// dash pattern on from one to the next
+  #[must_use]
+  pub fn to_lines(&self) -> Box<[Line]> {
+    let point_count = self.points.len();
+
+    if point_count < 2 {
+      return Box::default();
+    }
+
+    let segment_count = if self.closed {
+      point_count
+    } else {
+      point_count - 1
+    };
+
+    let calc_offset = |(x, y): (f32, f32), point_index: usize| {
+      let (other_x, other_y) = self.points[point_index % point_count];
+      (other_x - x, other_y - y)
+    };
+
+    let (min_x, min_y) = self
+      .points
+      .iter()
+      .fold((f32::INFINITY, f32::INFINITY), |(min_x, min_y), &(x, y)| {
+        (min_x.min(x), min_y.min(y))
+      });
+
+    let mut dash_offset = self.dash_offset;
+
+    (0..segment_count)
+      .map(|segment_index| {
+        let start = self.points[segment_index];
+        let end = self.points[(segment_index + 1) % point_count];
+
+        let prev_offset = if segment_index > 0 || self.closed {
+          calc_offset(start, segment_index + point_count - 1)
+        } else {
+          (0.0, 0.0)
+        };
+
+        let next_offset = if segment_index + 2 < point_count || self.closed {
+          calc_offset(end, segment_index + 2)
+        } else {
+          (0.0, 0.0)
+        };
+
+        let line = Line {
+          start,
+          end,
+          prev_offset,
+          next_offset,
+          z: self.z,
+          thickness: self.thickness,
+          color: self.color,
+          cap: self.cap,
+          join: self.join,
+          miter_limit: self.miter_limit,
+          dash_length: self.dash_length,
+          gap_length: self.gap_length,
+          dash_offset,
+          paint_id: self.paint_id,
+          paint_offset: (min_x - start.0.min(end.0), min_y - start.1.min(end.1)),
+        };
+
+        dash_offset += (end.0 - start.0).hypot(end.1 - start.1);
+        line
+      })
+      .collect()
+  }
+}
@@ -4,8 +4,10 @@ use ash::vk;
 #[repr(C, align(8))]
 pub struct PushConsts {
   pub round_rect_buffer: vk::DeviceAddress,
+  pub shadow_buffer: vk::DeviceAddress,
   pub glyph_buffer: vk::DeviceAddress,
+  pub paint_buffer: vk::DeviceAddress,
+  pub line_buffer: vk::DeviceAddress,
   pub cam_size: (f32, f32),
   pub glyph_atlas_size: (f32, f32),
-  pub window_scale_factor: f32,
 }
@@ -0,0 +1,5 @@
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+  pub position: (f32, f32),
+  pub size: (f32, f32),
+}
@@ -0,0 +1,59 @@
+use crate::models::{
+  align::Align, overflow::Overflow, text::Text, text_span::TextSpan,
+  vertical_anchor::VerticalAnchor, wrap::Wrap,
+};
+use std::borrow::Cow;
+
+/// Text made of spans with their own font, size, colour and decoration, laid out on one shared
+/// baseline
+#[derive(Clone)]
+pub struct RichText {
+  pub position: (f32, f32, f32),
+  pub align: Align,
+  pub vertical_anchor: VerticalAnchor,
+  pub max_width: Option<f32>,
+  pub line_height: f32,
+  pub wrap: Wrap,
+  pub overflow: Overflow,
+  pub spans: Cow<'static, [TextSpan]>,
+}
+
+impl Default for RichText {
+  #[inline]
+  fn default() -> Self {
+    Self {
+      position: (0.0, 0.0, 0.0),
+      align: Align::default(),
+      vertical_anchor: VerticalAnchor::default(),
+      max_width: None,
+      line_height: 1.2,
+      wrap: Wrap::default(),
+      overflow: Overflow::default(),
+      spans: Cow::default(),
+    }
+  }
+}
+
+impl From<Text> for RichText {
+  #[inline]
+  fn from(text: Text) -> Self {
+    Self {
+      position: text.position,
+      align: text.align,
+      vertical_anchor: text.vertical_anchor,
+      max_width: text.max_width,
+      line_height: text.line_height,
+      wrap: text.wrap,
+      overflow: text.overflow,
+      spans: vec![TextSpan {
+        color: text.color,
+        paint_id: text.paint_id,
+        font_size: text.font_size,
+        font_key: text.font_key,
+        decoration: text.decoration,
+        text: text.text,
+      }]
+      .into(),
+    }
+  }
+}
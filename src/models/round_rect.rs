@@ -1,27 +1,38 @@
-use crate::{model_sync::ModelSync, models::Model, renderer::Renderer};
+use crate::{
+  model_sync::{self, ModelSync},
+  models::{Model, border_align::BorderAlign},
+  renderer::Renderer,
+};
 use std::cmp::Ordering;
 use voracious_radix_sort::Radixable;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Default)]
 #[repr(C, align(16))]
 pub struct RoundRect {
   pub position: (f32, f32, f32),
-  pub radius: f32,
-  pub size: (f32, f32),
   pub color: u32,
+  pub size: (f32, f32),
+  /// Corner radii in the order top left, top right, bottom right, bottom left
+  pub radius: [f32; 4],
+  pub border_width: f32,
+  pub border_color: u32,
+  pub border_align: BorderAlign,
+  /// Paint that replaces the fill colour except for its alpha, placed relative to the top left
+  /// corner of the rect, or 0 to fill with the colour
+  pub paint_id: u32,
 }
 
 impl PartialEq for RoundRect {
   #[inline]
   fn eq(&self, other: &Self) -> bool {
-    self.position.2 == other.position.2
+    self.key() == other.key()
   }
 }
 
 impl PartialOrd for RoundRect {
   #[inline]
   fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-    self.position.2.partial_cmp(&other.position.2)
+    self.key().partial_cmp(&other.key())
   }
 }
 
@@ -30,7 +41,14 @@ impl Radixable<f32> for RoundRect {
 
   #[inline]
   fn key(&self) -> Self::Key {
-    self.position.2
+    // A translucent border makes the whole rect translucent
+    let color = if self.border_width > 0.0 && self.border_color & 0xFF != 0xFF {
+      self.border_color
+    } else {
+      self.color
+    };
+
+    model_sync::calc_sort_key(self.position.2, color, self.paint_id)
   }
 }
 
@@ -0,0 +1,72 @@
+use crate::{
+  model_sync::{self, ModelSync},
+  models::Model,
+  renderer::Renderer,
+};
+use std::cmp::Ordering;
+use voracious_radix_sort::Radixable;
+
+/// Soft shadow cast by a rounded rectangle
+#[derive(Clone, Copy, Default)]
+#[repr(C, align(16))]
+pub struct Shadow {
+  /// Position of the rect casting the shadow
+  pub position: (f32, f32, f32),
+  pub color: u32,
+  /// Size of the rect casting the shadow
+  pub size: (f32, f32),
+  /// Corner radii in the order top left, top right, bottom right, bottom left
+  pub radius: [f32; 4],
+  pub offset: (f32, f32),
+  /// Distance over which the edge fades out, which is twice the standard deviation of the blur
+  pub blur_radius: f32,
+  /// Distance the shadow grows past the rect before it is blurred, or shrinks when negative
+  pub spread: f32,
+}
+
+impl PartialEq for Shadow {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.key() == other.key()
+  }
+}
+
+impl PartialOrd for Shadow {
+  #[inline]
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.key().partial_cmp(&other.key())
+  }
+}
+
+impl Radixable<f32> for Shadow {
+  type Key = f32;
+
+  #[inline]
+  fn key(&self) -> Self::Key {
+    // Soft edges always have to be blended, even when the colour is opaque
+    model_sync::calc_sort_key(self.position.2, self.color & 0xFFFF_FF00, 0)
+  }
+}
+
+impl Model for Shadow {
+  #[inline]
+  fn get_vertex_count() -> usize {
+    6
+  }
+
+  #[inline]
+  fn get_sync<State>(renderer: &mut Renderer<State>) -> &mut ModelSync<Self>
+  where
+    Self: Sized,
+  {
+    renderer.get_shadow_sync()
+  }
+
+  #[inline]
+  fn get_clipped_sync<State>(renderer: &mut Renderer<State>) -> &mut ModelSync<Self>
+  where
+    Self: Sized,
+  {
+    renderer.get_clipped_shadow_sync()
+  }
+}
@@ -1,12 +1,44 @@
-use crate::models::{align::Align, font_key::FontKey};
+use crate::models::{
+  align::Align, font_key::FontKey, overflow::Overflow, text_decoration::TextDecoration,
+  vertical_anchor::VerticalAnchor, wrap::Wrap,
+};
 use std::borrow::Cow;
 
 #[derive(Clone)]
 pub struct Text {
   pub position: (f32, f32, f32),
   pub color: u32,
+  /// Paint that replaces the colour except for its alpha, or 0 to draw with the colour
+  pub paint_id: u32,
   pub font_size: f32,
   pub font_key: FontKey,
   pub align: Align,
+  pub vertical_anchor: VerticalAnchor,
+  pub max_width: Option<f32>,
+  pub line_height: f32,
+  pub wrap: Wrap,
+  pub overflow: Overflow,
+  pub decoration: TextDecoration,
   pub text: Cow<'static, str>,
 }
+
+impl Default for Text {
+  #[inline]
+  fn default() -> Self {
+    Self {
+      position: (0.0, 0.0, 0.0),
+      color: 0x0000_00FF,
+      paint_id: 0,
+      font_size: 16.0,
+      font_key: FontKey::default(),
+      align: Align::default(),
+      vertical_anchor: VerticalAnchor::default(),
+      max_width: None,
+      line_height: 1.2,
+      wrap: Wrap::default(),
+      overflow: Overflow::default(),
+      decoration: TextDecoration::default(),
+      text: Cow::default(),
+    }
+  }
+}
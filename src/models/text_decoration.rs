@@ -0,0 +1,10 @@
+use crate::models::{text_outline::TextOutline, text_shadow::TextShadow};
+
+/// Lines and effects drawn with a text, underline and strikethrough follow the metrics of its font
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextDecoration {
+  pub underline: bool,
+  pub strikethrough: bool,
+  pub outline: Option<TextOutline>,
+  pub shadow: Option<TextShadow>,
+}
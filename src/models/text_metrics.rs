@@ -0,0 +1,16 @@
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextMetrics {
+  pub width: f32,
+  pub height: f32,
+  /// Distance from the baseline to the top of the font, as a positive value
+  pub ascent: f32,
+  /// Distance from the baseline to the bottom of the font, as a positive value
+  pub descent: f32,
+  /// Distance from the top of the text to its first baseline
+  pub baseline: f32,
+  /// Horizontal advance of each char, where a ligature advances by its first char
+  pub advances: Box<[f32]>,
+  /// Caret position before each char and after the last char, relative to the text position and
+  /// on the baseline of its line
+  pub carets: Box<[(f32, f32)]>,
+}
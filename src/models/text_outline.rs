@@ -0,0 +1,6 @@
+/// Stroke drawn around the outside of each glyph of a text
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextOutline {
+  pub width: f32,
+  pub color: u32,
+}
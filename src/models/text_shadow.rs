@@ -0,0 +1,7 @@
+/// Blurred copy of a text drawn behind it, colour glyphs cast no shadow
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextShadow {
+  pub offset: (f32, f32),
+  pub blur_radius: f32,
+  pub color: u32,
+}
@@ -0,0 +1,27 @@
+use crate::models::{font_key::FontKey, text_decoration::TextDecoration};
+use std::borrow::Cow;
+
+#[derive(Clone)]
+pub struct TextSpan {
+  pub color: u32,
+  /// Paint that replaces the colour except for its alpha, or 0 to draw with the colour
+  pub paint_id: u32,
+  pub font_size: f32,
+  pub font_key: FontKey,
+  pub decoration: TextDecoration,
+  pub text: Cow<'static, str>,
+}
+
+impl Default for TextSpan {
+  #[inline]
+  fn default() -> Self {
+    Self {
+      color: 0x0000_00FF,
+      paint_id: 0,
+      font_size: 16.0,
+      font_key: FontKey::default(),
+      decoration: TextDecoration::default(),
+      text: Cow::default(),
+    }
+  }
+}
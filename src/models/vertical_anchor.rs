@@ -0,0 +1,16 @@
+/// Which line of a text its position is on, measured with the ascent and descent of its fonts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAnchor {
+  /// Top of the first line
+  Top,
+
+  /// Halfway between the top of the first line and the bottom of the last line
+  Middle,
+
+  /// Baseline of the first line
+  #[default]
+  Baseline,
+
+  /// Bottom of the last line
+  Bottom,
+}
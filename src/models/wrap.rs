@@ -0,0 +1,7 @@
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Wrap {
+  #[default]
+  Word,
+  Char,
+  None,
+}
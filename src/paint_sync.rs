@@ -0,0 +1,181 @@
+use crate::{
+  consts, models::paint::Paint, models::range::Range, storage_buffer::StorageBuffer, utils,
+};
+use ash::vk;
+use std::collections::VecDeque;
+
+/// Bit set in the IDs of paints with translucent stops, so that models referencing them are sorted
+/// as translucent
+pub const TRANSLUCENT_PAINT_ID_FLAG: u32 = 1 << 31_u32;
+
+/// Keeps paints at fixed indices in the model buffer, which models reference as paint ID - 1 so
+/// that paint ID 0 can mean no paint
+pub struct PaintSync {
+  paints: Vec<Paint>,
+  paint_capacity: usize,
+  free_indices: Vec<u32>,
+  changeset_queue: VecDeque<Vec<Range>>,
+}
+
+impl PaintSync {
+  pub(super) fn new(paint_capacity: usize) -> Self {
+    Self {
+      paints: Vec::with_capacity(paint_capacity),
+      paint_capacity,
+      free_indices: vec![],
+      changeset_queue: VecDeque::from_iter([vec![]]),
+    }
+  }
+
+  fn push_change(&mut self, index: u32) {
+    let changeset = self.changeset_queue.back_mut().unwrap();
+
+    changeset.push(Range {
+      start: index,
+      end: index + 1,
+    });
+  }
+
+  /// Index in the model buffer of a paint that has not been removed
+  fn to_index(&self, id: u32) -> u32 {
+    let index = id & !TRANSLUCENT_PAINT_ID_FLAG;
+    assert!(index != 0, "Paint ID 0 means no paint");
+    let index = index - 1;
+
+    assert!(
+      (index as usize) < self.paints.len() && !self.free_indices.contains(&index),
+      "Paint {id} does not exist"
+    );
+
+    index
+  }
+
+  pub(super) fn add_paint(&mut self, paint: Paint) -> u32 {
+    let index = if let Some(index) = self.free_indices.pop() {
+      self.paints[index as usize] = paint;
+      index
+    } else {
+      assert!(
+        self.paints.len() < self.paint_capacity,
+        "Paint capacity of {paint_capacity} is full",
+        paint_capacity = self.paint_capacity
+      );
+
+      self.paints.push(paint);
+      (self.paints.len() - 1).try_into().unwrap()
+    };
+
+    self.push_change(index);
+
+    if paint.is_translucent() {
+      (index + 1) | TRANSLUCENT_PAINT_ID_FLAG
+    } else {
+      index + 1
+    }
+  }
+
+  /// Replaces a paint, which has to stay opaque or translucent since models referencing it are
+  /// sorted by that
+  pub(super) fn update_paint(&mut self, id: u32, paint: Paint) {
+    let index = self.to_index(id);
+
+    assert!(
+      paint.is_translucent() == (id & TRANSLUCENT_PAINT_ID_FLAG != 0),
+      "Paint {id} cannot change between opaque and translucent stops"
+    );
+
+    self.paints[index as usize] = paint;
+    self.push_change(index);
+  }
+
+  /// Frees the index of a paint without touching the model buffer, since no model should reference
+  /// it anymore
+  pub(super) fn remove_paint(&mut self, id: u32) -> Paint {
+    let index = self.to_index(id);
+    self.free_indices.push(index);
+    self.paints[index as usize]
+  }
+
+  pub(super) fn sync_to(
+    &mut self,
+    model_buffer: &StorageBuffer,
+    vk_device: &ash::Device,
+    model_buffer_offset: usize,
+  ) -> Option<vk::CommandBuffer> {
+    utils::coalesce_ranges(self.changeset_queue.back_mut().unwrap());
+
+    let mut all_changeset = self
+      .changeset_queue
+      .iter()
+      .flatten()
+      .copied()
+      .collect::<Vec<_>>();
+
+    utils::coalesce_ranges(&mut all_changeset);
+
+    let transfer_command_buffer =
+      model_buffer.write(vk_device, &self.paints, &all_changeset, model_buffer_offset);
+
+    if self.changeset_queue.len() >= consts::MAX_IN_FLIGHT_FRAME_COUNT {
+      self.changeset_queue.pop_front();
+    }
+
+    self.changeset_queue.push_back(vec![]);
+    transfer_command_buffer
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{PaintSync, TRANSLUCENT_PAINT_ID_FLAG};
+  use crate::models::paint::Paint;
+
+  fn make_paint(stop_colors: [u32; 2]) -> Paint {
+    Paint {
+      stop_count: 2,
+      stop_offsets: [0.0, 1.0, 0.0, 0.0],
+      stop_colors: [stop_colors[0], stop_colors[1], 0, 0],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn translucent_paint_ids_are_flagged() {
+    let mut paint_sync = PaintSync::new(2);
+    let opaque_paint_id = paint_sync.add_paint(make_paint([0xFF00_00FF, 0x0000_FFFF]));
+    let translucent_paint_id = paint_sync.add_paint(make_paint([0xFF00_00FF, 0x0000_FF80]));
+
+    assert_eq!(opaque_paint_id, 1);
+    assert_eq!(translucent_paint_id, 2 | TRANSLUCENT_PAINT_ID_FLAG);
+  }
+
+  #[test]
+  fn unused_stops_do_not_make_paint_translucent() {
+    let paint = Paint {
+      stop_count: 1,
+      ..make_paint([0xFF00_00FF, 0x0000_FF00])
+    };
+
+    assert!(!paint.is_translucent());
+  }
+
+  #[test]
+  fn removed_translucent_paint_id_is_reused() {
+    let mut paint_sync = PaintSync::new(1);
+    let paint_id = paint_sync.add_paint(make_paint([0xFF00_0080, 0x0000_FFFF]));
+    paint_sync.remove_paint(paint_id);
+
+    assert_eq!(
+      paint_sync.add_paint(make_paint([0xFF00_00FF, 0x0000_FFFF])),
+      1
+    );
+  }
+
+  #[test]
+  #[should_panic = "cannot change between opaque and translucent stops"]
+  fn updating_paint_keeps_its_translucency() {
+    let mut paint_sync = PaintSync::new(1);
+    let paint_id = paint_sync.add_paint(make_paint([0xFF00_00FF, 0x0000_FFFF]));
+    paint_sync.update_paint(paint_id, make_paint([0xFF00_0080, 0x0000_FFFF]));
+  }
+}
@@ -3,16 +3,21 @@ use crate::{
   glyph_renderer::GlyphRenderer,
   model_sync::ModelSync,
   models::{
-    Model as _, glyph::Glyph, model_capacities::ModelCapacities, push_consts::PushConsts,
-    round_rect::RoundRect,
+    Model as _, glyph::Glyph, glyph_mode::GlyphMode, line::Line, model_capacities::ModelCapacities,
+    push_consts::PushConsts, round_rect::RoundRect, shadow::Shadow,
   },
+  paint_sync::PaintSync,
+  sampled_image::SampledImage,
   storage_buffer::StorageBuffer,
 };
 use ash::{khr, vk};
+use image::RgbaImage;
 use rustc_hash::FxHashSet;
 use std::{
-  ffi::{CStr, CString, c_char},
-  iter, mem, ptr, slice,
+  ffi::{CStr, CString, c_char, c_void},
+  iter, mem,
+  ops::Range,
+  ptr, slice,
 };
 use vk_mem::Alloc as _;
 use winit::{
@@ -46,29 +51,59 @@ const DYNAMIC_STATES: &[vk::DynamicState] = &[
   vk::DynamicState::DEPTH_COMPARE_OP,
 ];
 
+// Offscreen settings
+const OFFSCREEN_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
 struct WindowMinimized;
 
-struct Shared {
+struct WindowSurface {
   window: Window,
-  _vk_entry: ash::Entry,
-  vk_instance: ash::Instance,
   vk_surface_instance: khr::surface::Instance,
   vk_surface: vk::SurfaceKHR,
+  present_queue_family_index: u32,
+  present_queue: vk::Queue,
+  swapchain_format: vk::SurfaceFormatKHR,
+  vk_swapchain_device: khr::swapchain::Device,
+}
+
+enum Output {
+  Window(WindowSurface),
+  Offscreen { size: (f64, f64), scale_factor: f64 },
+}
+
+/// Byte offsets of each model type in the model buffer
+#[derive(Clone, Copy)]
+struct ModelBufferOffsets {
+  round_rect: usize,
+  shadow: usize,
+  line: usize,
+  glyph: usize,
+  clipped_round_rect: usize,
+  clipped_shadow: usize,
+  clipped_line: usize,
+  clipped_glyph: usize,
+  paint: usize,
+}
+
+struct Shared {
+  output: Output,
+  _vk_entry: ash::Entry,
+  vk_instance: ash::Instance,
   vk_physical_device: vk::PhysicalDevice,
   graphics_queue_family_index: u32,
-  present_queue_family_index: u32,
   transfer_queue_family_index: u32,
   vk_device: ash::Device,
   graphics_queue: vk::Queue,
-  present_queue: vk::Queue,
   transfer_queue: vk::Queue,
-  swapchain_format: vk::SurfaceFormatKHR,
-  vk_swapchain_device: khr::swapchain::Device,
+  color_format: vk::Format,
+  color_final_layout: vk::ImageLayout,
   sampler: vk::Sampler,
   descriptor_set_layout: vk::DescriptorSetLayout,
   pipeline_layout: vk::PipelineLayout,
   render_pass: vk::RenderPass,
-  graphics_pipeline: vk::Pipeline,
+  opaque_graphics_pipeline: vk::Pipeline,
+  translucent_graphics_pipeline: vk::Pipeline,
+  clip_depth_graphics_pipeline: vk::Pipeline,
   descriptor_pool: vk::DescriptorPool,
   descriptor_sets: Box<[vk::DescriptorSet]>,
   init_done_semaphore: vk::Semaphore,
@@ -76,7 +111,13 @@ struct Shared {
   model_buffer: StorageBuffer,
   round_rect_sync: ModelSync<RoundRect>,
   clipped_round_rect_sync: ModelSync<RoundRect>,
+  shadow_sync: ModelSync<Shadow>,
+  clipped_shadow_sync: ModelSync<Shadow>,
+  line_sync: ModelSync<Line>,
+  clipped_line_sync: ModelSync<Line>,
+  paint_sync: PaintSync,
   glyph_renderer: GlyphRenderer,
+  max_msaa_sample_count: vk::SampleCountFlags,
   msaa_sample_count: vk::SampleCountFlags,
   model_capacities: ModelCapacities,
   glyph_atlas_size: (u16, u16),
@@ -89,59 +130,458 @@ struct Shared {
   frame_index: usize,
 }
 
+/// Creates the render pass along with the opaque and translucent graphics pipelines, which all
+/// depend on the MSAA sample count
+fn create_render_pass_and_pipelines(
+  vk_device: &ash::Device,
+  color_format: vk::Format,
+  color_final_layout: vk::ImageLayout,
+  msaa_sample_count: vk::SampleCountFlags,
+  pipeline_layout: vk::PipelineLayout,
+) -> (vk::RenderPass, vk::Pipeline, vk::Pipeline, vk::Pipeline) {
+  let vert_shader_module_create_info = vk::ShaderModuleCreateInfo {
+    code_size: VERT_SHADER_CODE.len(),
+    p_code: VERT_SHADER_CODE.as_ptr().cast(),
+    ..Default::default()
+  };
+
+  let frag_shader_module_create_info = vk::ShaderModuleCreateInfo {
+    code_size: FRAG_SHADER_CODE.len(),
+    p_code: FRAG_SHADER_CODE.as_ptr().cast(),
+    ..Default::default()
+  };
+
+  let vert_shader_module = unsafe {
+    vk_device
+      .create_shader_module(&vert_shader_module_create_info, None)
+      .unwrap()
+  };
+
+  let frag_shader_module = unsafe {
+    vk_device
+      .create_shader_module(&frag_shader_module_create_info, None)
+      .unwrap()
+  };
+
+  let main_name = CString::new("main").unwrap();
+
+  let shader_stage_create_infos = [
+    vk::PipelineShaderStageCreateInfo {
+      stage: vk::ShaderStageFlags::VERTEX,
+      module: vert_shader_module,
+      p_name: main_name.as_ptr(),
+      ..Default::default()
+    },
+    vk::PipelineShaderStageCreateInfo {
+      stage: vk::ShaderStageFlags::FRAGMENT,
+      module: frag_shader_module,
+      p_name: main_name.as_ptr(),
+      ..Default::default()
+    },
+  ];
+
+  let vert_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::default();
+
+  let input_assembly_state_create_info = vk::PipelineInputAssemblyStateCreateInfo {
+    topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+    ..Default::default()
+  };
+
+  let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
+    viewport_count: 1,
+    scissor_count: 1,
+    ..Default::default()
+  };
+
+  let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo {
+    front_face: vk::FrontFace::CLOCKWISE,
+    line_width: 1.0,
+    ..Default::default()
+  };
+
+  let opaque_multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+    rasterization_samples: msaa_sample_count,
+    alpha_to_coverage_enable: vk::TRUE,
+    alpha_to_one_enable: vk::TRUE,
+    ..Default::default()
+  };
+
+  // Translucent models rely on blending instead, as alpha to coverage would turn them opaque
+  let translucent_multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+    rasterization_samples: msaa_sample_count,
+    ..Default::default()
+  };
+
+  let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo {
+    depth_test_enable: vk::TRUE,
+    ..Default::default()
+  };
+
+  let opaque_color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+    color_write_mask: vk::ColorComponentFlags::RGBA,
+    ..Default::default()
+  }];
+
+  let opaque_color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo {
+    attachment_count: opaque_color_blend_attachment_states
+      .len()
+      .try_into()
+      .unwrap(),
+    p_attachments: opaque_color_blend_attachment_states.as_ptr(),
+    ..Default::default()
+  };
+
+  let translucent_color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+    blend_enable: vk::TRUE,
+    src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+    dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+    color_blend_op: vk::BlendOp::ADD,
+    src_alpha_blend_factor: vk::BlendFactor::ONE,
+    dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+    alpha_blend_op: vk::BlendOp::ADD,
+    color_write_mask: vk::ColorComponentFlags::RGBA,
+  }];
+
+  let translucent_color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo {
+    attachment_count: translucent_color_blend_attachment_states
+      .len()
+      .try_into()
+      .unwrap(),
+    p_attachments: translucent_color_blend_attachment_states.as_ptr(),
+    ..Default::default()
+  };
+
+  // Translucent clip hosts only write their depth, so that clipped models can be tested against it
+  let clip_depth_color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState::default()];
+
+  let clip_depth_color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo {
+    attachment_count: clip_depth_color_blend_attachment_states
+      .len()
+      .try_into()
+      .unwrap(),
+    p_attachments: clip_depth_color_blend_attachment_states.as_ptr(),
+    ..Default::default()
+  };
+
+  let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo {
+    dynamic_state_count: DYNAMIC_STATES.len().try_into().unwrap(),
+    p_dynamic_states: DYNAMIC_STATES.as_ptr(),
+    ..Default::default()
+  };
+
+  // Attachment 0: MSAA color attachment (or direct if 1x)
+  // Attachment 1: Depth attachment
+  // Attachment 2: Resolve attachment (swapchain image) - only used when MSAA > 1x
+  let mut attachment_descs = vec![if msaa_sample_count == vk::SampleCountFlags::TYPE_1 {
+    // Color attachment
+    vk::AttachmentDescription2 {
+      format: color_format,
+      samples: vk::SampleCountFlags::TYPE_1,
+      load_op: vk::AttachmentLoadOp::CLEAR,
+      store_op: vk::AttachmentStoreOp::STORE,
+      stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+      stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+      initial_layout: vk::ImageLayout::UNDEFINED,
+      final_layout: color_final_layout,
+      ..Default::default()
+    }
+  } else {
+    // MSAA color attachment
+    vk::AttachmentDescription2 {
+      format: color_format,
+      samples: msaa_sample_count,
+      load_op: vk::AttachmentLoadOp::CLEAR,
+      store_op: vk::AttachmentStoreOp::DONT_CARE,
+      stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+      stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+      initial_layout: vk::ImageLayout::UNDEFINED,
+      final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+      ..Default::default()
+    }
+  }];
+
+  // Depth attachment
+  attachment_descs.push(vk::AttachmentDescription2 {
+    format: vk::Format::D16_UNORM,
+    samples: msaa_sample_count,
+    load_op: vk::AttachmentLoadOp::CLEAR,
+    store_op: vk::AttachmentStoreOp::DONT_CARE,
+    stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+    stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+    initial_layout: vk::ImageLayout::UNDEFINED,
+    final_layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+    ..Default::default()
+  });
+
+  if msaa_sample_count != vk::SampleCountFlags::TYPE_1 {
+    // Resolve attachment (swapchain image)
+    attachment_descs.push(vk::AttachmentDescription2 {
+      format: color_format,
+      samples: vk::SampleCountFlags::TYPE_1,
+      load_op: vk::AttachmentLoadOp::DONT_CARE,
+      store_op: vk::AttachmentStoreOp::STORE,
+      stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+      stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+      initial_layout: vk::ImageLayout::UNDEFINED,
+      final_layout: color_final_layout,
+      ..Default::default()
+    });
+  }
+
+  let attachment_descs = attachment_descs;
+
+  let color_attachment_refs = [vk::AttachmentReference2 {
+    attachment: 0,
+    layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    ..Default::default()
+  }];
+
+  let depth_attachment_ref = vk::AttachmentReference2 {
+    attachment: 1,
+    layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+    ..Default::default()
+  };
+
+  let resolve_attachment_refs = [vk::AttachmentReference2 {
+    attachment: 2,
+    layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    ..Default::default()
+  }];
+
+  let subpass_descs = [vk::SubpassDescription2 {
+    pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+    color_attachment_count: color_attachment_refs.len().try_into().unwrap(),
+    p_color_attachments: color_attachment_refs.as_ptr(),
+    p_depth_stencil_attachment: &raw const depth_attachment_ref,
+    p_resolve_attachments: if msaa_sample_count == vk::SampleCountFlags::TYPE_1 {
+      ptr::null()
+    } else {
+      resolve_attachment_refs.as_ptr()
+    },
+    ..Default::default()
+  }];
+
+  let subpass_deps = [vk::SubpassDependency2 {
+    src_subpass: vk::SUBPASS_EXTERNAL,
+    dst_subpass: 0,
+    src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+      | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+    dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+      | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+    src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+      | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+      | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    dependency_flags: vk::DependencyFlags::BY_REGION,
+    ..Default::default()
+  }];
+
+  let render_pass_create_info = vk::RenderPassCreateInfo2 {
+    attachment_count: attachment_descs.len().try_into().unwrap(),
+    p_attachments: attachment_descs.as_ptr(),
+    subpass_count: subpass_descs.len().try_into().unwrap(),
+    p_subpasses: subpass_descs.as_ptr(),
+    dependency_count: subpass_deps.len().try_into().unwrap(),
+    p_dependencies: subpass_deps.as_ptr(),
+    ..Default::default()
+  };
+
+  let render_pass = unsafe {
+    vk_device
+      .create_render_pass2(&render_pass_create_info, None)
+      .unwrap()
+  };
+
+  let graphics_pipeline_create_infos = [
+    (
+      &opaque_multisample_state_create_info,
+      &opaque_color_blend_state_create_info,
+    ),
+    (
+      &translucent_multisample_state_create_info,
+      &translucent_color_blend_state_create_info,
+    ),
+    (
+      &translucent_multisample_state_create_info,
+      &clip_depth_color_blend_state_create_info,
+    ),
+  ]
+  .map(
+    |(multisample_state_create_info, color_blend_state_create_info)| {
+      vk::GraphicsPipelineCreateInfo {
+        stage_count: shader_stage_create_infos.len().try_into().unwrap(),
+        p_stages: shader_stage_create_infos.as_ptr(),
+        p_vertex_input_state: &raw const vert_input_state_create_info,
+        p_input_assembly_state: &raw const input_assembly_state_create_info,
+        p_viewport_state: &raw const viewport_state_create_info,
+        p_rasterization_state: &raw const rasterization_state_create_info,
+        p_multisample_state: multisample_state_create_info,
+        p_depth_stencil_state: &raw const depth_stencil_state_create_info,
+        p_color_blend_state: color_blend_state_create_info,
+        p_dynamic_state: &raw const dynamic_state_create_info,
+        layout: pipeline_layout,
+        render_pass,
+        subpass: 0,
+        base_pipeline_index: -1,
+        ..Default::default()
+      }
+    },
+  );
+
+  let graphics_pipelines = unsafe {
+    vk_device
+      .create_graphics_pipelines(
+        vk::PipelineCache::null(),
+        &graphics_pipeline_create_infos,
+        None,
+      )
+      .unwrap()
+  };
+
+  let (opaque_graphics_pipeline, translucent_graphics_pipeline, clip_depth_graphics_pipeline) = (
+    graphics_pipelines[0],
+    graphics_pipelines[1],
+    graphics_pipelines[2],
+  );
+
+  unsafe {
+    vk_device.destroy_shader_module(frag_shader_module, None);
+  }
+  unsafe {
+    vk_device.destroy_shader_module(vert_shader_module, None);
+  }
+
+  (
+    render_pass,
+    opaque_graphics_pipeline,
+    translucent_graphics_pipeline,
+    clip_depth_graphics_pipeline,
+  )
+}
+
+/// Splits the translucent models of every type into runs of one type each, in the order they are
+/// blended from back to front. Models at the same depth keep the order of their types.
+/// - `translucent_models`: range of the translucent models of each type with their sort keys,
+///   which ascend from back to front
+fn merge_translucent_draw_runs(
+  translucent_models: &[(Range<usize>, &[f32])],
+) -> Vec<(usize, Range<usize>)> {
+  let mut sort_key_indices = vec![0; translucent_models.len()];
+  let mut draw_runs = Vec::<(usize, Range<usize>)>::new();
+
+  loop {
+    // Earlier types win ties, as only a strictly smaller key takes over
+    let mut next_type_index = None;
+    let mut next_sort_key = f32::INFINITY;
+
+    for (type_index, &(_, sort_keys)) in translucent_models.iter().enumerate() {
+      if let Some(&sort_key) = sort_keys.get(sort_key_indices[type_index])
+        && (next_type_index.is_none() || sort_key < next_sort_key)
+      {
+        next_type_index = Some(type_index);
+        next_sort_key = sort_key;
+      }
+    }
+
+    let Some(type_index) = next_type_index else {
+      return draw_runs;
+    };
+
+    let model_index = translucent_models[type_index].0.start + sort_key_indices[type_index];
+    sort_key_indices[type_index] += 1;
+
+    match draw_runs.last_mut() {
+      Some(&mut (run_type_index, ref mut model_range)) if run_type_index == type_index => {
+        model_range.end = model_index + 1;
+      }
+      _ => draw_runs.push((type_index, model_index..model_index + 1)),
+    }
+  }
+}
+
+fn choose_msaa_sample_count(
+  max_msaa_sample_count: vk::SampleCountFlags,
+  window_scale_factor: f32,
+) -> vk::SampleCountFlags {
+  if max_msaa_sample_count.contains(vk::SampleCountFlags::TYPE_4) && window_scale_factor < 2.0 {
+    vk::SampleCountFlags::TYPE_4
+  } else if max_msaa_sample_count.contains(vk::SampleCountFlags::TYPE_2) {
+    vk::SampleCountFlags::TYPE_2
+  } else {
+    vk::SampleCountFlags::TYPE_1
+  }
+}
+
+/// Binds each glyph atlas to the binding of its index in every descriptor set
+fn write_glyph_atlas_descriptor_sets(
+  vk_device: &ash::Device,
+  sampler: vk::Sampler,
+  descriptor_sets: &[vk::DescriptorSet],
+  glyph_atlases: &[&SampledImage],
+) {
+  let descriptor_image_infos = glyph_atlases
+    .iter()
+    .map(|glyph_atlas| {
+      glyph_atlas
+        .get_image_views()
+        .iter()
+        .map(|&image_view| vk::DescriptorImageInfo {
+          sampler,
+          image_view,
+          image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        })
+        .collect::<Box<_>>()
+    })
+    .collect::<Box<_>>();
+
+  let descriptor_set_writes = descriptor_image_infos
+    .iter()
+    .enumerate()
+    .flat_map(|(binding, descriptor_image_infos)| {
+      descriptor_sets
+        .iter()
+        .zip(descriptor_image_infos.iter())
+        .map(
+          move |(&descriptor_set, descriptor_image_info)| vk::WriteDescriptorSet {
+            dst_set: descriptor_set,
+            dst_binding: binding as u32,
+            dst_array_element: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            p_image_info: descriptor_image_info,
+            ..Default::default()
+          },
+        )
+    })
+    .collect::<Box<_>>();
+
+  unsafe {
+    vk_device.update_descriptor_sets(&descriptor_set_writes, &[]);
+  }
+}
+
 impl Shared {
   fn new(
-    event_loop: &ActiveEventLoop,
-    title: &str,
+    window: Option<Window>,
     size: (f64, f64),
+    scale_factor: f64,
     model_capacities: ModelCapacities,
     glyph_atlas_size: (u16, u16),
+    glyph_mode: GlyphMode,
   ) -> Self {
-    let (width, height) = size;
     let ModelCapacities {
       round_rect_capacity,
       clipped_round_rect_capacity,
+      shadow_capacity,
+      clipped_shadow_capacity,
+      line_capacity,
+      clipped_line_capacity,
       glyph_capacity,
       clipped_glyph_capacity,
+      paint_capacity,
     } = model_capacities;
 
-    let window = event_loop
-      .create_window(
-        Window::default_attributes()
-          .with_enabled_buttons(WindowButtons::CLOSE | WindowButtons::MINIMIZE)
-          .with_inner_size(Size::Logical(LogicalSize::new(width, height)))
-          .with_position(Position::Logical(LogicalPosition::new(8_f64, 8_f64)))
-          .with_resizable(false)
-          .with_title(title)
-          .with_visible(false),
-      )
-      .unwrap();
-
-    if let Some(current_monitor) = window.current_monitor() {
-      let PhysicalSize {
-        width: window_outer_width,
-        height: window_outer_height,
-      } = window.outer_size();
-
-      let PhysicalSize {
-        width: monitor_width,
-        height: monitor_height,
-      } = current_monitor.size();
-
-      let (window_outer_x, window_outer_y) = (
-        monitor_width.saturating_sub(window_outer_width) >> 1_u32,
-        monitor_height.saturating_sub(window_outer_height) >> 1_u32,
-      );
-
-      window.set_outer_position(Position::Physical(PhysicalPosition::new(
-        window_outer_x.cast_signed(),
-        window_outer_y.cast_signed(),
-      )));
-    } else {
-      eprintln!("Failed to get current monitor to center the window");
-    }
-
-    let window_scale_factor = window.scale_factor() as f32;
+    let window_scale_factor = scale_factor as f32;
     let vk_entry = unsafe { ash::Entry::load().unwrap() };
 
     let vk_app_info = vk::ApplicationInfo {
@@ -319,11 +759,10 @@ impl Shared {
       .map(|name| name.as_ptr())
       .collect::<Box<_>>();
 
-    let window_handle = window.window_handle().unwrap();
-    let display_handle = window.display_handle().unwrap();
-
-    let vk_instance_ext_names =
-      ash_window::enumerate_required_extensions(display_handle.as_raw()).unwrap();
+    let vk_instance_ext_names = window.as_ref().map_or(&[][..], |window| {
+      let display_handle = window.display_handle().unwrap();
+      ash_window::enumerate_required_extensions(display_handle.as_raw()).unwrap()
+    });
 
     let vk_instance_ext_names = vk_instance_ext_names
       .iter()
@@ -358,18 +797,24 @@ impl Shared {
         .unwrap()
     };
 
-    let vk_surface_instance = khr::surface::Instance::new(&vk_entry, &vk_instance);
+    let vk_surface = window.as_ref().map(|window| {
+      let vk_surface_instance = khr::surface::Instance::new(&vk_entry, &vk_instance);
+      let window_handle = window.window_handle().unwrap();
+      let display_handle = window.display_handle().unwrap();
 
-    let vk_surface = unsafe {
-      ash_window::create_surface(
-        &vk_entry,
-        &vk_instance,
-        display_handle.as_raw(),
-        window_handle.as_raw(),
-        None,
-      )
-      .unwrap()
-    };
+      let vk_surface = unsafe {
+        ash_window::create_surface(
+          &vk_entry,
+          &vk_instance,
+          display_handle.as_raw(),
+          window_handle.as_raw(),
+          None,
+        )
+        .unwrap()
+      };
+
+      (vk_surface_instance, vk_surface)
+    });
 
     let vk_physical_devices = unsafe { vk_instance.enumerate_physical_devices().unwrap() };
 
@@ -417,21 +862,28 @@ impl Shared {
             })
             .map(|(index, _queue_family_props)| index.try_into().unwrap())?;
 
-          let present_queue_family_index = queue_family_props.iter().enumerate().find_map(
-            |(index, _queue_family_props)| {
-              let index = index.try_into().unwrap();
-
-              unsafe {
-                vk_surface_instance
-                  .get_physical_device_surface_support(vk_physical_device, index, vk_surface)
-                  .unwrap_or_else(|err| {
-                    eprintln!("Failed to check physical device supports the surface. Assume not supported: {err}");
-                    false
-                  })
-                  .then_some(index)
+          let present_queue_family_index = if let Some((ref vk_surface_instance, vk_surface)) =
+            vk_surface
+          {
+            queue_family_props.iter().enumerate().find_map(
+              |(index, _queue_family_props)| {
+                let index = index.try_into().unwrap();
+
+                unsafe {
+                  vk_surface_instance
+                    .get_physical_device_surface_support(vk_physical_device, index, vk_surface)
+                    .unwrap_or_else(|err| {
+                      eprintln!("Failed to check physical device supports the surface. Assume not supported: {err}");
+                      false
+                    })
+                    .then_some(index)
+                }
               }
-            }
-          )?;
+            )?
+          } else {
+            // Offscreen rendering never presents, so no separate present queue is needed
+            graphics_queue_family_index
+          };
 
           let transfer_queue_family_index = queue_family_props
             .iter()
@@ -654,18 +1106,44 @@ impl Shared {
     let present_queue = unsafe { vk_device.get_device_queue2(&present_queue_info) };
     let transfer_queue = unsafe { vk_device.get_device_queue2(&transfer_queue_info) };
 
-    let vk_surface_formats = unsafe {
-      vk_surface_instance
-        .get_physical_device_surface_formats(vk_physical_device, vk_surface)
-        .unwrap()
-    };
+    let output =
+      if let (Some(window), Some((vk_surface_instance, vk_surface))) = (window, vk_surface) {
+        let vk_surface_formats = unsafe {
+          vk_surface_instance
+            .get_physical_device_surface_formats(vk_physical_device, vk_surface)
+            .unwrap()
+        };
 
-    let &swapchain_format = vk_surface_formats
-      .iter()
-      .find(|&vk_surface_format| vk_surface_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
-      .unwrap_or_else(|| &vk_surface_formats[0]);
+        let &swapchain_format = vk_surface_formats
+          .iter()
+          .find(|&vk_surface_format| {
+            vk_surface_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+          })
+          .unwrap_or_else(|| &vk_surface_formats[0]);
+
+        let vk_swapchain_device = khr::swapchain::Device::new(&vk_instance, &vk_device);
+
+        Output::Window(WindowSurface {
+          window,
+          vk_surface_instance,
+          vk_surface,
+          present_queue_family_index,
+          present_queue,
+          swapchain_format,
+          vk_swapchain_device,
+        })
+      } else {
+        Output::Offscreen { size, scale_factor }
+      };
 
-    let vk_swapchain_device = khr::swapchain::Device::new(&vk_instance, &vk_device);
+    // Swapchain images get presented while offscreen images get copied back to the host
+    let (color_format, color_final_layout) = match output {
+      Output::Window(ref window_surface) => (
+        window_surface.swapchain_format.format,
+        vk::ImageLayout::PRESENT_SRC_KHR,
+      ),
+      Output::Offscreen { .. } => (OFFSCREEN_FORMAT, vk::ImageLayout::TRANSFER_SRC_OPTIMAL),
+    };
 
     let max_msaa_sample_count = vk_physical_device_props
       .properties
@@ -676,104 +1154,7 @@ impl Shared {
         .limits
         .framebuffer_depth_sample_counts;
 
-    let msaa_sample_count = if max_msaa_sample_count.contains(vk::SampleCountFlags::TYPE_4)
-      && window_scale_factor < 2.0
-    {
-      vk::SampleCountFlags::TYPE_4
-    } else if max_msaa_sample_count.contains(vk::SampleCountFlags::TYPE_2) {
-      vk::SampleCountFlags::TYPE_2
-    } else {
-      vk::SampleCountFlags::TYPE_1
-    };
-
-    let vert_shader_module_create_info = vk::ShaderModuleCreateInfo {
-      code_size: VERT_SHADER_CODE.len(),
-      p_code: VERT_SHADER_CODE.as_ptr().cast(),
-      ..Default::default()
-    };
-
-    let frag_shader_module_create_info = vk::ShaderModuleCreateInfo {
-      code_size: FRAG_SHADER_CODE.len(),
-      p_code: FRAG_SHADER_CODE.as_ptr().cast(),
-      ..Default::default()
-    };
-
-    let vert_shader_module = unsafe {
-      vk_device
-        .create_shader_module(&vert_shader_module_create_info, None)
-        .unwrap()
-    };
-
-    let frag_shader_module = unsafe {
-      vk_device
-        .create_shader_module(&frag_shader_module_create_info, None)
-        .unwrap()
-    };
-
-    let main_name = CString::new("main").unwrap();
-
-    let shader_stage_create_infos = [
-      vk::PipelineShaderStageCreateInfo {
-        stage: vk::ShaderStageFlags::VERTEX,
-        module: vert_shader_module,
-        p_name: main_name.as_ptr(),
-        ..Default::default()
-      },
-      vk::PipelineShaderStageCreateInfo {
-        stage: vk::ShaderStageFlags::FRAGMENT,
-        module: frag_shader_module,
-        p_name: main_name.as_ptr(),
-        ..Default::default()
-      },
-    ];
-
-    let vert_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::default();
-
-    let input_assembly_state_create_info = vk::PipelineInputAssemblyStateCreateInfo {
-      topology: vk::PrimitiveTopology::TRIANGLE_LIST,
-      ..Default::default()
-    };
-
-    let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
-      viewport_count: 1,
-      scissor_count: 1,
-      ..Default::default()
-    };
-
-    let rasterization_state_create_info = vk::PipelineRasterizationStateCreateInfo {
-      front_face: vk::FrontFace::CLOCKWISE,
-      line_width: 1.0,
-      ..Default::default()
-    };
-
-    let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
-      rasterization_samples: msaa_sample_count,
-      alpha_to_coverage_enable: vk::TRUE,
-      alpha_to_one_enable: vk::TRUE,
-      ..Default::default()
-    };
-
-    let depth_stencil_state_create_info = vk::PipelineDepthStencilStateCreateInfo {
-      depth_test_enable: vk::TRUE,
-      ..Default::default()
-    };
-
-    let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
-      color_write_mask: vk::ColorComponentFlags::RGBA,
-      ..Default::default()
-    }];
-
-    let color_blend_state_create_info = vk::PipelineColorBlendStateCreateInfo {
-      attachment_count: color_blend_attachment_states.len().try_into().unwrap(),
-      p_attachments: color_blend_attachment_states.as_ptr(),
-      ..Default::default()
-    };
-
-    let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo {
-      dynamic_state_count: DYNAMIC_STATES.len().try_into().unwrap(),
-      p_dynamic_states: DYNAMIC_STATES.as_ptr(),
-      ..Default::default()
-    };
+    let msaa_sample_count = choose_msaa_sample_count(max_msaa_sample_count, window_scale_factor);
 
     let sampler_create_info = vk::SamplerCreateInfo {
       mag_filter: vk::Filter::LINEAR,
@@ -793,14 +1174,15 @@ impl Shared {
         .unwrap()
     };
 
-    let descriptor_set_layout_bindings = [vk::DescriptorSetLayoutBinding {
-      binding: 0,
+    // Coverage and colour glyph atlases
+    let descriptor_set_layout_bindings = [0, 1].map(|binding| vk::DescriptorSetLayoutBinding {
+      binding,
       descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
       descriptor_count: 1,
       stage_flags: vk::ShaderStageFlags::FRAGMENT,
       p_immutable_samplers: &raw const sampler,
       ..Default::default()
-    }];
+    });
 
     let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo {
       binding_count: descriptor_set_layout_bindings.len().try_into().unwrap(),
@@ -814,8 +1196,9 @@ impl Shared {
         .unwrap()
     };
 
+    // Fragments read paints from the buffer in the push constants
     let push_const_ranges = [vk::PushConstantRange {
-      stage_flags: vk::ShaderStageFlags::VERTEX,
+      stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
       offset: 0,
       size: mem::size_of::<PushConsts>().try_into().unwrap(),
     }];
@@ -834,190 +1217,45 @@ impl Shared {
         .unwrap()
     };
 
-    // Attachment 0: MSAA color attachment (or direct if 1x)
-    // Attachment 1: Depth attachment
-    // Attachment 2: Resolve attachment (swapchain image) - only used when MSAA > 1x
-    let mut attachment_descs = vec![if msaa_sample_count == vk::SampleCountFlags::TYPE_1 {
-      // Color attachment
-      vk::AttachmentDescription2 {
-        format: swapchain_format.format,
-        samples: vk::SampleCountFlags::TYPE_1,
-        load_op: vk::AttachmentLoadOp::CLEAR,
-        store_op: vk::AttachmentStoreOp::STORE,
-        stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-        stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-        initial_layout: vk::ImageLayout::UNDEFINED,
-        final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-        ..Default::default()
-      }
-    } else {
-      // MSAA color attachment
-      vk::AttachmentDescription2 {
-        format: swapchain_format.format,
-        samples: msaa_sample_count,
-        load_op: vk::AttachmentLoadOp::CLEAR,
-        store_op: vk::AttachmentStoreOp::DONT_CARE,
-        stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-        stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-        initial_layout: vk::ImageLayout::UNDEFINED,
-        final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-        ..Default::default()
-      }
+    let (
+      render_pass,
+      opaque_graphics_pipeline,
+      translucent_graphics_pipeline,
+      clip_depth_graphics_pipeline,
+    ) = create_render_pass_and_pipelines(
+      &vk_device,
+      color_format,
+      color_final_layout,
+      msaa_sample_count,
+      pipeline_layout,
+    );
+
+    let descriptor_pool_sizes = [vk::DescriptorPoolSize {
+      ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+      descriptor_count: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32 * 2,
     }];
 
-    // Depth attachment
-    attachment_descs.push(vk::AttachmentDescription2 {
-      format: vk::Format::D16_UNORM,
-      samples: msaa_sample_count,
-      load_op: vk::AttachmentLoadOp::CLEAR,
-      store_op: vk::AttachmentStoreOp::DONT_CARE,
-      stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-      stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-      initial_layout: vk::ImageLayout::UNDEFINED,
-      final_layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+    let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
+      max_sets: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32,
+      pool_size_count: descriptor_pool_sizes.len().try_into().unwrap(),
+      p_pool_sizes: descriptor_pool_sizes.as_ptr(),
       ..Default::default()
-    });
+    };
 
-    if msaa_sample_count != vk::SampleCountFlags::TYPE_1 {
-      // Resolve attachment (swapchain image)
-      attachment_descs.push(vk::AttachmentDescription2 {
-        format: swapchain_format.format,
-        samples: vk::SampleCountFlags::TYPE_1,
-        load_op: vk::AttachmentLoadOp::DONT_CARE,
-        store_op: vk::AttachmentStoreOp::STORE,
-        stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
-        stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
-        initial_layout: vk::ImageLayout::UNDEFINED,
-        final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
-        ..Default::default()
-      });
-    }
+    let descriptor_pool = unsafe {
+      vk_device
+        .create_descriptor_pool(&descriptor_pool_create_info, None)
+        .unwrap()
+    };
 
-    let attachment_descs = attachment_descs;
+    let descriptor_set_layouts = [descriptor_set_layout; consts::MAX_IN_FLIGHT_FRAME_COUNT];
 
-    let color_attachment_refs = [vk::AttachmentReference2 {
-      attachment: 0,
-      layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+    let descriptor_set_alloc_info = vk::DescriptorSetAllocateInfo {
+      descriptor_pool,
+      descriptor_set_count: descriptor_set_layouts.len().try_into().unwrap(),
+      p_set_layouts: descriptor_set_layouts.as_ptr(),
       ..Default::default()
-    }];
-
-    let depth_attachment_ref = vk::AttachmentReference2 {
-      attachment: 1,
-      layout: vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
-      ..Default::default()
-    };
-
-    let resolve_attachment_refs = [vk::AttachmentReference2 {
-      attachment: 2,
-      layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-      ..Default::default()
-    }];
-
-    let subpass_descs = [vk::SubpassDescription2 {
-      pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
-      color_attachment_count: color_attachment_refs.len().try_into().unwrap(),
-      p_color_attachments: color_attachment_refs.as_ptr(),
-      p_depth_stencil_attachment: &raw const depth_attachment_ref,
-      p_resolve_attachments: if msaa_sample_count == vk::SampleCountFlags::TYPE_1 {
-        ptr::null()
-      } else {
-        resolve_attachment_refs.as_ptr()
-      },
-      ..Default::default()
-    }];
-
-    let subpass_deps = [vk::SubpassDependency2 {
-      src_subpass: vk::SUBPASS_EXTERNAL,
-      dst_subpass: 0,
-      src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-        | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
-      dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-        | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
-      src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
-        | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-      dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
-        | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-      dependency_flags: vk::DependencyFlags::BY_REGION,
-      ..Default::default()
-    }];
-
-    let render_pass_create_info = vk::RenderPassCreateInfo2 {
-      attachment_count: attachment_descs.len().try_into().unwrap(),
-      p_attachments: attachment_descs.as_ptr(),
-      subpass_count: subpass_descs.len().try_into().unwrap(),
-      p_subpasses: subpass_descs.as_ptr(),
-      dependency_count: subpass_deps.len().try_into().unwrap(),
-      p_dependencies: subpass_deps.as_ptr(),
-      ..Default::default()
-    };
-
-    let render_pass = unsafe {
-      vk_device
-        .create_render_pass2(&render_pass_create_info, None)
-        .unwrap()
-    };
-
-    let graphics_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
-      stage_count: shader_stage_create_infos.len().try_into().unwrap(),
-      p_stages: shader_stage_create_infos.as_ptr(),
-      p_vertex_input_state: &raw const vert_input_state_create_info,
-      p_input_assembly_state: &raw const input_assembly_state_create_info,
-      p_viewport_state: &raw const viewport_state_create_info,
-      p_rasterization_state: &raw const rasterization_state_create_info,
-      p_multisample_state: &raw const multisample_state_create_info,
-      p_depth_stencil_state: &raw const depth_stencil_state_create_info,
-      p_color_blend_state: &raw const color_blend_state_create_info,
-      p_dynamic_state: &raw const dynamic_state_create_info,
-      layout: pipeline_layout,
-      render_pass,
-      subpass: 0,
-      base_pipeline_index: -1,
-      ..Default::default()
-    }];
-
-    let graphics_pipeline = unsafe {
-      vk_device
-        .create_graphics_pipelines(
-          vk::PipelineCache::null(),
-          &graphics_pipeline_create_infos,
-          None,
-        )
-        .unwrap()[0]
-    };
-
-    unsafe {
-      vk_device.destroy_shader_module(frag_shader_module, None);
-    }
-    unsafe {
-      vk_device.destroy_shader_module(vert_shader_module, None);
-    }
-
-    let descriptor_pool_sizes = [vk::DescriptorPoolSize {
-      ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-      descriptor_count: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32,
-    }];
-
-    let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
-      max_sets: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32,
-      pool_size_count: descriptor_pool_sizes.len().try_into().unwrap(),
-      p_pool_sizes: descriptor_pool_sizes.as_ptr(),
-      ..Default::default()
-    };
-
-    let descriptor_pool = unsafe {
-      vk_device
-        .create_descriptor_pool(&descriptor_pool_create_info, None)
-        .unwrap()
-    };
-
-    let descriptor_set_layouts = [descriptor_set_layout; consts::MAX_IN_FLIGHT_FRAME_COUNT];
-
-    let descriptor_set_alloc_info = vk::DescriptorSetAllocateInfo {
-      descriptor_pool,
-      descriptor_set_count: descriptor_set_layouts.len().try_into().unwrap(),
-      p_set_layouts: descriptor_set_layouts.as_ptr(),
-      ..Default::default()
-    };
+    };
 
     let descriptor_sets = unsafe {
       vk_device
@@ -1065,8 +1303,13 @@ impl Shared {
 
     let round_rect_sync = ModelSync::new(round_rect_capacity);
     let clipped_round_rect_sync = ModelSync::new(clipped_round_rect_capacity);
+    let shadow_sync = ModelSync::new(shadow_capacity);
+    let clipped_shadow_sync = ModelSync::new(clipped_shadow_capacity);
+    let line_sync = ModelSync::new(line_capacity);
+    let clipped_line_sync = ModelSync::new(clipped_line_capacity);
+    let paint_sync = PaintSync::new(paint_capacity);
 
-    let (mut glyph_renderer, transfer_command_buffer) = GlyphRenderer::new(
+    let (glyph_renderer, transfer_command_buffers) = GlyphRenderer::new(
       &vk_device,
       &vk_allocator,
       graphics_queue_family_index,
@@ -1075,6 +1318,11 @@ impl Shared {
       glyph_capacity,
       clipped_glyph_capacity,
       glyph_atlas_size,
+      vk_physical_device_props
+        .properties
+        .limits
+        .max_image_array_layers,
+      glyph_mode,
     );
 
     let signal_semaphore_value = 1;
@@ -1086,8 +1334,8 @@ impl Shared {
     };
 
     let queue_submit_info = vk::SubmitInfo {
-      command_buffer_count: 1,
-      p_command_buffers: &raw const transfer_command_buffer,
+      command_buffer_count: transfer_command_buffers.len() as u32,
+      p_command_buffers: transfer_command_buffers.as_ptr(),
       signal_semaphore_count: 1,
       p_signal_semaphores: &raw const init_done_semaphore,
       p_next: (&raw const timeline_semaphore_submit_info).cast(),
@@ -1100,36 +1348,12 @@ impl Shared {
         .unwrap();
     }
 
-    let descriptor_image_infos = glyph_renderer
-      .get_glyph_atlas()
-      .get_image_views()
-      .iter()
-      .map(|&image_view| vk::DescriptorImageInfo {
-        sampler,
-        image_view,
-        image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-      })
-      .collect::<Box<_>>();
-
-    let descriptor_set_writes = descriptor_sets
-      .iter()
-      .zip(descriptor_image_infos.iter())
-      .map(
-        |(&descriptor_set, descriptor_image_info)| vk::WriteDescriptorSet {
-          dst_set: descriptor_set,
-          dst_binding: 0,
-          dst_array_element: 0,
-          descriptor_count: 1,
-          descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-          p_image_info: descriptor_image_info,
-          ..Default::default()
-        },
-      )
-      .collect::<Box<_>>();
-
-    unsafe {
-      vk_device.update_descriptor_sets(&descriptor_set_writes, &[]);
-    }
+    write_glyph_atlas_descriptor_sets(
+      &vk_device,
+      sampler,
+      &descriptor_sets,
+      &glyph_renderer.get_glyph_atlases(),
+    );
 
     let graphics_command_pools = iter::repeat_with(|| {
       let command_pool_create_info = vk::CommandPoolCreateInfo {
@@ -1202,26 +1426,24 @@ impl Shared {
         .collect::<Box<_>>();
 
     Self {
-      window,
+      output,
       _vk_entry: vk_entry,
       vk_instance,
-      vk_surface_instance,
-      vk_surface,
       vk_physical_device,
       graphics_queue_family_index,
-      present_queue_family_index,
       transfer_queue_family_index,
       vk_device,
       graphics_queue,
-      present_queue,
       transfer_queue,
-      swapchain_format,
-      vk_swapchain_device,
+      color_format,
+      color_final_layout,
       sampler,
       descriptor_set_layout,
       pipeline_layout,
       render_pass,
-      graphics_pipeline,
+      opaque_graphics_pipeline,
+      translucent_graphics_pipeline,
+      clip_depth_graphics_pipeline,
       descriptor_pool,
       descriptor_sets,
       init_done_semaphore,
@@ -1229,7 +1451,13 @@ impl Shared {
       model_buffer,
       round_rect_sync,
       clipped_round_rect_sync,
+      shadow_sync,
+      clipped_shadow_sync,
+      line_sync,
+      clipped_line_sync,
+      paint_sync,
       glyph_renderer,
+      max_msaa_sample_count,
       msaa_sample_count,
       model_capacities,
       glyph_atlas_size,
@@ -1243,201 +1471,897 @@ impl Shared {
     }
   }
 
-  fn drop(self) {
-    unsafe {
-      self
-        .in_flight_fences
-        .iter()
-        .for_each(|&fence| self.vk_device.destroy_fence(fence, None));
-    }
-    unsafe {
-      self
-        .transfer_done_semaphores
-        .iter()
-        .for_each(|&semaphore| self.vk_device.destroy_semaphore(semaphore, None));
-    }
-    unsafe {
-      self
-        .render_done_semaphores
-        .iter()
-        .for_each(|&semaphore| self.vk_device.destroy_semaphore(semaphore, None));
+  #[inline]
+  fn get_size(&self) -> (f32, f32) {
+    match self.output {
+      Output::Window(ref window_surface) => {
+        let LogicalSize { width, height } = window_surface
+          .window
+          .inner_size()
+          .to_logical(window_surface.window.scale_factor());
+
+        (width, height)
+      }
+      Output::Offscreen {
+        size: (width, height),
+        ..
+      } => (width as f32, height as f32),
     }
-    unsafe {
-      self
-        .image_avail_semaphores
-        .iter()
-        .for_each(|&semaphore| self.vk_device.destroy_semaphore(semaphore, None));
+  }
+
+  #[inline]
+  fn get_scale_factor(&self) -> f64 {
+    match self.output {
+      Output::Window(ref window_surface) => window_surface.window.scale_factor(),
+      Output::Offscreen { scale_factor, .. } => scale_factor,
     }
-    unsafe {
-      self
-        .graphics_command_pools
-        .iter()
-        .for_each(|&command_pool| self.vk_device.destroy_command_pool(command_pool, None));
+  }
+
+  /// Rasterizes glyphs for the new scale factor, and returns whether the MSAA sample count changed
+  /// along with it
+  fn set_scale_factor(&mut self, scale_factor: f64) -> bool {
+    if let Output::Offscreen {
+      scale_factor: ref mut offscreen_scale_factor,
+      ..
+    } = self.output
+    {
+      *offscreen_scale_factor = scale_factor;
     }
 
     self
       .glyph_renderer
-      .drop(&self.vk_device, &self.vk_allocator);
+      .set_window_scale_factor(scale_factor as f32);
 
-    self.model_buffer.drop(&self.vk_device, &self.vk_allocator);
-    drop(self.vk_allocator);
+    let msaa_sample_count =
+      choose_msaa_sample_count(self.max_msaa_sample_count, scale_factor as f32);
 
-    unsafe {
-      self
-        .vk_device
-        .destroy_semaphore(self.init_done_semaphore, None);
+    if msaa_sample_count == self.msaa_sample_count {
+      return false;
     }
+
     unsafe {
-      self
-        .vk_device
-        .destroy_descriptor_pool(self.descriptor_pool, None);
+      self.vk_device.device_wait_idle().unwrap();
     }
     unsafe {
       self
         .vk_device
-        .destroy_pipeline(self.graphics_pipeline, None);
-    }
-    unsafe {
-      self.vk_device.destroy_render_pass(self.render_pass, None);
+        .destroy_pipeline(self.clip_depth_graphics_pipeline, None);
     }
     unsafe {
       self
         .vk_device
-        .destroy_pipeline_layout(self.pipeline_layout, None);
+        .destroy_pipeline(self.translucent_graphics_pipeline, None);
     }
     unsafe {
       self
         .vk_device
-        .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
-    }
-    unsafe {
-      self.vk_device.destroy_sampler(self.sampler, None);
-    }
-    unsafe {
-      self.vk_device.destroy_device(None);
+        .destroy_pipeline(self.opaque_graphics_pipeline, None);
     }
     unsafe {
-      self
-        .vk_surface_instance
-        .destroy_surface(self.vk_surface, None);
+      self.vk_device.destroy_render_pass(self.render_pass, None);
     }
-    unsafe {
-      self.vk_instance.destroy_instance(None);
+
+    (
+      self.render_pass,
+      self.opaque_graphics_pipeline,
+      self.translucent_graphics_pipeline,
+      self.clip_depth_graphics_pipeline,
+    ) = create_render_pass_and_pipelines(
+      &self.vk_device,
+      self.color_format,
+      self.color_final_layout,
+      msaa_sample_count,
+      self.pipeline_layout,
+    );
+
+    self.msaa_sample_count = msaa_sample_count;
+    true
+  }
+
+  const fn get_model_buffer_offsets(&self) -> ModelBufferOffsets {
+    let round_rect = 0;
+    let shadow = self.model_capacities.round_rect_capacity * mem::size_of::<RoundRect>();
+    let line = shadow + self.model_capacities.shadow_capacity * mem::size_of::<Shadow>();
+    let glyph = line + self.model_capacities.line_capacity * mem::size_of::<Line>();
+    let clipped_round_rect = glyph + self.model_capacities.glyph_capacity * mem::size_of::<Glyph>();
+
+    let clipped_shadow = clipped_round_rect
+      + self.model_capacities.clipped_round_rect_capacity * mem::size_of::<RoundRect>();
+
+    let clipped_line =
+      clipped_shadow + self.model_capacities.clipped_shadow_capacity * mem::size_of::<Shadow>();
+
+    let clipped_glyph =
+      clipped_line + self.model_capacities.clipped_line_capacity * mem::size_of::<Line>();
+
+    let paint =
+      clipped_glyph + self.model_capacities.clipped_glyph_capacity * mem::size_of::<Glyph>();
+
+    ModelBufferOffsets {
+      round_rect,
+      shadow,
+      line,
+      glyph,
+      clipped_round_rect,
+      clipped_shadow,
+      clipped_line,
+      clipped_glyph,
+      paint,
     }
   }
-}
 
-pub struct Creating {
-  old_swapchain: vk::SwapchainKHR,
-}
+  fn sync_models(&mut self) -> (vk::DescriptorSet, bool) {
+    let transfer_done_semaphore = self.transfer_done_semaphores[self.frame_index];
 
-pub struct Created {
-  swapchain: vk::SwapchainKHR,
-  _swapchain_images: Box<[vk::Image]>,
-  swapchain_image_views: Box<[vk::ImageView]>,
-  msaa_image: Option<vk::Image>,
-  msaa_image_alloc: Option<vk_mem::Allocation>,
-  msaa_image_view: Option<vk::ImageView>,
-  depth_image: vk::Image,
-  depth_image_alloc: vk_mem::Allocation,
-  depth_image_view: vk::ImageView,
-  swapchain_extent: vk::Extent2D,
-  swapchain_framebuffers: Box<[vk::Framebuffer]>,
-}
+    let model_buffer_offsets = self.get_model_buffer_offsets();
 
-impl Created {
-  fn new(shared: &Shared, old_swapchain: vk::SwapchainKHR) -> Result<Self, WindowMinimized> {
-    let vk_surface_caps = unsafe {
-      shared
-        .vk_surface_instance
-        .get_physical_device_surface_capabilities(shared.vk_physical_device, shared.vk_surface)
-        .unwrap()
-    };
+    let round_rect_transfer_command_buffer = self.round_rect_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.round_rect,
+    );
 
-    let swapchain_extent = if vk_surface_caps.current_extent.width < u32::MAX {
-      vk_surface_caps.current_extent
-    } else {
-      let PhysicalSize { width, height } = shared.window.inner_size();
+    let shadow_transfer_command_buffer = self.shadow_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.shadow,
+    );
 
-      vk::Extent2D {
-        width: width.clamp(
-          vk_surface_caps.min_image_extent.width,
-          vk_surface_caps.max_image_extent.width,
-        ),
-        height: height.clamp(
-          vk_surface_caps.min_image_extent.height,
-          vk_surface_caps.max_image_extent.height,
-        ),
-      }
-    };
+    let line_transfer_command_buffer = self.line_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.line,
+    );
 
-    if swapchain_extent.width == 0 || swapchain_extent.height == 0 {
-      return Err(WindowMinimized);
-    }
+    let (glyph_transfer_command_buffers, glyph_atlas_grown) = self.glyph_renderer.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      &self.vk_allocator,
+      model_buffer_offsets.glyph,
+      model_buffer_offsets.clipped_glyph,
+      self.graphics_queue_family_index,
+      self.transfer_queue_family_index,
+    );
 
-    let swapchain_image_count = vk_surface_caps.min_image_count + 1;
+    let clipped_round_rect_transfer_command_buffer = self.clipped_round_rect_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.clipped_round_rect,
+    );
 
-    let swapchain_image_count = if vk_surface_caps.max_image_count > 0 {
-      swapchain_image_count.min(vk_surface_caps.max_image_count)
-    } else {
-      swapchain_image_count
-    };
+    let clipped_shadow_transfer_command_buffer = self.clipped_shadow_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.clipped_shadow,
+    );
 
-    let queue_family_indices = [
-      shared.graphics_queue_family_index,
-      shared.present_queue_family_index,
-    ];
+    let clipped_line_transfer_command_buffer = self.clipped_line_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.clipped_line,
+    );
 
-    let (swapchain_image_sharing_mode, swapchain_queue_family_indices) =
-      if shared.graphics_queue_family_index == shared.present_queue_family_index {
-        (vk::SharingMode::EXCLUSIVE, [].as_slice())
-      } else {
-        (vk::SharingMode::CONCURRENT, queue_family_indices.as_slice())
-      };
+    let paint_transfer_command_buffer = self.paint_sync.sync_to(
+      &self.model_buffer,
+      &self.vk_device,
+      model_buffer_offsets.paint,
+    );
 
-    let swapchain_create_info = vk::SwapchainCreateInfoKHR {
-      surface: shared.vk_surface,
-      min_image_count: swapchain_image_count,
-      image_format: shared.swapchain_format.format,
-      image_color_space: shared.swapchain_format.color_space,
-      image_extent: swapchain_extent,
-      image_array_layers: 1,
-      image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
-      image_sharing_mode: swapchain_image_sharing_mode,
-      queue_family_index_count: swapchain_queue_family_indices.len().try_into().unwrap(),
-      p_queue_family_indices: swapchain_queue_family_indices.as_ptr(),
-      pre_transform: vk_surface_caps.current_transform,
-      composite_alpha: vk::CompositeAlphaFlagsKHR::OPAQUE,
-      present_mode: vk::PresentModeKHR::FIFO,
-      clipped: vk::TRUE,
-      old_swapchain,
-      ..Default::default()
-    };
+    let transfer_command_buffers = [
+      round_rect_transfer_command_buffer,
+      shadow_transfer_command_buffer,
+      line_transfer_command_buffer,
+      clipped_round_rect_transfer_command_buffer,
+      clipped_shadow_transfer_command_buffer,
+      clipped_line_transfer_command_buffer,
+      paint_transfer_command_buffer,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(glyph_transfer_command_buffers)
+    .collect::<Box<_>>();
 
-    let swapchain = unsafe {
-      shared
-        .vk_swapchain_device
-        .create_swapchain(&swapchain_create_info, None)
-        .unwrap()
-    };
+    if glyph_atlas_grown {
+      write_glyph_atlas_descriptor_sets(
+        &self.vk_device,
+        self.sampler,
+        &self.descriptor_sets,
+        &self.glyph_renderer.get_glyph_atlases(),
+      );
+    }
 
-    let swapchain_images = unsafe {
-      shared
-        .vk_swapchain_device
-        .get_swapchain_images(swapchain)
-        .unwrap()
-    };
+    self.model_buffer.done_write();
+    self.glyph_renderer.done_glyph_atlas_write();
+    let descriptor_set = self.descriptor_sets[self.glyph_renderer.get_glyph_atlas_read_index()];
 
-    let swapchain_images = swapchain_images.into_boxed_slice();
+    if !transfer_command_buffers.is_empty() {
+      let wait_semaphore_value = 1;
 
-    let swapchain_image_views = swapchain_images
-      .iter()
-      .map(|&image| {
-        let image_view_create_info = vk::ImageViewCreateInfo {
-          image,
-          view_type: vk::ImageViewType::TYPE_2D,
-          format: shared.swapchain_format.format,
-          subresource_range: vk::ImageSubresourceRange {
+      let timeline_semaphore_submit_info = vk::TimelineSemaphoreSubmitInfo {
+        wait_semaphore_value_count: 1,
+        p_wait_semaphore_values: &raw const wait_semaphore_value,
+        ..Default::default()
+      };
+
+      let wait_dst_stage_mask = vk::PipelineStageFlags::TRANSFER;
+
+      let queue_submit_info = vk::SubmitInfo {
+        command_buffer_count: transfer_command_buffers.len().try_into().unwrap(),
+        p_command_buffers: transfer_command_buffers.as_ptr(),
+        wait_semaphore_count: 1,
+        p_wait_semaphores: &raw const self.init_done_semaphore,
+        p_wait_dst_stage_mask: &raw const wait_dst_stage_mask,
+        signal_semaphore_count: 1,
+        p_signal_semaphores: &raw const transfer_done_semaphore,
+        p_next: (&raw const timeline_semaphore_submit_info).cast(),
+        ..Default::default()
+      };
+
+      unsafe {
+        self
+          .vk_device
+          .queue_submit(self.transfer_queue, &[queue_submit_info], vk::Fence::null())
+          .unwrap();
+      }
+    }
+
+    (descriptor_set, !transfer_command_buffers.is_empty())
+  }
+
+  fn record_draw_cmds(
+    &self,
+    graphics_command_buffer: vk::CommandBuffer,
+    framebuffer: vk::Framebuffer,
+    extent: vk::Extent2D,
+    descriptor_set: vk::DescriptorSet,
+  ) {
+    let model_buffer_offsets = self.get_model_buffer_offsets();
+
+    let clear_values = [
+      vk::ClearValue {
+        color: vk::ClearColorValue {
+          float32: [0.0, 0.0, 0.0, 1.0],
+        },
+      },
+      vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+          depth: 1.0,
+          ..Default::default()
+        },
+      },
+    ];
+
+    let render_pass_begin_info = vk::RenderPassBeginInfo {
+      render_pass: self.render_pass,
+      framebuffer,
+      render_area: vk::Rect2D {
+        extent,
+        ..Default::default()
+      },
+      clear_value_count: clear_values.len().try_into().unwrap(),
+      p_clear_values: clear_values.as_ptr(),
+      ..Default::default()
+    };
+
+    let subpass_begin_info = vk::SubpassBeginInfo {
+      contents: vk::SubpassContents::INLINE,
+      ..Default::default()
+    };
+
+    unsafe {
+      self.vk_device.cmd_begin_render_pass2(
+        graphics_command_buffer,
+        &render_pass_begin_info,
+        &subpass_begin_info,
+      );
+    }
+
+    let viewports = [vk::Viewport {
+      width: extent.width as f32,
+      height: extent.height as f32,
+      min_depth: 0.0,
+      max_depth: 1.0,
+      ..Default::default()
+    }];
+
+    unsafe {
+      self
+        .vk_device
+        .cmd_set_viewport(graphics_command_buffer, 0, &viewports);
+    }
+
+    let scissors = [vk::Rect2D {
+      extent,
+      ..Default::default()
+    }];
+
+    unsafe {
+      self
+        .vk_device
+        .cmd_set_scissor(graphics_command_buffer, 0, &scissors);
+    }
+
+    unsafe {
+      self.vk_device.cmd_bind_descriptor_sets(
+        graphics_command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        self.pipeline_layout,
+        0,
+        &[descriptor_set],
+        &[],
+      );
+    }
+
+    let (cam_width, cam_height) = self.get_size();
+
+    let (glyph_atlas_width, glyph_atlas_height) = self.glyph_atlas_size;
+    let (glyph_atlas_width, glyph_atlas_height) =
+      (f32::from(glyph_atlas_width), f32::from(glyph_atlas_height));
+
+    let round_rect_count = self.round_rect_sync.get_model_count();
+    let shadow_count = self.shadow_sync.get_model_count();
+    let line_count = self.line_sync.get_model_count();
+    let glyph_count = self.glyph_renderer.get_glyph_count();
+    let clipped_round_rect_count = self.clipped_round_rect_sync.get_model_count();
+    let clipped_shadow_count = self.clipped_shadow_sync.get_model_count();
+    let clipped_line_count = self.clipped_line_sync.get_model_count();
+    let clipped_glyph_count = self.glyph_renderer.get_clipped_glyph_count();
+
+    if round_rect_count > 0 || shadow_count > 0 || line_count > 0 || glyph_count > 0 {
+      unsafe {
+        self
+          .vk_device
+          .cmd_set_depth_write_enable(graphics_command_buffer, true);
+      }
+
+      unsafe {
+        self
+          .vk_device
+          .cmd_set_depth_compare_op(graphics_command_buffer, vk::CompareOp::LESS_OR_EQUAL);
+      }
+
+      let push_consts = PushConsts {
+        round_rect_buffer: self
+          .model_buffer
+          .calc_read_addr(model_buffer_offsets.round_rect),
+        shadow_buffer: self
+          .model_buffer
+          .calc_read_addr(model_buffer_offsets.shadow),
+        glyph_buffer: self.model_buffer.calc_read_addr(model_buffer_offsets.glyph),
+        paint_buffer: self.model_buffer.calc_read_addr(model_buffer_offsets.paint),
+        line_buffer: self.model_buffer.calc_read_addr(model_buffer_offsets.line),
+        cam_size: (cam_width, cam_height),
+        glyph_atlas_size: (glyph_atlas_width, glyph_atlas_height),
+      };
+
+      let raw_push_consts = unsafe {
+        slice::from_raw_parts(
+          (&raw const push_consts).cast(),
+          mem::size_of::<PushConsts>(),
+        )
+      };
+
+      unsafe {
+        self.vk_device.cmd_push_constants(
+          graphics_command_buffer,
+          self.pipeline_layout,
+          vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+          0,
+          raw_push_consts,
+        );
+      }
+
+      let opaque_round_rect_count = self.round_rect_sync.get_opaque_model_count();
+      let opaque_shadow_count = self.shadow_sync.get_opaque_model_count();
+      let opaque_line_count = self.line_sync.get_opaque_model_count();
+
+      self.record_model_draw_cmds(
+        graphics_command_buffer,
+        (round_rect_count, opaque_round_rect_count),
+        (shadow_count, opaque_shadow_count),
+        (line_count, opaque_line_count),
+        (glyph_count, self.glyph_renderer.get_opaque_glyph_count()),
+        [
+          &self.shadow_sync.get_sort_keys(opaque_shadow_count),
+          &self.round_rect_sync.get_sort_keys(opaque_round_rect_count),
+          &self.line_sync.get_sort_keys(opaque_line_count),
+          &self.glyph_renderer.get_translucent_glyph_sort_keys(false),
+        ],
+      );
+    }
+
+    if clipped_round_rect_count > 0
+      || clipped_shadow_count > 0
+      || clipped_line_count > 0
+      || clipped_glyph_count > 0
+    {
+      // Translucent models do not write depth while they are blended, so their depth is written
+      // afterwards for the models clipped to them
+      self.record_clip_depth_draw_cmds(
+        graphics_command_buffer,
+        self.round_rect_sync.get_opaque_model_count()..round_rect_count,
+        self.line_sync.get_opaque_model_count()..line_count,
+        self.glyph_renderer.get_opaque_glyph_count()..glyph_count,
+      );
+
+      unsafe {
+        self
+          .vk_device
+          .cmd_set_depth_write_enable(graphics_command_buffer, false);
+      }
+
+      unsafe {
+        self
+          .vk_device
+          .cmd_set_depth_compare_op(graphics_command_buffer, vk::CompareOp::EQUAL);
+      }
+
+      let push_consts = PushConsts {
+        round_rect_buffer: self
+          .model_buffer
+          .calc_read_addr(model_buffer_offsets.clipped_round_rect),
+        shadow_buffer: self
+          .model_buffer
+          .calc_read_addr(model_buffer_offsets.clipped_shadow),
+        glyph_buffer: self
+          .model_buffer
+          .calc_read_addr(model_buffer_offsets.clipped_glyph),
+        paint_buffer: self.model_buffer.calc_read_addr(model_buffer_offsets.paint),
+        line_buffer: self
+          .model_buffer
+          .calc_read_addr(model_buffer_offsets.clipped_line),
+        cam_size: (cam_width, cam_height),
+        glyph_atlas_size: (glyph_atlas_width, glyph_atlas_height),
+      };
+
+      let raw_push_consts = unsafe {
+        slice::from_raw_parts(
+          (&raw const push_consts).cast(),
+          mem::size_of::<PushConsts>(),
+        )
+      };
+
+      unsafe {
+        self.vk_device.cmd_push_constants(
+          graphics_command_buffer,
+          self.pipeline_layout,
+          vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+          0,
+          raw_push_consts,
+        );
+      }
+
+      let opaque_clipped_round_rect_count = self.clipped_round_rect_sync.get_opaque_model_count();
+      let opaque_clipped_shadow_count = self.clipped_shadow_sync.get_opaque_model_count();
+      let opaque_clipped_line_count = self.clipped_line_sync.get_opaque_model_count();
+
+      self.record_model_draw_cmds(
+        graphics_command_buffer,
+        (clipped_round_rect_count, opaque_clipped_round_rect_count),
+        (clipped_shadow_count, opaque_clipped_shadow_count),
+        (clipped_line_count, opaque_clipped_line_count),
+        (
+          clipped_glyph_count,
+          self.glyph_renderer.get_opaque_clipped_glyph_count(),
+        ),
+        [
+          &self
+            .clipped_shadow_sync
+            .get_sort_keys(opaque_clipped_shadow_count),
+          &self
+            .clipped_round_rect_sync
+            .get_sort_keys(opaque_clipped_round_rect_count),
+          &self
+            .clipped_line_sync
+            .get_sort_keys(opaque_clipped_line_count),
+          &self.glyph_renderer.get_translucent_glyph_sort_keys(true),
+        ],
+      );
+    }
+
+    let subpass_end_info = vk::SubpassEndInfo::default();
+
+    unsafe {
+      self
+        .vk_device
+        .cmd_end_render_pass2(graphics_command_buffer, &subpass_end_info);
+    }
+  }
+
+  /// Writes the depth of translucent models without touching their colour, using the push
+  /// constants of the models that are not clipped
+  fn record_clip_depth_draw_cmds(
+    &self,
+    graphics_command_buffer: vk::CommandBuffer,
+    round_rect_range: Range<usize>,
+    line_range: Range<usize>,
+    glyph_range: Range<usize>,
+  ) {
+    if round_rect_range.is_empty() && line_range.is_empty() && glyph_range.is_empty() {
+      return;
+    }
+
+    unsafe {
+      self.vk_device.cmd_bind_pipeline(
+        graphics_command_buffer,
+        vk::PipelineBindPoint::GRAPHICS,
+        self.clip_depth_graphics_pipeline,
+      );
+    }
+
+    unsafe {
+      self
+        .vk_device
+        .cmd_set_depth_write_enable(graphics_command_buffer, true);
+    }
+
+    unsafe {
+      self
+        .vk_device
+        .cmd_set_depth_compare_op(graphics_command_buffer, vk::CompareOp::LESS_OR_EQUAL);
+    }
+
+    for (model_range, vertex_count, model_type) in [
+      (round_rect_range, RoundRect::get_vertex_count(), 0),
+      (line_range, Line::get_vertex_count(), 3),
+      (glyph_range, Glyph::get_vertex_count(), 1),
+    ] {
+      if model_range.is_empty() {
+        continue;
+      }
+
+      unsafe {
+        self.vk_device.cmd_draw(
+          graphics_command_buffer,
+          (model_range.len() * vertex_count).try_into().unwrap(),
+          1,
+          (model_range.start * vertex_count).try_into().unwrap(),
+          model_type,
+        );
+      }
+    }
+  }
+
+  fn record_model_draw_cmds(
+    &self,
+    graphics_command_buffer: vk::CommandBuffer,
+    round_rect_counts: (usize, usize),
+    shadow_counts: (usize, usize),
+    line_counts: (usize, usize),
+    glyph_counts: (usize, usize),
+    translucent_sort_keys: [&[f32]; 4],
+  ) {
+    let (round_rect_count, opaque_round_rect_count) = round_rect_counts;
+    let (shadow_count, opaque_shadow_count) = shadow_counts;
+    let (line_count, opaque_line_count) = line_counts;
+    let (glyph_count, opaque_glyph_count) = glyph_counts;
+    let [
+      shadow_sort_keys,
+      round_rect_sort_keys,
+      line_sort_keys,
+      glyph_sort_keys,
+    ] = translucent_sort_keys;
+
+    // Model types as the shaders number them with their vertex counts. Shadows usually lie behind
+    // the rects casting them, so they are drawn first at the same depth
+    let model_types = [
+      (2, Shadow::get_vertex_count()),
+      (0, RoundRect::get_vertex_count()),
+      (3, Line::get_vertex_count()),
+      (1, Glyph::get_vertex_count()),
+    ];
+
+    let opaque_draws = [
+      0..opaque_shadow_count,
+      0..opaque_round_rect_count,
+      0..opaque_line_count,
+      0..opaque_glyph_count,
+    ]
+    .into_iter()
+    .enumerate()
+    .collect::<Vec<_>>();
+
+    // Translucent models of every type are blended together from back to front
+    let translucent_draws = merge_translucent_draw_runs(&[
+      (opaque_shadow_count..shadow_count, shadow_sort_keys),
+      (
+        opaque_round_rect_count..round_rect_count,
+        round_rect_sort_keys,
+      ),
+      (opaque_line_count..line_count, line_sort_keys),
+      (opaque_glyph_count..glyph_count, glyph_sort_keys),
+    ]);
+
+    // Opaque models are drawn first so that translucent models can be blended on top of them
+    for (graphics_pipeline, blended, draws) in [
+      (self.opaque_graphics_pipeline, false, opaque_draws),
+      (self.translucent_graphics_pipeline, true, translucent_draws),
+    ] {
+      if draws
+        .iter()
+        .all(|&(_type_index, ref model_range)| model_range.is_empty())
+      {
+        continue;
+      }
+
+      unsafe {
+        self.vk_device.cmd_bind_pipeline(
+          graphics_command_buffer,
+          vk::PipelineBindPoint::GRAPHICS,
+          graphics_pipeline,
+        );
+      }
+
+      // Translucent models must not hide the translucent models behind them that are blended
+      // after them
+      if blended {
+        unsafe {
+          self
+            .vk_device
+            .cmd_set_depth_write_enable(graphics_command_buffer, false);
+        }
+      }
+
+      for (type_index, model_range) in draws {
+        if model_range.is_empty() {
+          continue;
+        }
+
+        let (model_type, vertex_count) = model_types[type_index];
+
+        unsafe {
+          self.vk_device.cmd_draw(
+            graphics_command_buffer,
+            (model_range.len() * vertex_count).try_into().unwrap(),
+            1,
+            (model_range.start * vertex_count).try_into().unwrap(),
+            model_type,
+          );
+        }
+      }
+    }
+  }
+
+  fn drop(self) {
+    unsafe {
+      self
+        .in_flight_fences
+        .iter()
+        .for_each(|&fence| self.vk_device.destroy_fence(fence, None));
+    }
+    unsafe {
+      self
+        .transfer_done_semaphores
+        .iter()
+        .for_each(|&semaphore| self.vk_device.destroy_semaphore(semaphore, None));
+    }
+    unsafe {
+      self
+        .render_done_semaphores
+        .iter()
+        .for_each(|&semaphore| self.vk_device.destroy_semaphore(semaphore, None));
+    }
+    unsafe {
+      self
+        .image_avail_semaphores
+        .iter()
+        .for_each(|&semaphore| self.vk_device.destroy_semaphore(semaphore, None));
+    }
+    unsafe {
+      self
+        .graphics_command_pools
+        .iter()
+        .for_each(|&command_pool| self.vk_device.destroy_command_pool(command_pool, None));
+    }
+
+    self
+      .glyph_renderer
+      .drop(&self.vk_device, &self.vk_allocator);
+
+    self.model_buffer.drop(&self.vk_device, &self.vk_allocator);
+    drop(self.vk_allocator);
+
+    unsafe {
+      self
+        .vk_device
+        .destroy_semaphore(self.init_done_semaphore, None);
+    }
+    unsafe {
+      self
+        .vk_device
+        .destroy_descriptor_pool(self.descriptor_pool, None);
+    }
+    unsafe {
+      self
+        .vk_device
+        .destroy_pipeline(self.clip_depth_graphics_pipeline, None);
+    }
+    unsafe {
+      self
+        .vk_device
+        .destroy_pipeline(self.translucent_graphics_pipeline, None);
+    }
+    unsafe {
+      self
+        .vk_device
+        .destroy_pipeline(self.opaque_graphics_pipeline, None);
+    }
+    unsafe {
+      self.vk_device.destroy_render_pass(self.render_pass, None);
+    }
+    unsafe {
+      self
+        .vk_device
+        .destroy_pipeline_layout(self.pipeline_layout, None);
+    }
+    unsafe {
+      self
+        .vk_device
+        .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+    }
+    unsafe {
+      self.vk_device.destroy_sampler(self.sampler, None);
+    }
+    unsafe {
+      self.vk_device.destroy_device(None);
+    }
+    if let Output::Window(ref window_surface) = self.output {
+      unsafe {
+        window_surface
+          .vk_surface_instance
+          .destroy_surface(window_surface.vk_surface, None);
+      }
+    }
+    unsafe {
+      self.vk_instance.destroy_instance(None);
+    }
+  }
+}
+
+pub struct Creating {
+  old_swapchain: vk::SwapchainKHR,
+}
+
+enum Target {
+  Swapchain {
+    swapchain: vk::SwapchainKHR,
+    _images: Box<[vk::Image]>,
+    image_views: Box<[vk::ImageView]>,
+  },
+  Offscreen {
+    image: vk::Image,
+    image_alloc: vk_mem::Allocation,
+    image_view: vk::ImageView,
+    readback_buffer: vk::Buffer,
+    readback_buffer_alloc: vk_mem::Allocation,
+    readback_buffer_data: *mut c_void,
+  },
+}
+
+impl Target {
+  #[inline]
+  fn get_image_views(&self) -> &[vk::ImageView] {
+    match *self {
+      Self::Swapchain {
+        ref image_views, ..
+      } => image_views,
+      Self::Offscreen { ref image_view, .. } => slice::from_ref(image_view),
+    }
+  }
+}
+
+pub struct Created {
+  target: Target,
+  msaa_image: Option<vk::Image>,
+  msaa_image_alloc: Option<vk_mem::Allocation>,
+  msaa_image_view: Option<vk::ImageView>,
+  depth_image: vk::Image,
+  depth_image_alloc: vk_mem::Allocation,
+  depth_image_view: vk::ImageView,
+  extent: vk::Extent2D,
+  framebuffers: Box<[vk::Framebuffer]>,
+}
+
+impl Created {
+  fn new(shared: &Shared, old_swapchain: vk::SwapchainKHR) -> Result<Self, WindowMinimized> {
+    let Output::Window(ref window_surface) = shared.output else {
+      unreachable!("Swapchain can only be created for a window");
+    };
+
+    let vk_surface_caps = unsafe {
+      window_surface
+        .vk_surface_instance
+        .get_physical_device_surface_capabilities(
+          shared.vk_physical_device,
+          window_surface.vk_surface,
+        )
+        .unwrap()
+    };
+
+    let swapchain_extent = if vk_surface_caps.current_extent.width < u32::MAX {
+      vk_surface_caps.current_extent
+    } else {
+      let PhysicalSize { width, height } = window_surface.window.inner_size();
+
+      vk::Extent2D {
+        width: width.clamp(
+          vk_surface_caps.min_image_extent.width,
+          vk_surface_caps.max_image_extent.width,
+        ),
+        height: height.clamp(
+          vk_surface_caps.min_image_extent.height,
+          vk_surface_caps.max_image_extent.height,
+        ),
+      }
+    };
+
+    if swapchain_extent.width == 0 || swapchain_extent.height == 0 {
+      return Err(WindowMinimized);
+    }
+
+    let swapchain_image_count = vk_surface_caps.min_image_count + 1;
+
+    let swapchain_image_count = if vk_surface_caps.max_image_count > 0 {
+      swapchain_image_count.min(vk_surface_caps.max_image_count)
+    } else {
+      swapchain_image_count
+    };
+
+    let queue_family_indices = [
+      shared.graphics_queue_family_index,
+      window_surface.present_queue_family_index,
+    ];
+
+    let (swapchain_image_sharing_mode, swapchain_queue_family_indices) =
+      if shared.graphics_queue_family_index == window_surface.present_queue_family_index {
+        (vk::SharingMode::EXCLUSIVE, [].as_slice())
+      } else {
+        (vk::SharingMode::CONCURRENT, queue_family_indices.as_slice())
+      };
+
+    let swapchain_create_info = vk::SwapchainCreateInfoKHR {
+      surface: window_surface.vk_surface,
+      min_image_count: swapchain_image_count,
+      image_format: window_surface.swapchain_format.format,
+      image_color_space: window_surface.swapchain_format.color_space,
+      image_extent: swapchain_extent,
+      image_array_layers: 1,
+      image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+      image_sharing_mode: swapchain_image_sharing_mode,
+      queue_family_index_count: swapchain_queue_family_indices.len().try_into().unwrap(),
+      p_queue_family_indices: swapchain_queue_family_indices.as_ptr(),
+      pre_transform: vk_surface_caps.current_transform,
+      composite_alpha: vk::CompositeAlphaFlagsKHR::OPAQUE,
+      present_mode: vk::PresentModeKHR::FIFO,
+      clipped: vk::TRUE,
+      old_swapchain,
+      ..Default::default()
+    };
+
+    let swapchain = unsafe {
+      window_surface
+        .vk_swapchain_device
+        .create_swapchain(&swapchain_create_info, None)
+        .unwrap()
+    };
+
+    let swapchain_images = unsafe {
+      window_surface
+        .vk_swapchain_device
+        .get_swapchain_images(swapchain)
+        .unwrap()
+    };
+
+    let swapchain_images = swapchain_images.into_boxed_slice();
+
+    let swapchain_image_views = swapchain_images
+      .iter()
+      .map(|&image| {
+        let image_view_create_info = vk::ImageViewCreateInfo {
+          image,
+          view_type: vk::ImageViewType::TYPE_2D,
+          format: shared.color_format,
+          subresource_range: vk::ImageSubresourceRange {
             aspect_mask: vk::ImageAspectFlags::COLOR,
             base_mip_level: 0,
             level_count: 1,
@@ -1456,6 +2380,120 @@ impl Created {
       })
       .collect::<Box<_>>();
 
+    let created = Self::with_target(
+      shared,
+      Target::Swapchain {
+        swapchain,
+        _images: swapchain_images,
+        image_views: swapchain_image_views,
+      },
+      swapchain_extent,
+    );
+
+    window_surface.window.set_visible(true);
+    Ok(created)
+  }
+
+  fn new_offscreen(shared: &Shared, extent: vk::Extent2D) -> Self {
+    let image_create_info = vk::ImageCreateInfo {
+      image_type: vk::ImageType::TYPE_2D,
+      format: shared.color_format,
+      extent: vk::Extent3D {
+        width: extent.width,
+        height: extent.height,
+        depth: 1,
+      },
+      mip_levels: 1,
+      array_layers: 1,
+      samples: vk::SampleCountFlags::TYPE_1,
+      tiling: vk::ImageTiling::OPTIMAL,
+      usage: vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+      sharing_mode: vk::SharingMode::EXCLUSIVE,
+      initial_layout: vk::ImageLayout::UNDEFINED,
+      ..Default::default()
+    };
+
+    let image_alloc_create_info = vk_mem::AllocationCreateInfo {
+      flags: vk_mem::AllocationCreateFlags::DEDICATED_MEMORY,
+      usage: vk_mem::MemoryUsage::AutoPreferDevice,
+      priority: 1.0,
+      ..Default::default()
+    };
+
+    let (image, image_alloc) = unsafe {
+      shared
+        .vk_allocator
+        .create_image(&image_create_info, &image_alloc_create_info)
+        .unwrap()
+    };
+
+    let image_view_create_info = vk::ImageViewCreateInfo {
+      image,
+      view_type: vk::ImageViewType::TYPE_2D,
+      format: shared.color_format,
+      subresource_range: vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: 1,
+        base_array_layer: 0,
+        layer_count: 1,
+      },
+      ..Default::default()
+    };
+
+    let image_view = unsafe {
+      shared
+        .vk_device
+        .create_image_view(&image_view_create_info, None)
+        .unwrap()
+    };
+
+    let readback_buffer_create_info = vk::BufferCreateInfo {
+      size: (extent.width as usize * extent.height as usize * 4) as u64,
+      usage: vk::BufferUsageFlags::TRANSFER_DST,
+      sharing_mode: vk::SharingMode::EXCLUSIVE,
+      ..Default::default()
+    };
+
+    let readback_buffer_alloc_create_info = vk_mem::AllocationCreateInfo {
+      flags: vk_mem::AllocationCreateFlags::MAPPED
+        | vk_mem::AllocationCreateFlags::HOST_ACCESS_RANDOM,
+      usage: vk_mem::MemoryUsage::AutoPreferHost,
+      priority: 1.0,
+      ..Default::default()
+    };
+
+    let (readback_buffer, readback_buffer_alloc) = unsafe {
+      shared
+        .vk_allocator
+        .create_buffer(
+          &readback_buffer_create_info,
+          &readback_buffer_alloc_create_info,
+        )
+        .unwrap()
+    };
+
+    let readback_buffer_alloc_info = shared
+      .vk_allocator
+      .get_allocation_info2(&readback_buffer_alloc);
+
+    let readback_buffer_data = readback_buffer_alloc_info.allocation_info.mapped_data;
+
+    Self::with_target(
+      shared,
+      Target::Offscreen {
+        image,
+        image_alloc,
+        image_view,
+        readback_buffer,
+        readback_buffer_alloc,
+        readback_buffer_data,
+      },
+      extent,
+    )
+  }
+
+  fn with_target(shared: &Shared, target: Target, extent: vk::Extent2D) -> Self {
     // Create MSAA image if using multisampling
     let (msaa_image, msaa_image_alloc, msaa_image_view) =
       if shared.msaa_sample_count == vk::SampleCountFlags::TYPE_1 {
@@ -1463,10 +2501,10 @@ impl Created {
       } else {
         let msaa_image_create_info = vk::ImageCreateInfo {
           image_type: vk::ImageType::TYPE_2D,
-          format: shared.swapchain_format.format,
+          format: shared.color_format,
           extent: vk::Extent3D {
-            width: swapchain_extent.width,
-            height: swapchain_extent.height,
+            width: extent.width,
+            height: extent.height,
             depth: 1,
           },
           mip_levels: 1,
@@ -1497,7 +2535,7 @@ impl Created {
         let msaa_image_view_create_info = vk::ImageViewCreateInfo {
           image: msaa_image,
           view_type: vk::ImageViewType::TYPE_2D,
-          format: shared.swapchain_format.format,
+          format: shared.color_format,
           subresource_range: vk::ImageSubresourceRange {
             aspect_mask: vk::ImageAspectFlags::COLOR,
             base_mip_level: 0,
@@ -1526,8 +2564,8 @@ impl Created {
       image_type: vk::ImageType::TYPE_2D,
       format: vk::Format::D16_UNORM,
       extent: vk::Extent3D {
-        width: swapchain_extent.width,
-        height: swapchain_extent.height,
+        width: extent.width,
+        height: extent.height,
         depth: 1,
       },
       mip_levels: 1,
@@ -1577,20 +2615,21 @@ impl Created {
         .unwrap()
     };
 
-    let swapchain_framebuffers = swapchain_image_views
+    let framebuffers = target
+      .get_image_views()
       .iter()
-      .map(|&swapchain_image_view| {
+      .map(|&image_view| {
         let attachments = msaa_image_view.map_or_else(
-          || vec![swapchain_image_view, depth_image_view],
-          |msaa_view| vec![msaa_view, depth_image_view, swapchain_image_view],
+          || vec![image_view, depth_image_view],
+          |msaa_view| vec![msaa_view, depth_image_view, image_view],
         );
 
         let framebuffer_create_info = vk::FramebufferCreateInfo {
           render_pass: shared.render_pass,
           attachment_count: attachments.len().try_into().unwrap(),
           p_attachments: attachments.as_ptr(),
-          width: swapchain_extent.width,
-          height: swapchain_extent.height,
+          width: extent.width,
+          height: extent.height,
           layers: 1,
           ..Default::default()
         };
@@ -1604,534 +2643,384 @@ impl Created {
       })
       .collect::<Box<_>>();
 
-    shared.window.set_visible(true);
-
-    Ok(Self {
-      swapchain,
-      _swapchain_images: swapchain_images,
-      swapchain_image_views,
-      msaa_image,
-      msaa_image_view,
-      msaa_image_alloc,
-      depth_image,
-      depth_image_view,
-      depth_image_alloc,
-      swapchain_extent,
-      swapchain_framebuffers,
-    })
-  }
-
-  fn on_swapchain_suboptimal(self, shared: &Shared) -> Result<Self, WindowMinimized> {
-    let result = Self::new(shared, self.swapchain);
-
-    unsafe {
-      shared.vk_device.device_wait_idle().unwrap();
-    }
-
-    self.drop(shared, matches!(result, Err(WindowMinimized)));
-    result
-  }
-
-  fn drop(mut self, shared: &Shared, skip_swapchain: bool) {
-    unsafe {
-      self
-        .swapchain_framebuffers
-        .iter()
-        .for_each(|&framebuffer| shared.vk_device.destroy_framebuffer(framebuffer, None));
-    }
-
-    unsafe {
-      shared
-        .vk_device
-        .destroy_image_view(self.depth_image_view, None);
-    }
-
-    unsafe {
-      shared
-        .vk_allocator
-        .destroy_image(self.depth_image, &mut self.depth_image_alloc);
-    }
-
-    if let Some(msaa_image_view) = self.msaa_image_view {
-      unsafe {
-        shared.vk_device.destroy_image_view(msaa_image_view, None);
-      }
-    }
-
-    if let (Some(msaa_image), Some(mut msaa_image_alloc)) = (self.msaa_image, self.msaa_image_alloc)
-    {
-      unsafe {
-        shared
-          .vk_allocator
-          .destroy_image(msaa_image, &mut msaa_image_alloc);
-      }
-    }
-
-    unsafe {
-      self
-        .swapchain_image_views
-        .iter()
-        .for_each(|&image_view| shared.vk_device.destroy_image_view(image_view, None));
-    }
-
-    if !skip_swapchain {
-      unsafe {
-        shared
-          .vk_swapchain_device
-          .destroy_swapchain(self.swapchain, None);
-      }
-    }
-  }
-}
-
-pub struct Renderer<State> {
-  shared: Shared,
-  state: State,
-}
-
-impl Renderer<Creating> {
-  pub(super) fn new(
-    event_loop: &ActiveEventLoop,
-    title: &str,
-    size: (f64, f64),
-    model_capacities: ModelCapacities,
-    glyph_atlas_size: (u16, u16),
-  ) -> Self {
     Self {
-      shared: Shared::new(event_loop, title, size, model_capacities, glyph_atlas_size),
-      state: Creating {
-        old_swapchain: vk::SwapchainKHR::null(),
-      },
-    }
-  }
-
-  pub(super) fn drop(self) {
-    let Self { shared, state: _ } = self;
-
-    unsafe {
-      shared.vk_device.device_wait_idle().unwrap();
-    }
-
-    shared.drop();
-  }
-}
-
-impl TryFrom<Renderer<Creating>> for Renderer<Created> {
-  type Error = Renderer<Creating>;
-
-  fn try_from(renderer: Renderer<Creating>) -> Result<Self, Self::Error> {
-    let Renderer { shared, state } = renderer;
-
-    match Created::new(&shared, state.old_swapchain) {
-      Ok(created) => {
-        unsafe {
-          shared
-            .vk_swapchain_device
-            .destroy_swapchain(state.old_swapchain, None);
-        }
-
-        Ok(Self {
-          shared,
-          state: created,
-        })
-      }
-      Err(WindowMinimized) => Err(Renderer { shared, state }),
-    }
-  }
-}
-
-impl Renderer<Created> {
-  pub(super) fn render(self) -> Result<Self, Renderer<Creating>> {
-    let Self { mut shared, state } = self;
-
-    let image_avail_semaphore = shared.image_avail_semaphores[shared.frame_index];
-    let render_done_semaphore = shared.render_done_semaphores[shared.frame_index];
-    let transfer_done_semaphore = shared.transfer_done_semaphores[shared.frame_index];
-    let in_flight_fence = shared.in_flight_fences[shared.frame_index];
-    let graphics_command_pool = shared.graphics_command_pools[shared.frame_index];
-    let graphics_command_buffer = shared.graphics_command_buffers[shared.frame_index];
-
-    unsafe {
-      shared
-        .vk_device
-        .wait_for_fences(&[in_flight_fence], true, u64::MAX)
-        .unwrap();
-    }
-
-    let round_rect_buffer_offset = 0;
-
-    let glyph_buffer_offset =
-      shared.model_capacities.round_rect_capacity * mem::size_of::<RoundRect>();
-
-    let clipped_round_rect_buffer_offset =
-      glyph_buffer_offset + shared.model_capacities.glyph_capacity * mem::size_of::<Glyph>();
-
-    let clipped_glyph_buffer_offset = clipped_round_rect_buffer_offset
-      + shared.model_capacities.clipped_round_rect_capacity * mem::size_of::<RoundRect>();
-
-    let round_rect_transfer_command_buffer = shared.round_rect_sync.sync_to(
-      &shared.model_buffer,
-      &shared.vk_device,
-      round_rect_buffer_offset,
-      false,
-    );
-
-    let glyph_transfer_command_buffers = shared.glyph_renderer.sync_to(
-      &shared.model_buffer,
-      &shared.vk_device,
-      glyph_buffer_offset,
-      clipped_glyph_buffer_offset,
-      shared.graphics_queue_family_index,
-      shared.transfer_queue_family_index,
-    );
-
-    let clipped_round_rect_transfer_command_buffer = shared.clipped_round_rect_sync.sync_to(
-      &shared.model_buffer,
-      &shared.vk_device,
-      clipped_round_rect_buffer_offset,
-      true,
-    );
-
-    let transfer_command_buffers = [
-      round_rect_transfer_command_buffer,
-      clipped_round_rect_transfer_command_buffer,
-    ]
-    .into_iter()
-    .flatten()
-    .chain(glyph_transfer_command_buffers)
-    .collect::<Box<_>>();
-
-    let glyph_atlas = shared.glyph_renderer.get_glyph_atlas();
-    shared.model_buffer.done_write();
-    glyph_atlas.done_write();
-    let descriptor_set = shared.descriptor_sets[glyph_atlas.get_read_index()];
-
-    if !transfer_command_buffers.is_empty() {
-      let wait_semaphore_value = 1;
-
-      let timeline_semaphore_submit_info = vk::TimelineSemaphoreSubmitInfo {
-        wait_semaphore_value_count: 1,
-        p_wait_semaphore_values: &raw const wait_semaphore_value,
-        ..Default::default()
-      };
-
-      let wait_dst_stage_mask = vk::PipelineStageFlags::TRANSFER;
-
-      let queue_submit_info = vk::SubmitInfo {
-        command_buffer_count: transfer_command_buffers.len().try_into().unwrap(),
-        p_command_buffers: transfer_command_buffers.as_ptr(),
-        wait_semaphore_count: 1,
-        p_wait_semaphores: &raw const shared.init_done_semaphore,
-        p_wait_dst_stage_mask: &raw const wait_dst_stage_mask,
-        signal_semaphore_count: 1,
-        p_signal_semaphores: &raw const transfer_done_semaphore,
-        p_next: (&raw const timeline_semaphore_submit_info).cast(),
-        ..Default::default()
-      };
-
-      unsafe {
-        shared
-          .vk_device
-          .queue_submit(
-            shared.transfer_queue,
-            &[queue_submit_info],
-            vk::Fence::null(),
-          )
-          .unwrap();
-      }
-    }
-
-    let acquire_next_image_info = vk::AcquireNextImageInfoKHR {
-      swapchain: state.swapchain,
-      timeout: u64::MAX,
-      semaphore: image_avail_semaphore,
-      device_mask: 1,
-      ..Default::default()
-    };
-
-    let swapchain_image_index = match unsafe {
-      shared
-        .vk_swapchain_device
-        .acquire_next_image2(&acquire_next_image_info)
-    } {
-      Ok((swapchain_image_index, _swapchain_suboptimal)) => swapchain_image_index,
-      Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
-        let old_swapchain = state.swapchain;
-
-        return match state.on_swapchain_suboptimal(&shared) {
-          Ok(new_state) => Ok(Self {
-            shared,
-            state: new_state,
-          }),
-          Err(WindowMinimized) => Err(Renderer {
-            shared,
-            state: Creating { old_swapchain },
-          }),
-        };
-      }
-      Err(err) => panic!("{err}"),
-    };
-
-    let swapchain_framebuffer = state.swapchain_framebuffers[swapchain_image_index as usize];
-
-    unsafe {
-      shared
-        .vk_device
-        .reset_command_pool(graphics_command_pool, vk::CommandPoolResetFlags::empty())
-        .unwrap();
-    }
-
-    let graphics_command_buffer_begin_info = vk::CommandBufferBeginInfo {
-      flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
-      ..Default::default()
-    };
-
-    unsafe {
-      shared
-        .vk_device
-        .begin_command_buffer(graphics_command_buffer, &graphics_command_buffer_begin_info)
-        .unwrap();
-    }
-
-    let clear_values = [
-      vk::ClearValue {
-        color: vk::ClearColorValue {
-          float32: [0.0, 0.0, 0.0, 1.0],
-        },
-      },
-      vk::ClearValue {
-        depth_stencil: vk::ClearDepthStencilValue {
-          depth: 1.0,
-          ..Default::default()
-        },
-      },
-    ];
+      target,
+      msaa_image,
+      msaa_image_alloc,
+      msaa_image_view,
+      depth_image,
+      depth_image_alloc,
+      depth_image_view,
+      extent,
+      framebuffers,
+    }
+  }
 
-    let render_pass_begin_info = vk::RenderPassBeginInfo {
-      render_pass: shared.render_pass,
-      framebuffer: swapchain_framebuffer,
-      render_area: vk::Rect2D {
-        extent: state.swapchain_extent,
-        ..Default::default()
-      },
-      clear_value_count: clear_values.len().try_into().unwrap(),
-      p_clear_values: clear_values.as_ptr(),
-      ..Default::default()
+  fn on_swapchain_suboptimal(self, shared: &Shared) -> Result<Self, WindowMinimized> {
+    let Target::Swapchain { swapchain, .. } = self.target else {
+      unreachable!("Offscreen target never becomes suboptimal");
     };
 
-    let subpass_begin_info = vk::SubpassBeginInfo {
-      contents: vk::SubpassContents::INLINE,
-      ..Default::default()
-    };
+    let result = Self::new(shared, swapchain);
 
     unsafe {
-      shared.vk_device.cmd_begin_render_pass2(
-        graphics_command_buffer,
-        &render_pass_begin_info,
-        &subpass_begin_info,
-      );
+      shared.vk_device.device_wait_idle().unwrap();
     }
 
+    self.drop(shared, matches!(result, Err(WindowMinimized)));
+    result
+  }
+
+  fn drop(mut self, shared: &Shared, skip_swapchain: bool) {
     unsafe {
-      shared.vk_device.cmd_bind_pipeline(
-        graphics_command_buffer,
-        vk::PipelineBindPoint::GRAPHICS,
-        shared.graphics_pipeline,
-      );
+      self
+        .framebuffers
+        .iter()
+        .for_each(|&framebuffer| shared.vk_device.destroy_framebuffer(framebuffer, None));
     }
 
-    let viewports = [vk::Viewport {
-      width: state.swapchain_extent.width as f32,
-      height: state.swapchain_extent.height as f32,
-      min_depth: 0.0,
-      max_depth: 1.0,
-      ..Default::default()
-    }];
-
     unsafe {
       shared
         .vk_device
-        .cmd_set_viewport(graphics_command_buffer, 0, &viewports);
+        .destroy_image_view(self.depth_image_view, None);
     }
 
-    let scissors = [vk::Rect2D {
-      extent: state.swapchain_extent,
-      ..Default::default()
-    }];
-
     unsafe {
       shared
-        .vk_device
-        .cmd_set_scissor(graphics_command_buffer, 0, &scissors);
+        .vk_allocator
+        .destroy_image(self.depth_image, &mut self.depth_image_alloc);
     }
 
-    unsafe {
-      shared.vk_device.cmd_bind_descriptor_sets(
-        graphics_command_buffer,
-        vk::PipelineBindPoint::GRAPHICS,
-        shared.pipeline_layout,
-        0,
-        &[descriptor_set],
-        &[],
-      );
+    if let Some(msaa_image_view) = self.msaa_image_view {
+      unsafe {
+        shared.vk_device.destroy_image_view(msaa_image_view, None);
+      }
+    }
+
+    if let (Some(msaa_image), Some(mut msaa_image_alloc)) = (self.msaa_image, self.msaa_image_alloc)
+    {
+      unsafe {
+        shared
+          .vk_allocator
+          .destroy_image(msaa_image, &mut msaa_image_alloc);
+      }
     }
 
-    let window_scale_factor = shared.window.scale_factor();
+    match self.target {
+      Target::Swapchain {
+        swapchain,
+        _images: _,
+        image_views,
+      } => {
+        unsafe {
+          image_views
+            .iter()
+            .for_each(|&image_view| shared.vk_device.destroy_image_view(image_view, None));
+        }
 
-    let LogicalSize {
-      width: cam_width,
-      height: cam_height,
-    } = shared.window.inner_size().to_logical(window_scale_factor);
+        if !skip_swapchain && let Output::Window(ref window_surface) = shared.output {
+          unsafe {
+            window_surface
+              .vk_swapchain_device
+              .destroy_swapchain(swapchain, None);
+          }
+        }
+      }
+      Target::Offscreen {
+        image,
+        mut image_alloc,
+        image_view,
+        readback_buffer,
+        mut readback_buffer_alloc,
+        readback_buffer_data: _,
+      } => {
+        unsafe {
+          shared.vk_device.destroy_image_view(image_view, None);
+        }
+        unsafe {
+          shared.vk_allocator.destroy_image(image, &mut image_alloc);
+        }
+        unsafe {
+          shared
+            .vk_allocator
+            .destroy_buffer(readback_buffer, &mut readback_buffer_alloc);
+        }
+      }
+    }
+  }
+}
 
-    let (glyph_atlas_width, glyph_atlas_height) = shared.glyph_atlas_size;
-    let (glyph_atlas_width, glyph_atlas_height) =
-      (f32::from(glyph_atlas_width), f32::from(glyph_atlas_height));
+pub struct Renderer<State> {
+  shared: Shared,
+  state: State,
+}
 
-    let round_rect_vertex_count = (shared.round_rect_sync.get_model_count()
-      * RoundRect::get_vertex_count())
-    .try_into()
-    .unwrap();
+impl Renderer<Creating> {
+  pub(super) fn new(
+    event_loop: &ActiveEventLoop,
+    title: &str,
+    size: (f64, f64),
+    model_capacities: ModelCapacities,
+    glyph_atlas_size: (u16, u16),
+    glyph_mode: GlyphMode,
+  ) -> Self {
+    let (width, height) = size;
 
-    let glyph_vertex_count = (shared.glyph_renderer.get_glyph_count() * Glyph::get_vertex_count())
-      .try_into()
+    let window = event_loop
+      .create_window(
+        Window::default_attributes()
+          .with_enabled_buttons(WindowButtons::CLOSE | WindowButtons::MINIMIZE)
+          .with_inner_size(Size::Logical(LogicalSize::new(width, height)))
+          .with_position(Position::Logical(LogicalPosition::new(8_f64, 8_f64)))
+          .with_resizable(false)
+          .with_title(title)
+          .with_visible(false),
+      )
       .unwrap();
 
-    let clipped_round_rect_vertex_count = (shared.clipped_round_rect_sync.get_model_count()
-      * RoundRect::get_vertex_count())
-    .try_into()
-    .unwrap();
-
-    let clipped_glyph_vertex_count = (shared.glyph_renderer.get_clipped_glyph_count()
-      * Glyph::get_vertex_count())
-    .try_into()
-    .unwrap();
+    if let Some(current_monitor) = window.current_monitor() {
+      let PhysicalSize {
+        width: window_outer_width,
+        height: window_outer_height,
+      } = window.outer_size();
 
-    if round_rect_vertex_count > 0 || glyph_vertex_count > 0 {
-      unsafe {
-        shared
-          .vk_device
-          .cmd_set_depth_write_enable(graphics_command_buffer, true);
-      }
+      let PhysicalSize {
+        width: monitor_width,
+        height: monitor_height,
+      } = current_monitor.size();
 
-      unsafe {
-        shared
-          .vk_device
-          .cmd_set_depth_compare_op(graphics_command_buffer, vk::CompareOp::LESS_OR_EQUAL);
-      }
+      let (window_outer_x, window_outer_y) = (
+        monitor_width.saturating_sub(window_outer_width) >> 1_u32,
+        monitor_height.saturating_sub(window_outer_height) >> 1_u32,
+      );
 
-      let push_consts = PushConsts {
-        round_rect_buffer: shared.model_buffer.calc_read_addr(round_rect_buffer_offset),
-        glyph_buffer: shared.model_buffer.calc_read_addr(glyph_buffer_offset),
-        cam_size: (cam_width, cam_height),
-        glyph_atlas_size: (glyph_atlas_width, glyph_atlas_height),
-        window_scale_factor: window_scale_factor as f32,
-      };
+      window.set_outer_position(Position::Physical(PhysicalPosition::new(
+        window_outer_x.cast_signed(),
+        window_outer_y.cast_signed(),
+      )));
+    } else {
+      eprintln!("Failed to get current monitor to center the window");
+    }
 
-      let raw_push_consts = unsafe {
-        slice::from_raw_parts(
-          (&raw const push_consts).cast(),
-          mem::size_of::<PushConsts>(),
-        )
-      };
+    let scale_factor = window.scale_factor();
 
-      unsafe {
-        shared.vk_device.cmd_push_constants(
-          graphics_command_buffer,
-          shared.pipeline_layout,
-          vk::ShaderStageFlags::VERTEX,
-          0,
-          raw_push_consts,
-        );
-      }
+    Self {
+      shared: Shared::new(
+        Some(window),
+        size,
+        scale_factor,
+        model_capacities,
+        glyph_atlas_size,
+        glyph_mode,
+      ),
+      state: Creating {
+        old_swapchain: vk::SwapchainKHR::null(),
+      },
     }
+  }
 
-    if round_rect_vertex_count > 0 {
-      unsafe {
-        shared
-          .vk_device
-          .cmd_draw(graphics_command_buffer, round_rect_vertex_count, 1, 0, 0);
-      }
+  #[inline]
+  pub(super) fn set_scale_factor(&mut self, scale_factor: f64) {
+    self.shared.set_scale_factor(scale_factor);
+  }
+
+  pub(super) fn drop(self) {
+    let Self { shared, state: _ } = self;
+
+    unsafe {
+      shared.vk_device.device_wait_idle().unwrap();
     }
 
-    if glyph_vertex_count > 0 {
-      unsafe {
-        shared
-          .vk_device
-          .cmd_draw(graphics_command_buffer, glyph_vertex_count, 1, 0, 1);
+    shared.drop();
+  }
+}
+
+impl TryFrom<Renderer<Creating>> for Renderer<Created> {
+  type Error = Renderer<Creating>;
+
+  fn try_from(renderer: Renderer<Creating>) -> Result<Self, Self::Error> {
+    let Renderer { shared, state } = renderer;
+
+    match Created::new(&shared, state.old_swapchain) {
+      Ok(created) => {
+        if let Output::Window(ref window_surface) = shared.output {
+          unsafe {
+            window_surface
+              .vk_swapchain_device
+              .destroy_swapchain(state.old_swapchain, None);
+          }
+        }
+
+        Ok(Self {
+          shared,
+          state: created,
+        })
       }
+      Err(WindowMinimized) => Err(Renderer { shared, state }),
     }
+  }
+}
 
-    if clipped_round_rect_vertex_count > 0 || clipped_glyph_vertex_count > 0 {
-      unsafe {
-        shared
-          .vk_device
-          .cmd_set_depth_write_enable(graphics_command_buffer, false);
-      }
+impl Renderer<Created> {
+  pub(super) fn new_headless(
+    size: (f64, f64),
+    scale_factor: f64,
+    model_capacities: ModelCapacities,
+    glyph_atlas_size: (u16, u16),
+    glyph_mode: GlyphMode,
+  ) -> Self {
+    let (width, height) = size;
 
-      unsafe {
-        shared
-          .vk_device
-          .cmd_set_depth_compare_op(graphics_command_buffer, vk::CompareOp::EQUAL);
+    let extent = vk::Extent2D {
+      width: (width * scale_factor).round() as u32,
+      height: (height * scale_factor).round() as u32,
+    };
+
+    let shared = Shared::new(
+      None,
+      size,
+      scale_factor,
+      model_capacities,
+      glyph_atlas_size,
+      glyph_mode,
+    );
+    let state = Created::new_offscreen(&shared, extent);
+    Self { shared, state }
+  }
+
+  /// Rebuilds the target when its size or MSAA sample count depends on the new scale factor
+  pub(super) fn set_scale_factor(self, scale_factor: f64) -> Result<Self, Renderer<Creating>> {
+    let Self { mut shared, state } = self;
+    let msaa_changed = shared.set_scale_factor(scale_factor);
+
+    match state.target {
+      Target::Swapchain { swapchain, .. } if msaa_changed => {
+        match state.on_swapchain_suboptimal(&shared) {
+          Ok(new_state) => Ok(Self {
+            shared,
+            state: new_state,
+          }),
+          Err(WindowMinimized) => Err(Renderer {
+            shared,
+            state: Creating {
+              old_swapchain: swapchain,
+            },
+          }),
+        }
       }
+      Target::Swapchain { .. } => Ok(Self { shared, state }),
+      Target::Offscreen { .. } => {
+        let Output::Offscreen {
+          size: (width, height),
+          ..
+        } = shared.output
+        else {
+          unreachable!("Offscreen target is only created for offscreen output");
+        };
 
-      let push_consts = PushConsts {
-        round_rect_buffer: shared
-          .model_buffer
-          .calc_read_addr(clipped_round_rect_buffer_offset),
-        glyph_buffer: shared
-          .model_buffer
-          .calc_read_addr(clipped_glyph_buffer_offset),
-        cam_size: (cam_width, cam_height),
-        glyph_atlas_size: (glyph_atlas_width, glyph_atlas_height),
-        window_scale_factor: window_scale_factor as f32,
-      };
+        let extent = vk::Extent2D {
+          width: (width * scale_factor).round() as u32,
+          height: (height * scale_factor).round() as u32,
+        };
 
-      let raw_push_consts = unsafe {
-        slice::from_raw_parts(
-          (&raw const push_consts).cast(),
-          mem::size_of::<PushConsts>(),
-        )
-      };
+        unsafe {
+          shared.vk_device.device_wait_idle().unwrap();
+        }
 
-      unsafe {
-        shared.vk_device.cmd_push_constants(
-          graphics_command_buffer,
-          shared.pipeline_layout,
-          vk::ShaderStageFlags::VERTEX,
-          0,
-          raw_push_consts,
-        );
+        state.drop(&shared, false);
+        let state = Created::new_offscreen(&shared, extent);
+        Ok(Self { shared, state })
       }
     }
+  }
 
-    if clipped_round_rect_vertex_count > 0 {
-      unsafe {
-        shared.vk_device.cmd_draw(
-          graphics_command_buffer,
-          clipped_round_rect_vertex_count,
-          1,
-          0,
-          0,
-        );
-      }
+  pub(super) fn render(mut self) -> Result<Self, Renderer<Creating>> {
+    let Target::Swapchain { swapchain, .. } = self.state.target else {
+      self.draw_offscreen();
+      return Ok(self);
+    };
+
+    let Self { mut shared, state } = self;
+
+    let image_avail_semaphore = shared.image_avail_semaphores[shared.frame_index];
+    let render_done_semaphore = shared.render_done_semaphores[shared.frame_index];
+    let transfer_done_semaphore = shared.transfer_done_semaphores[shared.frame_index];
+    let in_flight_fence = shared.in_flight_fences[shared.frame_index];
+    let graphics_command_pool = shared.graphics_command_pools[shared.frame_index];
+    let graphics_command_buffer = shared.graphics_command_buffers[shared.frame_index];
+
+    unsafe {
+      shared
+        .vk_device
+        .wait_for_fences(&[in_flight_fence], true, u64::MAX)
+        .unwrap();
     }
 
-    if clipped_glyph_vertex_count > 0 {
-      unsafe {
-        shared
-          .vk_device
-          .cmd_draw(graphics_command_buffer, clipped_glyph_vertex_count, 1, 0, 1);
+    let (descriptor_set, has_transfer) = shared.sync_models();
+
+    let Output::Window(ref window_surface) = shared.output else {
+      unreachable!("Swapchain can only be created for a window");
+    };
+
+    let acquire_next_image_info = vk::AcquireNextImageInfoKHR {
+      swapchain,
+      timeout: u64::MAX,
+      semaphore: image_avail_semaphore,
+      device_mask: 1,
+      ..Default::default()
+    };
+
+    let swapchain_image_index = match unsafe {
+      window_surface
+        .vk_swapchain_device
+        .acquire_next_image2(&acquire_next_image_info)
+    } {
+      Ok((swapchain_image_index, _swapchain_suboptimal)) => swapchain_image_index,
+      Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+        return match state.on_swapchain_suboptimal(&shared) {
+          Ok(new_state) => Ok(Self {
+            shared,
+            state: new_state,
+          }),
+          Err(WindowMinimized) => Err(Renderer {
+            shared,
+            state: Creating {
+              old_swapchain: swapchain,
+            },
+          }),
+        };
       }
+      Err(err) => panic!("{err}"),
+    };
+
+    let swapchain_framebuffer = state.framebuffers[swapchain_image_index as usize];
+
+    unsafe {
+      shared
+        .vk_device
+        .reset_command_pool(graphics_command_pool, vk::CommandPoolResetFlags::empty())
+        .unwrap();
     }
 
-    let subpass_end_info = vk::SubpassEndInfo::default();
+    let graphics_command_buffer_begin_info = vk::CommandBufferBeginInfo {
+      flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+      ..Default::default()
+    };
 
     unsafe {
       shared
         .vk_device
-        .cmd_end_render_pass2(graphics_command_buffer, &subpass_end_info);
+        .begin_command_buffer(graphics_command_buffer, &graphics_command_buffer_begin_info)
+        .unwrap();
     }
 
+    shared.record_draw_cmds(
+      graphics_command_buffer,
+      swapchain_framebuffer,
+      state.extent,
+      descriptor_set,
+    );
+
     unsafe {
       shared
         .vk_device
@@ -2147,7 +3036,7 @@ impl Renderer<Created> {
       vk::PipelineStageFlags::FRAGMENT_SHADER,
     ];
 
-    if !transfer_command_buffers.is_empty() {
+    if has_transfer {
       wait_semaphore_values.push(0);
       wait_semaphores.push(transfer_done_semaphore);
       wait_dst_stage_masks.push(vk::PipelineStageFlags::VERTEX_SHADER);
@@ -2186,26 +3075,24 @@ impl Renderer<Created> {
         .unwrap();
     }
 
-    shared.window.pre_present_notify();
+    window_surface.window.pre_present_notify();
 
     let present_info = vk::PresentInfoKHR {
       wait_semaphore_count: 1,
       p_wait_semaphores: &raw const render_done_semaphore,
       swapchain_count: 1,
-      p_swapchains: &raw const state.swapchain,
+      p_swapchains: &raw const swapchain,
       p_image_indices: &raw const swapchain_image_index,
       ..Default::default()
     };
 
     match unsafe {
-      shared
+      window_surface
         .vk_swapchain_device
-        .queue_present(shared.present_queue, &present_info)
+        .queue_present(window_surface.present_queue, &present_info)
     } {
       Ok(false) => (),
       Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
-        let old_swapchain = state.swapchain;
-
         return match state.on_swapchain_suboptimal(&shared) {
           Ok(new_state) => Ok(Self {
             shared,
@@ -2213,7 +3100,9 @@ impl Renderer<Created> {
           }),
           Err(WindowMinimized) => Err(Renderer {
             shared,
-            state: Creating { old_swapchain },
+            state: Creating {
+              old_swapchain: swapchain,
+            },
           }),
         };
       }
@@ -2229,6 +3118,232 @@ impl Renderer<Created> {
     })
   }
 
+  fn draw_offscreen(&mut self) {
+    let Target::Offscreen {
+      image,
+      readback_buffer,
+      ..
+    } = self.state.target
+    else {
+      unreachable!("Only an offscreen target can be drawn offscreen");
+    };
+
+    let Self {
+      ref mut shared,
+      ref state,
+    } = *self;
+
+    let transfer_done_semaphore = shared.transfer_done_semaphores[shared.frame_index];
+    let in_flight_fence = shared.in_flight_fences[shared.frame_index];
+    let graphics_command_pool = shared.graphics_command_pools[shared.frame_index];
+    let graphics_command_buffer = shared.graphics_command_buffers[shared.frame_index];
+
+    unsafe {
+      shared
+        .vk_device
+        .wait_for_fences(&[in_flight_fence], true, u64::MAX)
+        .unwrap();
+    }
+
+    let (descriptor_set, has_transfer) = shared.sync_models();
+
+    unsafe {
+      shared
+        .vk_device
+        .reset_command_pool(graphics_command_pool, vk::CommandPoolResetFlags::empty())
+        .unwrap();
+    }
+
+    let graphics_command_buffer_begin_info = vk::CommandBufferBeginInfo {
+      flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+      ..Default::default()
+    };
+
+    unsafe {
+      shared
+        .vk_device
+        .begin_command_buffer(graphics_command_buffer, &graphics_command_buffer_begin_info)
+        .unwrap();
+    }
+
+    shared.record_draw_cmds(
+      graphics_command_buffer,
+      state.framebuffers[0],
+      state.extent,
+      descriptor_set,
+    );
+
+    // The render pass leaves the image in TRANSFER_SRC_OPTIMAL, so it only needs to wait for the
+    // color attachment writes before copying it into the readback buffer
+    let image_memory_barrier = vk::ImageMemoryBarrier {
+      src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+      dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+      old_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+      new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+      src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+      dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+      image,
+      subresource_range: vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        level_count: 1,
+        layer_count: 1,
+        ..Default::default()
+      },
+      ..Default::default()
+    };
+
+    unsafe {
+      shared.vk_device.cmd_pipeline_barrier(
+        graphics_command_buffer,
+        vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::DependencyFlags::empty(),
+        &[],
+        &[],
+        &[image_memory_barrier],
+      );
+    }
+
+    let buffer_image_copy = vk::BufferImageCopy {
+      image_subresource: vk::ImageSubresourceLayers {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        layer_count: 1,
+        ..Default::default()
+      },
+      image_extent: vk::Extent3D {
+        width: state.extent.width,
+        height: state.extent.height,
+        depth: 1,
+      },
+      ..Default::default()
+    };
+
+    unsafe {
+      shared.vk_device.cmd_copy_image_to_buffer(
+        graphics_command_buffer,
+        image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        readback_buffer,
+        &[buffer_image_copy],
+      );
+    }
+
+    let buffer_memory_barrier = vk::BufferMemoryBarrier {
+      src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+      dst_access_mask: vk::AccessFlags::HOST_READ,
+      src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+      dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+      buffer: readback_buffer,
+      size: vk::WHOLE_SIZE,
+      ..Default::default()
+    };
+
+    unsafe {
+      shared.vk_device.cmd_pipeline_barrier(
+        graphics_command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::HOST,
+        vk::DependencyFlags::empty(),
+        &[],
+        &[buffer_memory_barrier],
+        &[],
+      );
+    }
+
+    unsafe {
+      shared
+        .vk_device
+        .end_command_buffer(graphics_command_buffer)
+        .unwrap();
+    }
+
+    let mut wait_semaphore_values = vec![1];
+    let mut wait_semaphores = vec![shared.init_done_semaphore];
+    let mut wait_dst_stage_masks = vec![vk::PipelineStageFlags::FRAGMENT_SHADER];
+
+    if has_transfer {
+      wait_semaphore_values.push(0);
+      wait_semaphores.push(transfer_done_semaphore);
+      wait_dst_stage_masks.push(vk::PipelineStageFlags::VERTEX_SHADER);
+    }
+
+    let wait_semaphore_values = wait_semaphore_values;
+    let wait_semaphores = wait_semaphores;
+    let wait_dst_stage_masks = wait_dst_stage_masks;
+
+    let timeline_semaphore_submit_info = vk::TimelineSemaphoreSubmitInfo {
+      wait_semaphore_value_count: wait_semaphore_values.len().try_into().unwrap(),
+      p_wait_semaphore_values: wait_semaphore_values.as_ptr(),
+      ..Default::default()
+    };
+
+    let queue_submit_info = vk::SubmitInfo {
+      wait_semaphore_count: wait_semaphores.len().try_into().unwrap(),
+      p_wait_semaphores: wait_semaphores.as_ptr(),
+      p_wait_dst_stage_mask: wait_dst_stage_masks.as_ptr(),
+      command_buffer_count: 1,
+      p_command_buffers: &raw const graphics_command_buffer,
+      p_next: (&raw const timeline_semaphore_submit_info).cast(),
+      ..Default::default()
+    };
+
+    unsafe {
+      shared.vk_device.reset_fences(&[in_flight_fence]).unwrap();
+    }
+
+    unsafe {
+      shared
+        .vk_device
+        .queue_submit(shared.graphics_queue, &[queue_submit_info], in_flight_fence)
+        .unwrap();
+    }
+
+    // Offscreen frames are read back right away, so there is nothing to overlap with
+    unsafe {
+      shared
+        .vk_device
+        .wait_for_fences(&[in_flight_fence], true, u64::MAX)
+        .unwrap();
+    }
+
+    shared.frame_index = (shared.frame_index + 1) % consts::MAX_IN_FLIGHT_FRAME_COUNT;
+  }
+
+  /// Draws a frame offscreen and reads it back, or `None` when rendering to a window
+  pub(super) fn render_to_image(&mut self) -> Option<RgbaImage> {
+    if !matches!(self.state.target, Target::Offscreen { .. }) {
+      return None;
+    }
+
+    self.draw_offscreen();
+
+    let Target::Offscreen {
+      ref readback_buffer_alloc,
+      readback_buffer_data,
+      ..
+    } = self.state.target
+    else {
+      unreachable!("Only an offscreen target can be read back");
+    };
+
+    self
+      .shared
+      .vk_allocator
+      .invalidate_allocation(readback_buffer_alloc, 0, vk::WHOLE_SIZE)
+      .unwrap();
+
+    let vk::Extent2D { width, height } = self.state.extent;
+
+    let pixels = unsafe {
+      slice::from_raw_parts(
+        readback_buffer_data.cast::<u8>(),
+        width as usize * height as usize * 4,
+      )
+    };
+
+    RgbaImage::from_raw(width, height, pixels.to_vec())
+  }
+
   pub(super) fn drop(self) {
     let Self { shared, state } = self;
 
@@ -2243,8 +3358,21 @@ impl Renderer<Created> {
 
 impl<State> Renderer<State> {
   #[inline]
-  pub(super) const fn get_window(&self) -> &Window {
-    &self.shared.window
+  pub(super) const fn get_window(&self) -> Option<&Window> {
+    match self.shared.output {
+      Output::Window(ref window_surface) => Some(&window_surface.window),
+      Output::Offscreen { .. } => None,
+    }
+  }
+
+  #[inline]
+  pub(super) fn get_size(&self) -> (f32, f32) {
+    self.shared.get_size()
+  }
+
+  #[inline]
+  pub(super) fn get_scale_factor(&self) -> f64 {
+    self.shared.get_scale_factor()
   }
 
   #[inline]
@@ -2257,6 +3385,31 @@ impl<State> Renderer<State> {
     &mut self.shared.clipped_round_rect_sync
   }
 
+  #[inline]
+  pub(super) const fn get_shadow_sync(&mut self) -> &mut ModelSync<Shadow> {
+    &mut self.shared.shadow_sync
+  }
+
+  #[inline]
+  pub(super) const fn get_clipped_shadow_sync(&mut self) -> &mut ModelSync<Shadow> {
+    &mut self.shared.clipped_shadow_sync
+  }
+
+  #[inline]
+  pub(super) const fn get_line_sync(&mut self) -> &mut ModelSync<Line> {
+    &mut self.shared.line_sync
+  }
+
+  #[inline]
+  pub(super) const fn get_clipped_line_sync(&mut self) -> &mut ModelSync<Line> {
+    &mut self.shared.clipped_line_sync
+  }
+
+  #[inline]
+  pub(super) const fn get_paint_sync(&mut self) -> &mut PaintSync {
+    &mut self.shared.paint_sync
+  }
+
   #[inline]
   pub(super) const fn get_glyph_renderer(&self) -> &GlyphRenderer {
     &self.shared.glyph_renderer
@@ -2267,3 +3420,31 @@ impl<State> Renderer<State> {
     &mut self.shared.glyph_renderer
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::merge_translucent_draw_runs;
+
+  #[test]
+  fn translucent_models_of_every_type_are_merged_back_to_front() {
+    let draw_runs = merge_translucent_draw_runs(&[
+      (2..4, &[1.0_f32, 3.0_f32]),
+      (5..7, &[2.0_f32, 2.5_f32]),
+      (0..0, &[]),
+    ]);
+
+    assert_eq!(draw_runs, [(0, 2..3), (1, 5..7), (0, 3..4)]);
+  }
+
+  #[test]
+  fn translucent_models_at_same_depth_keep_type_order() {
+    let draw_runs = merge_translucent_draw_runs(&[(0..1, &[2.0_f32]), (0..2, &[2.0_f32, 2.0_f32])]);
+
+    assert_eq!(draw_runs, [(0, 0..1), (1, 0..2)]);
+  }
+
+  #[test]
+  fn no_translucent_models_make_no_runs() {
+    assert!(merge_translucent_draw_runs(&[(3..3, &[]), (1..1, &[])]).is_empty());
+  }
+}
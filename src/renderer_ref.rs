@@ -1,10 +1,15 @@
 use crate::{
   glyph_renderer::{GlyphRenderer, IconId, TextId},
   model_sync::ModelSync,
-  models::{Model, icon::Icon, text::Text},
+  models::{
+    Model, char_index::CharIndex, font_key::FontKey, font_metrics::FontMetrics, icon::Icon,
+    paint::Paint, rect::Rect, rich_text::RichText, text::Text, text_metrics::TextMetrics,
+  },
+  paint_sync::PaintSync,
   renderer::{Created, Creating, Renderer},
 };
-use winit::{dpi::LogicalSize, window::Window};
+use std::{io, ops::Range, path::PathBuf};
+use winit::window::Window;
 
 pub struct RendererRef<'render>(&'render mut Result<Renderer<Created>, Renderer<Creating>>);
 
@@ -18,7 +23,7 @@ impl<'render> RendererRef<'render> {
 
   #[must_use]
   #[inline]
-  pub const fn get_window(&self) -> &Window {
+  pub const fn get_window(&self) -> Option<&Window> {
     match *self.0 {
       Ok(ref renderer) => renderer.get_window(),
       Err(ref renderer) => renderer.get_window(),
@@ -28,9 +33,19 @@ impl<'render> RendererRef<'render> {
   #[must_use]
   #[inline]
   pub fn get_size(&self) -> (f32, f32) {
-    let window = self.get_window();
-    let LogicalSize { width, height } = window.inner_size().to_logical(window.scale_factor());
-    (width, height)
+    match *self.0 {
+      Ok(ref renderer) => renderer.get_size(),
+      Err(ref renderer) => renderer.get_size(),
+    }
+  }
+
+  #[must_use]
+  #[inline]
+  pub fn get_scale_factor(&self) -> f64 {
+    match *self.0 {
+      Ok(ref renderer) => renderer.get_scale_factor(),
+      Err(ref renderer) => renderer.get_scale_factor(),
+    }
   }
 
   #[inline]
@@ -48,6 +63,14 @@ impl<'render> RendererRef<'render> {
     }
   }
 
+  #[inline]
+  const fn get_paint_sync(&mut self) -> &mut PaintSync {
+    match *self.0 {
+      Ok(ref mut renderer) => renderer.get_paint_sync(),
+      Err(ref mut renderer) => renderer.get_paint_sync(),
+    }
+  }
+
   #[inline]
   const fn get_glyph_renderer(&self) -> &GlyphRenderer {
     match *self.0 {
@@ -94,11 +117,50 @@ impl<'render> RendererRef<'render> {
     self.get_model_sync(clipped).bulk_remove_models(ids)
   }
 
+  /// Adds a paint that models fill with by setting their paint ID to the returned ID
+  /// - `paint`: paint to add
+  #[inline]
+  pub fn add_paint(&mut self, paint: Paint) -> u32 {
+    self.get_paint_sync().add_paint(paint)
+  }
+
+  #[inline]
+  pub fn update_paint(&mut self, id: u32, paint: Paint) {
+    self.get_paint_sync().update_paint(id, paint);
+  }
+
+  /// Removes a paint, whose ID may be reused by the next added paint
+  /// - `id`: paint to remove, which no model may reference anymore
+  #[inline]
+  pub fn remove_paint(&mut self, id: u32) -> Paint {
+    self.get_paint_sync().remove_paint(id)
+  }
+
   #[inline]
   pub fn add_text(&mut self, text: &Text, clipped: bool) -> TextId {
     self.get_glyph_renderer_mut().add_text(text, clipped)
   }
 
+  #[inline]
+  pub fn add_rich_text(&mut self, text: &RichText, clipped: bool) -> TextId {
+    self.get_glyph_renderer_mut().add_rich_text(text, clipped)
+  }
+
+  /// Reuses the glyph models of `text_id` for the new text, only uploading glyphs that changed
+  #[inline]
+  pub fn update_text(&mut self, text_id: &mut TextId, text: &Text) {
+    self.get_glyph_renderer_mut().update_text(*text_id, text);
+  }
+
+  /// Reuses the glyph models of `text_id` for the new rich text, only uploading glyphs that
+  /// changed
+  #[inline]
+  pub fn update_rich_text(&mut self, text_id: &mut TextId, text: &RichText) {
+    self
+      .get_glyph_renderer_mut()
+      .update_rich_text(*text_id, text);
+  }
+
   #[inline]
   pub fn remove_text(&mut self, text_id: TextId) {
     self.get_glyph_renderer_mut().remove_text(text_id);
@@ -114,15 +176,88 @@ impl<'render> RendererRef<'render> {
     self.get_glyph_renderer_mut().remove_icon(icon_id);
   }
 
+  /// Lays out text the same way as `add_text` without adding it
+  #[must_use]
+  #[inline]
+  pub fn measure_text(&mut self, text: &Text) -> TextMetrics {
+    self.get_glyph_renderer_mut().measure_text(text)
+  }
+
+  /// Lays out rich text the same way as `add_rich_text` without adding it
+  #[must_use]
+  #[inline]
+  pub fn measure_rich_text(&mut self, text: &RichText) -> TextMetrics {
+    self.get_glyph_renderer_mut().measure_rich_text(text)
+  }
+
+  #[must_use]
+  #[inline]
+  pub fn measure_icon(&mut self, icon: Icon) -> TextMetrics {
+    self.get_glyph_renderer_mut().measure_icon(icon)
+  }
+
+  /// Vertical metrics of `font_key` at `font_size`, for lining up text and icons in any font
+  #[must_use]
+  #[inline]
+  pub fn get_font_metrics(&mut self, font_key: &FontKey, font_size: f32) -> FontMetrics {
+    self
+      .get_glyph_renderer_mut()
+      .get_font_metrics(font_key, font_size)
+  }
+
   #[must_use]
   #[inline]
   pub fn get_text_size(&self, text_id: &TextId) -> (f32, f32) {
-    self.get_glyph_renderer().get_text_size(text_id)
+    self.get_glyph_renderer().get_text_size(*text_id)
+  }
+
+  /// Index of the caret position closest to `point`, such as where a click puts the caret
+  #[must_use]
+  #[inline]
+  pub fn text_hit_test(&self, text_id: &TextId, point: (f32, f32)) -> CharIndex {
+    self.get_glyph_renderer().text_hit_test(*text_id, point)
+  }
+
+  /// Rect of the caret before the char at `char_index`, clamped to the end of the text
+  #[must_use]
+  #[inline]
+  pub fn caret_rect(&self, text_id: &TextId, char_index: CharIndex) -> Rect {
+    self
+      .get_glyph_renderer()
+      .get_caret_rect(*text_id, char_index)
+  }
+
+  /// Rects that highlight the chars in `char_range`, mixed direction lines may need several
+  #[must_use]
+  #[inline]
+  pub fn selection_rects(&self, text_id: &TextId, char_range: Range<CharIndex>) -> Box<[Rect]> {
+    self
+      .get_glyph_renderer()
+      .get_selection_rects(*text_id, char_range)
+  }
+
+  /// Loads the glyphs that earlier launches cached in `atlas_cache_path`, so they skip being
+  /// rasterized again, call it right after creating the app
+  #[inline]
+  pub fn load_atlas_cache<AtlasCachePath: Into<PathBuf>>(
+    &mut self,
+    atlas_cache_path: AtlasCachePath,
+  ) {
+    self
+      .get_glyph_renderer_mut()
+      .load_atlas_cache(atlas_cache_path.into());
+  }
+
+  /// Writes the glyphs used since `load_atlas_cache` back to its file, call it before dropping the
+  /// app
+  #[inline]
+  pub fn save_atlas_cache(&self) -> io::Result<()> {
+    self.get_glyph_renderer().save_atlas_cache()
   }
 
   #[must_use]
   #[inline]
   pub fn get_icon_size(&self, icon_id: &IconId) -> (f32, f32) {
-    self.get_glyph_renderer().get_icon_size(icon_id)
+    self.get_glyph_renderer().get_icon_size(*icon_id)
   }
 }
@@ -13,6 +13,7 @@ pub struct SampledImage {
   transfer_command_pools: Box<[vk::CommandPool]>,
   transfer_command_buffers: Box<[vk::CommandBuffer]>,
   size: usize,
+  layer_count: u32,
   read_index: usize,
 }
 
@@ -24,6 +25,8 @@ impl SampledImage {
     transfer_queue_family_index: u32,
     size: usize,
     extent: vk::Extent2D,
+    layer_count: u32,
+    format: vk::Format,
   ) -> (Self, vk::CommandBuffer) {
     let vk::Extent2D { width, height } = extent;
 
@@ -56,14 +59,14 @@ impl SampledImage {
 
     let image_create_info = vk::ImageCreateInfo {
       image_type: vk::ImageType::TYPE_2D,
-      format: vk::Format::R8_UNORM,
+      format,
       extent: vk::Extent3D {
         width,
         height,
         depth: 1,
       },
       mip_levels: 1,
-      array_layers: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32,
+      array_layers: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32 * layer_count,
       samples: vk::SampleCountFlags::TYPE_1,
       tiling: vk::ImageTiling::OPTIMAL,
       usage: vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
@@ -88,14 +91,14 @@ impl SampledImage {
       .map(|index| {
         let image_view_create_info = vk::ImageViewCreateInfo {
           image,
-          view_type: vk::ImageViewType::TYPE_2D,
-          format: vk::Format::R8_UNORM,
+          view_type: vk::ImageViewType::TYPE_2D_ARRAY,
+          format,
           subresource_range: vk::ImageSubresourceRange {
             aspect_mask: vk::ImageAspectFlags::COLOR,
             base_mip_level: 0,
             level_count: 1,
-            base_array_layer: index as u32,
-            layer_count: 1,
+            base_array_layer: index as u32 * layer_count,
+            layer_count,
           },
           ..Default::default()
         };
@@ -168,7 +171,7 @@ impl SampledImage {
         base_mip_level: 0,
         level_count: 1,
         base_array_layer: 0,
-        layer_count: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32,
+        layer_count: consts::MAX_IN_FLIGHT_FRAME_COUNT as u32 * layer_count,
       },
       ..Default::default()
     };
@@ -202,6 +205,7 @@ impl SampledImage {
         transfer_command_pools,
         transfer_command_buffers,
         size,
+        layer_count,
         read_index: 0,
       },
       transfer_command_buffer,
@@ -213,11 +217,21 @@ impl SampledImage {
     &self.image_views
   }
 
+  #[inline]
+  pub(super) const fn get_layer_count(&self) -> u32 {
+    self.layer_count
+  }
+
   #[inline]
   pub(super) const fn get_read_index(&self) -> usize {
     self.read_index
   }
 
+  #[inline]
+  pub(super) const fn set_read_index(&mut self, read_index: usize) {
+    self.read_index = read_index;
+  }
+
   pub(super) fn write(
     &mut self,
     vk_device: &ash::Device,
@@ -266,8 +280,8 @@ impl SampledImage {
         aspect_mask: vk::ImageAspectFlags::COLOR,
         base_mip_level: 0,
         level_count: 1,
-        base_array_layer: write_index as u32,
-        layer_count: 1,
+        base_array_layer: write_index as u32 * self.layer_count,
+        layer_count: self.layer_count,
       },
       ..Default::default()
     };
@@ -291,7 +305,8 @@ impl SampledImage {
         image_subresource: vk::ImageSubresourceLayers {
           aspect_mask: vk::ImageAspectFlags::COLOR,
           mip_level: 0,
-          base_array_layer: write_index as u32,
+          base_array_layer: write_index as u32 * self.layer_count
+            + region.image_subresource.base_array_layer,
           layer_count: 1,
         },
         ..region
@@ -323,8 +338,8 @@ impl SampledImage {
         aspect_mask: vk::ImageAspectFlags::COLOR,
         base_mip_level: 0,
         level_count: 1,
-        base_array_layer: write_index as u32,
-        layer_count: 1,
+        base_array_layer: write_index as u32 * self.layer_count,
+        layer_count: self.layer_count,
       },
       ..Default::default()
     };
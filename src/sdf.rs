@@ -1,9 +1,19 @@
 /// Signed distance function for rounded rectangle
 /// - `position`: point relative to rect center
 /// - `half_size`: half of rect dimensions
-/// - `radius`: corner radius
+/// - `radii`: corner radii in the order top left, top right, bottom right, bottom left
 #[must_use]
-pub fn sd_round_rect(position: (f32, f32), half_size: (f32, f32), radius: f32) -> f32 {
+pub fn sd_round_rect(position: (f32, f32), half_size: (f32, f32), radii: [f32; 4]) -> f32 {
+  let [top_left, top_right, bottom_right, bottom_left] = radii;
+
+  // y goes down, so the bottom corners have a positive y
+  let radius = match (position.0 > 0.0, position.1 > 0.0) {
+    (false, false) => top_left,
+    (true, false) => top_right,
+    (true, true) => bottom_right,
+    (false, true) => bottom_left,
+  };
+
   let (qx, qy) = (
     position.0.abs() - half_size.0 + radius,
     position.1.abs() - half_size.1 + radius,
@@ -14,3 +24,204 @@ pub fn sd_round_rect(position: (f32, f32), half_size: (f32, f32), radius: f32) -
   let outer_dist = (qx * qx + qy * qy).sqrt();
   outer_dist + inner_dist - radius
 }
+
+/// Squared distance far enough to never be the nearest one
+const FAR_SQUARED_DIST: f32 = 1e20;
+
+/// Squared Euclidean distance transform along one row or column, in place
+/// - `grid`: squared distances of the whole image
+/// - `offset`: index of the first pixel in `grid`
+/// - `stride`: index step between pixels in `grid`
+/// - `len`: pixel count of the row or column
+fn edt_1d(grid: &mut [f32], offset: usize, stride: usize, len: usize) {
+  let dists = (0..len)
+    .map(|pixel_index| grid[offset + pixel_index * stride])
+    .collect::<Box<_>>();
+
+  // Parabola vertices of the lower envelope and where each one starts
+  let mut vertices = vec![0; len];
+  let mut bounds = vec![0.0; len + 1];
+  bounds[0] = -FAR_SQUARED_DIST;
+  bounds[1] = FAR_SQUARED_DIST;
+  let mut envelope_index = 0;
+
+  for pixel_index in 1..len {
+    let intersect = |vertex: usize| {
+      (dists[pixel_index] - dists[vertex] + (pixel_index * pixel_index) as f32
+        - (vertex * vertex) as f32)
+        / (pixel_index - vertex) as f32
+        * 0.5
+    };
+
+    let mut intersection = intersect(vertices[envelope_index]);
+
+    while intersection <= bounds[envelope_index] && envelope_index > 0 {
+      envelope_index -= 1;
+      intersection = intersect(vertices[envelope_index]);
+    }
+
+    if intersection > bounds[envelope_index] {
+      envelope_index += 1;
+    }
+
+    vertices[envelope_index] = pixel_index;
+    bounds[envelope_index] = intersection;
+    bounds[envelope_index + 1] = FAR_SQUARED_DIST;
+  }
+
+  envelope_index = 0;
+
+  for pixel_index in 0..len {
+    while bounds[envelope_index + 1] < pixel_index as f32 {
+      envelope_index += 1;
+    }
+
+    let vertex = vertices[envelope_index];
+    let vertex_dist = pixel_index.abs_diff(vertex) as f32;
+    grid[offset + pixel_index * stride] = vertex_dist.mul_add(vertex_dist, dists[vertex]);
+  }
+}
+
+/// Squared Euclidean distance transform of a whole image, in place
+/// - `grid`: row-major squared distances
+/// - `width`: pixel count in each row
+fn edt(grid: &mut [f32], width: usize) {
+  let height = grid.len().div_euclid(width);
+
+  for x in 0..width {
+    edt_1d(grid, x, width, height);
+  }
+
+  for y in 0..height {
+    edt_1d(grid, y * width, 1, width);
+  }
+}
+
+/// Converts glyph coverage into a signed distance field, where 128 is the edge and values rise
+/// inwards
+/// - `coverage`: row-major coverage of each pixel
+/// - `width`: pixel count in each row
+/// - `spread`: distance in pixels from the edge to where the field saturates
+#[must_use]
+pub fn sd_coverage(coverage: &[u8], width: usize, spread: f32) -> Box<[u8]> {
+  // Partially covered pixels start at their estimated distance to the edge
+  let (mut outer_grid, mut inner_grid): (Vec<_>, Vec<_>) = coverage
+    .iter()
+    .map(|&pixel_coverage| match pixel_coverage {
+      0 => (FAR_SQUARED_DIST, 0.0),
+      255 => (0.0, FAR_SQUARED_DIST),
+      _ => {
+        let dist = 0.5 - f32::from(pixel_coverage) / 255.0;
+        (dist.max(0.0).powi(2), dist.min(0.0).powi(2))
+      }
+    })
+    .unzip();
+
+  edt(&mut outer_grid, width);
+  edt(&mut inner_grid, width);
+
+  outer_grid
+    .iter()
+    .zip(&inner_grid)
+    .map(|(&outer_squared_dist, &inner_squared_dist)| {
+      let dist = outer_squared_dist.sqrt() - inner_squared_dist.sqrt();
+      ((0.5 - dist / (spread * 2.0)).clamp(0.0, 1.0) * 255.0).round() as u8
+    })
+    .collect()
+}
+
+/// Grows glyph coverage outwards by a distance, keeping its edge antialiased
+/// - `coverage`: row-major coverage of each pixel
+/// - `width`: pixel count in each row
+/// - `radius`: distance in pixels to grow by
+#[must_use]
+pub fn dilate_coverage(coverage: &[u8], width: usize, radius: f32) -> Box<[u8]> {
+  let mut outer_grid = coverage
+    .iter()
+    .map(|&pixel_coverage| match pixel_coverage {
+      0 => FAR_SQUARED_DIST,
+      _ => (0.5 - f32::from(pixel_coverage) / 255.0).max(0.0).powi(2),
+    })
+    .collect::<Box<_>>();
+
+  edt(&mut outer_grid, width);
+
+  outer_grid
+    .iter()
+    .zip(coverage)
+    .map(|(&outer_squared_dist, &pixel_coverage)| {
+      // Distances are between pixel centers, so the edge of a covered pixel is half a pixel out
+      let dilated_coverage = (radius + 1.0 - outer_squared_dist.sqrt()).clamp(0.0, 1.0);
+      (dilated_coverage * 255.0)
+        .round()
+        .max(f32::from(pixel_coverage)) as u8
+    })
+    .collect()
+}
+
+/// Pixels a blur spreads coverage by on each side
+/// - `radius`: blur radius, twice the standard deviation of the Gaussian
+#[must_use]
+pub fn calc_blur_extent(radius: f32) -> u32 {
+  (radius * 1.5).ceil().max(0.0) as u32
+}
+
+/// Blurs coverage with a Gaussian, treating pixels outside of it as empty
+/// - `coverage`: row-major coverage of each pixel
+/// - `width`: pixel count in each row
+/// - `radius`: blur radius, twice the standard deviation of the Gaussian
+#[must_use]
+pub fn blur_coverage(coverage: &[u8], width: usize, radius: f32) -> Box<[u8]> {
+  let extent = calc_blur_extent(radius) as usize;
+
+  if extent == 0 {
+    return coverage.into();
+  }
+
+  let height = coverage.len().div_euclid(width);
+  let sigma = radius * 0.5;
+
+  let mut kernel = (0..=extent << 1_usize)
+    .map(|index| {
+      let offset = index.abs_diff(extent) as f32;
+      (-offset * offset / (2.0 * sigma * sigma)).exp()
+    })
+    .collect::<Box<_>>();
+
+  let kernel_sum = kernel.iter().sum::<f32>();
+  kernel.iter_mut().for_each(|weight| *weight /= kernel_sum);
+
+  // Each pass blurs along one axis and the second pass reads what the first one wrote
+  let blur_pass = |pixels: &[f32], stride: usize, len: usize, line_count: usize, line_stride| {
+    let mut blurred_pixels = vec![0.0; pixels.len()];
+
+    for line_index in 0..line_count {
+      for pixel_index in 0..len {
+        blurred_pixels[line_index * line_stride + pixel_index * stride] = kernel
+          .iter()
+          .enumerate()
+          .filter_map(|(kernel_index, &weight)| {
+            let sample_index = (pixel_index + kernel_index).checked_sub(extent)?;
+            (sample_index < len)
+              .then(|| weight * pixels[line_index * line_stride + sample_index * stride])
+          })
+          .sum();
+      }
+    }
+
+    blurred_pixels
+  };
+
+  let pixels = coverage
+    .iter()
+    .map(|&pixel_coverage| f32::from(pixel_coverage))
+    .collect::<Box<_>>();
+
+  let pixels = blur_pass(&pixels, 1, width, height, width);
+  let pixels = blur_pass(&pixels, width, height, width, 1);
+
+  pixels
+    .iter()
+    .map(|&pixel_coverage| pixel_coverage.round().clamp(0.0, 255.0) as u8)
+    .collect()
+}
@@ -1,43 +1,350 @@
 #version 460 core
+#extension GL_EXT_buffer_reference : require
 
 // Model types
 const int ROUND_RECT = 0;
 const int GLYPH = 1;
+const int SHADOW = 2;
+const int LINE = 3;
+
+// Glyph settings
+const uint SOLID_ATLAS_PAGE = 0xFFFFFFFFu;
+
+// Glyph flags
+const uint GLYPH_FLAG_SDF = 1u;
+const uint GLYPH_FLAG_COLOR = 2u;
+
+// Line caps
+const uint LINE_CAP_BUTT = 0u;
+const uint LINE_CAP_ROUND = 1u;
+const uint LINE_CAP_SQUARE = 2u;
+
+// Line joins
+const uint LINE_JOIN_MITER = 0u;
+const uint LINE_JOIN_ROUND = 1u;
+const uint LINE_JOIN_BEVEL = 2u;
+
+// Paint kinds
+const uint PAINT_KIND_LINEAR_GRADIENT = 0u;
+const uint PAINT_KIND_RADIAL_GRADIENT = 1u;
+const uint PAINT_KIND_CONIC_GRADIENT = 2u;
+
+// Paint settings
+const uint MAX_PAINT_STOP_COUNT = 4u;
+const uint TRANSLUCENT_PAINT_ID_FLAG = 0x80000000u;
+
+const float PI = 3.14159265;
+
+struct Paint {
+  uint kind;
+  uint stop_count;
+  vec2 start;
+  vec2 end;
+  float stop_offsets[MAX_PAINT_STOP_COUNT];
+  uint stop_colors[MAX_PAINT_STOP_COUNT];
+};
+
+struct Line {
+  vec2 start;
+  vec2 end;
+  vec2 prev_offset;
+  vec2 next_offset;
+  float z;
+  float thickness;
+  uint color;
+  uint cap;
+  uint join;
+  float miter_limit;
+  float dash_length;
+  float gap_length;
+  float dash_offset;
+  uint paint_id;
+  vec2 paint_offset;
+};
+
+layout(buffer_reference, std430) readonly buffer PaintBuffer {
+  Paint paints[];
+};
+
+layout(buffer_reference, std430) readonly buffer LineBuffer {
+  Line lines[];
+};
+
+// Only the buffers of the push constants shared with the vertex shader that fragments read from
+layout(push_constant) uniform PushConsts {
+  layout(offset = 24) PaintBuffer paint_buffer;
+  layout(offset = 32) LineBuffer line_buffer;
+} push_consts;
 
 layout(location = 0) flat in int model_type;
-layout(location = 1) in vec3 color;
+layout(location = 1) in vec4 color;
 layout(location = 2) in vec2 local_position;
 layout(location = 3) flat in vec2 half_size;
-layout(location = 4) flat in float radius;
+layout(location = 4) flat in vec4 radius;
 layout(location = 5) in vec2 atlas_position;
+layout(location = 6) flat in uint atlas_page;
+layout(location = 7) flat in uint glyph_flags;
+layout(location = 8) flat in vec4 border_color;
+layout(location = 9) flat in float border_width;
+layout(location = 10) flat in float border_outset;
+layout(location = 11) flat in float blur_radius;
+layout(location = 12) flat in uint paint_id;
+layout(location = 13) in vec2 world_position;
+layout(location = 14) flat in uint line_index;
+layout(location = 15) flat in vec2 paint_origin;
 
-layout(binding = 0) uniform sampler2D glyph_atlas_sampler;
+layout(binding = 0) uniform sampler2DArray glyph_atlas_sampler;
+layout(binding = 1) uniform sampler2DArray color_glyph_atlas_sampler;
 
 layout(location = 0) out vec4 out_color;
 
 /// Signed distance function for rounded rectangle
 /// - `position`: point relative to rect center
 /// - `half_size`: half of rect dimensions
-/// - `radius`: corner radius
-float sd_round_rect(const vec2 position, const vec2 half_size, const float radius) {
+/// - `radii`: corner radii in the order top left, top right, bottom right, bottom left
+float sd_round_rect(const vec2 position, const vec2 half_size, const vec4 radii) {
+  // y goes down, so the bottom corners have a positive y
+  const vec2 side_radii = position.x > 0.0 ? radii.yz : radii.xw;
+  const float radius = position.y > 0.0 ? side_radii.y : side_radii.x;
   const vec2 q = abs(position) - half_size + vec2(radius);
   return length(max(q, vec2(0.0))) + min(max(q.x, q.y), 0.0) - radius;
 }
 
+vec4 unpack_color(const uint packed_color) {
+  return vec4(
+    (packed_color >> 24) & 0xFF,
+    (packed_color >> 16) & 0xFF,
+    (packed_color >> 8) & 0xFF,
+    packed_color & 0xFF
+  ) / 255.0;
+}
+
+/// Colour of a paint at a position in a model
+/// - `paint`: paint to evaluate
+/// - `position`: position in logical pixels from the top left corner of the model bounds
+vec4 eval_paint(const Paint paint, const vec2 position) {
+  const vec2 axis = paint.end - paint.start;
+  const vec2 to_position = position - paint.start;
+  float t;
+
+  switch (paint.kind) {
+    case PAINT_KIND_RADIAL_GRADIENT:
+      t = length(to_position) / max(length(axis), 1e-6);
+      break;
+
+    case PAINT_KIND_CONIC_GRADIENT:
+      // y goes down, so increasing angles go clockwise
+      t = fract((atan(to_position.y, to_position.x) - atan(axis.y, axis.x)) / (2.0 * PI));
+      break;
+
+    default:
+      t = dot(to_position, axis) / max(dot(axis, axis), 1e-6);
+      break;
+  }
+
+  // Each stop takes over from the previous one between their offsets
+  vec4 paint_color = unpack_color(paint.stop_colors[0]);
+
+  for (uint i = 1u; i < min(paint.stop_count, MAX_PAINT_STOP_COUNT); i++) {
+    const float start_offset = paint.stop_offsets[i - 1u];
+    const float end_offset = paint.stop_offsets[i];
+    const float stop_t = clamp((t - start_offset) / max(end_offset - start_offset, 1e-6), 0.0, 1.0);
+    paint_color = mix(paint_color, unpack_color(paint.stop_colors[i]), stop_t);
+  }
+
+  return paint_color;
+}
+
+/// Signed distance to one end of a line, which is either capped or joined to the next line
+/// - `line`: line the end belongs to
+/// - `position`: point relative to the end, with x pointing out of the line and y across it
+/// - `neighbor_dir`: direction from the end to the point of the joined line, or zero for a cap
+/// - `d`: distance to the sides of the line
+/// - `cut`: set when the point lies past the seam shared with the joined line
+float sd_line_end(
+  const Line line,
+  const vec2 position,
+  const vec2 neighbor_dir,
+  const float d,
+  inout bool cut
+) {
+  const float half_thickness = line.thickness * 0.5;
+
+  if (neighbor_dir == vec2(0.0)) {
+    switch (line.cap) {
+      case LINE_CAP_ROUND:
+        return position.x > 0.0 ? length(position) - half_thickness : d;
+
+      case LINE_CAP_SQUARE:
+        return max(d, position.x - half_thickness);
+
+      default:
+        return max(d, position.x);
+    }
+  }
+
+  // Both lines are cut along the bisector of their directions, which splits the joint between
+  // them without overlap, so the cut is not anti-aliased
+  const vec2 out_dir = vec2(1.0, 0.0);
+  const vec2 join_dir = normalize(neighbor_dir);
+  const vec2 seam_normal = out_dir + join_dir;
+
+  if (dot(position, length(seam_normal) > 1e-6 ? seam_normal : out_dir) > 0.0) {
+    cut = true;
+  }
+
+  // The cosine of half the turn between the lines sets how far the outer corner reaches
+  const float half_turn_cos = length(seam_normal) * 0.5;
+  const vec2 bevel_normal = out_dir - join_dir;
+
+  switch (line.join) {
+    case LINE_JOIN_ROUND:
+      return position.x > 0.0 ? length(position) - half_thickness : d;
+
+    case LINE_JOIN_MITER:
+      if (half_turn_cos * line.miter_limit >= 1.0) {
+        return d;
+      }
+      break;
+  }
+
+  if (length(bevel_normal) <= 1e-6) {
+    return d;
+  }
+
+  return max(d, dot(position, normalize(bevel_normal)) - half_thickness * half_turn_cos);
+}
+
+/// Signed distance to a line with its caps, joins and dashes
+/// - `line`: line to measure
+/// - `position`: point along the line from its start and across it
+/// - `cut`: set when the point belongs to a joined line instead
+float sd_line(const Line line, const vec2 position, out bool cut) {
+  const vec2 axis = line.end - line.start;
+  const float line_length = length(axis);
+  const vec2 dir = line_length > 0.0 ? axis / line_length : vec2(1.0, 0.0);
+  const mat2 to_local = transpose(mat2(dir, vec2(-dir.y, dir.x)));
+  const float half_thickness = line.thickness * 0.5;
+  cut = false;
+
+  float d = abs(position.y) - half_thickness;
+
+  d = sd_line_end(
+    line,
+    vec2(-position.x, position.y),
+    to_local * line.prev_offset * vec2(-1.0, 1.0),
+    d,
+    cut
+  );
+
+  d = sd_line_end(
+    line,
+    vec2(position.x - line_length, position.y),
+    to_local * line.next_offset,
+    d,
+    cut
+  );
+
+  if (line.dash_length <= 0.0) {
+    return d;
+  }
+
+  // Distance along the line to the nearest dash, whose ends are shaped like the caps
+  const float period = line.dash_length + line.gap_length;
+  const float dash_position = mod(line.dash_offset + position.x - line.dash_length * 0.5 + period * 0.5, period) - period * 0.5;
+  const float dash_d = abs(dash_position) - line.dash_length * 0.5;
+
+  switch (line.cap) {
+    case LINE_CAP_ROUND:
+      return max(d, length(vec2(max(dash_d, 0.0), position.y)) - half_thickness);
+
+    case LINE_CAP_SQUARE:
+      return max(d, dash_d - half_thickness);
+
+    default:
+      return max(d, dash_d);
+  }
+}
+
+/// Approximation of the error function with a maximum error of about 5e-4
+float erf(const float x) {
+  const float abs_x = abs(x);
+  float y = 1.0 + (0.278393 + (0.230389 + 0.078108 * abs_x * abs_x) * abs_x) * abs_x;
+  y *= y;
+  return sign(x) * (1.0 - 1.0 / (y * y));
+}
+
 void main() {
+  vec4 fill_color = color;
+
+  // Paints replace the model colour but keep its alpha as an opacity
+  if (paint_id != 0u) {
+    const uint paint_index = (paint_id & ~TRANSLUCENT_PAINT_ID_FLAG) - 1u;
+    fill_color = eval_paint(push_consts.paint_buffer.paints[paint_index], world_position - paint_origin);
+    fill_color.a *= color.a;
+  }
+
+  vec3 rgb = fill_color.rgb;
+  float color_a = fill_color.a;
   float a;
 
   switch (model_type) {
     case ROUND_RECT:
-      const float d = sd_round_rect(local_position, half_size, radius);
+      // Distance to the outer edge of the border, which is the edge of the rect for inner borders
+      const float d = sd_round_rect(local_position, half_size, radius) - border_outset;
       const float w = fwidth(d) * 0.65;
       a = 1.0 - smoothstep(-w, w, d);
+
+      // The border covers the band within its width of the outer edge and blends into the fill
+      if (border_width > 0.0) {
+        const vec4 rect_color = mix(fill_color, border_color, smoothstep(-w, w, d + border_width));
+        rgb = rect_color.rgb;
+        color_a = rect_color.a;
+      }
+      break;
+
+    case SHADOW:
+      // Coverage of a Gaussian blurred edge at the distance to the rect, which is exact for straight
+      // edges and close enough around corners
+      const float shadow_d = sd_round_rect(local_position, half_size, radius);
+      const float sigma = max(blur_radius * 0.5, fwidth(shadow_d) * 0.5);
+      a = 0.5 - 0.5 * erf(shadow_d / (sigma * sqrt(2.0)));
+      break;
+
+    case LINE:
+      bool cut;
+      const float line_d = sd_line(push_consts.line_buffer.lines[line_index], local_position, cut);
+      const float line_w = fwidth(line_d) * 0.65;
+      a = cut ? 0.0 : 1.0 - smoothstep(-line_w, line_w, line_d);
       break;
 
     case GLYPH:
-      a = texture(glyph_atlas_sampler, atlas_position).r;
+      if (atlas_page == SOLID_ATLAS_PAGE) {
+        a = 1.0;
+        break;
+      }
+
+      // Colour glyphs keep their own colours and only take the alpha of the text colour
+      if ((glyph_flags & GLYPH_FLAG_COLOR) != 0u) {
+        const vec4 glyph_color = texture(color_glyph_atlas_sampler, vec3(atlas_position, atlas_page));
+        rgb = glyph_color.rgb;
+        a = glyph_color.a;
+        break;
+      }
+
+      a = texture(glyph_atlas_sampler, vec3(atlas_position, atlas_page)).r;
+
+      // Distance fields store the edge at 0.5 and get anti-aliased over about a screen pixel
+      if ((glyph_flags & GLYPH_FLAG_SDF) != 0u) {
+        const float w = fwidth(a) * 0.65;
+        a = smoothstep(0.5 - w, 0.5 + w, a);
+      }
       break;
   }
 
-  out_color = vec4(color, a);
+  // Fully transparent fragments must not write depth, otherwise they would hide models behind them
+  a *= color_a;
+  if (a <= 0.0) discard;
+
+  out_color = vec4(rgb, a);
 }
@@ -13,15 +13,51 @@ const vec2 POSITIONS[6] = vec2[](
 // Model types
 const int ROUND_RECT = 0;
 const int GLYPH = 1;
+const int SHADOW = 2;
+const int LINE = 3;
 
-// Glyph settings
-const float GLYPH_RESOLUTION_SCALE = 2.0;
+// Border alignments
+const uint BORDER_ALIGN_INNER = 0u;
+const uint BORDER_ALIGN_CENTER = 1u;
+const uint BORDER_ALIGN_OUTER = 2u;
 
 struct RoundRect {
   vec3 position;
-  float radius;
+  uint color;
   vec2 size;
+  float radius[4];
+  float border_width;
+  uint border_color;
+  uint border_align;
+  uint paint_id;
+};
+
+struct Shadow {
+  vec3 position;
   uint color;
+  vec2 size;
+  float radius[4];
+  vec2 offset;
+  float blur_radius;
+  float spread;
+};
+
+struct Line {
+  vec2 start;
+  vec2 end;
+  vec2 prev_offset;
+  vec2 next_offset;
+  float z;
+  float thickness;
+  uint color;
+  uint cap;
+  uint join;
+  float miter_limit;
+  float dash_length;
+  float gap_length;
+  float dash_offset;
+  uint paint_id;
+  vec2 paint_offset;
 };
 
 struct Glyph {
@@ -29,30 +65,64 @@ struct Glyph {
   uint color;
   vec2 size;
   vec2 atlas_position;
+  vec2 atlas_size;
+  vec2 paint_origin;
+  uint atlas_page;
+  uint flags;
+  uint paint_id;
 };
 
 layout(buffer_reference, std430) readonly buffer RoundRectBuffer {
   RoundRect round_rects[];
 };
 
+layout(buffer_reference, std430) readonly buffer ShadowBuffer {
+  Shadow shadows[];
+};
+
+layout(buffer_reference, std430) readonly buffer LineBuffer {
+  Line lines[];
+};
+
 layout(buffer_reference, std430) readonly buffer GlyphBuffer {
   Glyph glyphs[];
 };
 
 layout(push_constant) uniform PushConsts {
   RoundRectBuffer round_rect_buffer;
+  ShadowBuffer shadow_buffer;
   GlyphBuffer glyph_buffer;
+  uvec2 paint_buffer; // Only read by the fragment shader
+  LineBuffer line_buffer;
   vec2 cam_size;
   vec2 glyph_atlas_size;
-  float window_scale_factor;
 } push_consts;
 
 layout(location = 0) flat out int model_type;
-layout(location = 1) out vec3 color;
+layout(location = 1) out vec4 color;
 layout(location = 2) out vec2 local_position;
 layout(location = 3) flat out vec2 half_size;
-layout(location = 4) flat out float radius;
+layout(location = 4) flat out vec4 radius;
 layout(location = 5) out vec2 atlas_position;
+layout(location = 6) flat out uint atlas_page;
+layout(location = 7) flat out uint glyph_flags;
+layout(location = 8) flat out vec4 border_color;
+layout(location = 9) flat out float border_width;
+layout(location = 10) flat out float border_outset;
+layout(location = 11) flat out float blur_radius;
+layout(location = 12) flat out uint paint_id;
+layout(location = 13) out vec2 world_position;
+layout(location = 14) flat out uint line_index;
+layout(location = 15) flat out vec2 paint_origin;
+
+vec4 unpack_color(const uint packed_color) {
+  return vec4(
+    (packed_color >> 24) & 0xFF,
+    (packed_color >> 16) & 0xFF,
+    (packed_color >> 8) & 0xFF,
+    packed_color & 0xFF
+  ) / 255.0;
+}
 
 void main() {
   const vec2 position = POSITIONS[gl_VertexIndex % POSITIONS.length()];
@@ -60,18 +130,92 @@ void main() {
   vec3 model_position;
   vec2 model_size;
   uint model_color;
+  paint_id = 0u;
+  paint_origin = vec2(0.0);
 
   switch (gl_BaseInstance) {
     case ROUND_RECT:
       const RoundRect round_rect = push_consts.round_rect_buffer.round_rects[model_index];
-      model_position = round_rect.position;
-      model_size = round_rect.size;
+
+      switch (round_rect.border_align) {
+        case BORDER_ALIGN_CENTER:
+          border_outset = round_rect.border_width * 0.5;
+          break;
+
+        case BORDER_ALIGN_OUTER:
+          border_outset = round_rect.border_width;
+          break;
+
+        default:
+          border_outset = 0.0;
+          break;
+      }
+
+      // Borders outside the rect grow its quad
+      model_position = round_rect.position - vec3(border_outset, border_outset, 0.0);
+      model_size = round_rect.size + vec2(border_outset * 2.0);
       model_color = round_rect.color;
 
       model_type = ROUND_RECT;
-      local_position = (position - vec2(0.5)) * round_rect.size;
+      local_position = (position - vec2(0.5)) * model_size;
       half_size = round_rect.size * 0.5;
-      radius = round_rect.radius;
+      radius = vec4(
+        round_rect.radius[0],
+        round_rect.radius[1],
+        round_rect.radius[2],
+        round_rect.radius[3]
+      );
+      border_color = unpack_color(round_rect.border_color);
+      border_width = round_rect.border_width;
+      paint_id = round_rect.paint_id;
+      paint_origin = round_rect.position.xy;
+      break;
+
+    case SHADOW:
+      const Shadow shadow = push_consts.shadow_buffer.shadows[model_index];
+
+      // The blur fades out within about three standard deviations of the spread rect
+      const float shadow_extent = max(shadow.spread, 0.0) + shadow.blur_radius * 1.5;
+      model_position = shadow.position + vec3(shadow.offset - vec2(shadow_extent), 0.0);
+      model_size = shadow.size + vec2(shadow_extent * 2.0);
+      model_color = shadow.color;
+
+      model_type = SHADOW;
+      local_position = (position - vec2(0.5)) * model_size;
+      half_size = max(shadow.size * 0.5 + vec2(shadow.spread), vec2(0.0));
+      radius = max(
+        vec4(shadow.radius[0], shadow.radius[1], shadow.radius[2], shadow.radius[3]) + vec4(shadow.spread),
+        vec4(0.0)
+      );
+      blur_radius = shadow.blur_radius;
+      break;
+
+    case LINE:
+      const Line line = push_consts.line_buffer.lines[model_index];
+      const vec2 line_axis = line.end - line.start;
+      const float line_length = length(line_axis);
+      const vec2 line_dir = line_length > 0.0 ? line_axis / line_length : vec2(1.0, 0.0);
+      const float half_thickness = line.thickness * 0.5;
+
+      // The quad is laid along the line with room for caps, miters and anti-aliasing
+      const float line_extent = half_thickness * max(line.miter_limit, 1.0) + 1.0;
+      local_position = mix(
+        vec2(-line_extent, -half_thickness - 1.0),
+        vec2(line_length + line_extent, half_thickness + 1.0),
+        position
+      );
+
+      model_position = vec3(
+        line.start + line_dir * local_position.x + vec2(-line_dir.y, line_dir.x) * local_position.y,
+        line.z
+      );
+      model_size = vec2(0.0);
+      model_color = line.color;
+
+      model_type = LINE;
+      paint_id = line.paint_id;
+      paint_origin = min(line.start, line.end) + line.paint_offset;
+      line_index = model_index;
       break;
 
     case GLYPH:
@@ -81,15 +225,21 @@ void main() {
       model_color = glyph.color;
 
       model_type = GLYPH;
-      atlas_position = (position * glyph.size * push_consts.window_scale_factor * GLYPH_RESOLUTION_SCALE + glyph.atlas_position) / push_consts.glyph_atlas_size;
+      atlas_position = (position * glyph.atlas_size + glyph.atlas_position) / push_consts.glyph_atlas_size;
+      atlas_page = glyph.atlas_page;
+      glyph_flags = glyph.flags;
+      paint_id = glyph.paint_id;
+      paint_origin = glyph.paint_origin;
       break;
   }
 
+  world_position = position * model_size + model_position.xy;
+
   gl_Position = vec4(
-    (position * model_size + model_position.xy) / push_consts.cam_size * vec2(2.0) - vec2(1.0),
+    world_position / push_consts.cam_size * vec2(2.0) - vec2(1.0),
     model_position.z,
     1.0
   );
 
-  color = vec3((model_color >> 24) & 0xFF, (model_color >> 16) & 0xFF, (model_color >> 8) & 0xFF) / 255.0;
+  color = unpack_color(model_color);
 }
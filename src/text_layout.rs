@@ -0,0 +1,910 @@
+use crate::models::{
+  align::Align, char_index::CharIndex, overflow::Overflow, rect::Rect, text_metrics::TextMetrics,
+  wrap::Wrap,
+};
+use std::ops::Range;
+use unicode_bidi::BidiInfo;
+use unicode_linebreak::BreakOpportunity;
+
+/// Width of the rect drawn at a caret position
+const CARET_WIDTH: f32 = 1.0;
+
+/// Widest gap between chars that are still selected with one rect, which hides rounding errors
+const SELECTION_GAP: f32 = 0.01;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+  pub chars: Range<usize>,
+  pub width: f32,
+}
+
+/// Line placed relative to the text position, with its chars in visual order
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacedLine {
+  /// Whether the paragraph of the line runs right to left
+  pub rtl: bool,
+  /// Where the first char in visual order starts
+  pub x: f32,
+  /// Baseline of the line
+  pub y: f32,
+  /// Chars of each piece of the line that runs in one direction in visual order, and whether it
+  /// runs right to left
+  pub visual_runs: Vec<(Range<usize>, bool)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlacedText {
+  pub lines: Vec<PlacedLine>,
+  /// Caret position before each char and after the last char, on the baseline of its line
+  pub carets: Box<[(f32, f32)]>,
+  /// Whether each char is laid out right to left
+  pub rtl_chars: Box<[bool]>,
+}
+
+/// Breaks text into lines at Unicode line break opportunities
+/// - `advances`: horizontal advance of each char in `text`
+/// - `max_width`: width a line may not exceed unless `wrap` is `Wrap::None`
+/// - `wrap`: whether lines may break between words or between any chars
+#[must_use]
+pub fn break_lines(text: &str, advances: &[f32], max_width: Option<f32>, wrap: Wrap) -> Vec<Line> {
+  let chars = text.chars().collect::<Box<[_]>>();
+  let mut break_opportunities = vec![None; chars.len() + 1];
+  let mut byte_indices = text
+    .char_indices()
+    .map(|(byte_index, _ch)| byte_index)
+    .enumerate();
+
+  for (byte_index, break_opportunity) in unicode_linebreak::linebreaks(text) {
+    let char_index = byte_indices
+      .find_map(|(char_index, char_byte_index)| {
+        (char_byte_index == byte_index).then_some(char_index)
+      })
+      .unwrap_or(chars.len());
+
+    break_opportunities[char_index] = Some(break_opportunity);
+  }
+
+  let mut offsets = Vec::with_capacity(advances.len() + 1);
+  offsets.push(0.0);
+
+  for &advance in advances {
+    offsets.push(offsets[offsets.len() - 1] + advance);
+  }
+
+  let calc_width = |line_chars: Range<usize>| {
+    let end = chars[line_chars.clone()]
+      .iter()
+      .rposition(|ch| !ch.is_whitespace())
+      .map_or(line_chars.start, |index| line_chars.start + index + 1);
+
+    offsets[end] - offsets[line_chars.start]
+  };
+
+  let max_width = max_width.filter(|_max_width| wrap != Wrap::None);
+  let mut lines = vec![];
+  let mut line_start = 0;
+  let mut line_break = None;
+
+  for (index, &ch) in chars.iter().enumerate() {
+    if index > line_start {
+      match break_opportunities[index] {
+        Some(BreakOpportunity::Mandatory) => {
+          lines.push(Line {
+            chars: line_start..index,
+            width: calc_width(line_start..index),
+          });
+
+          line_start = index;
+          line_break = None;
+        }
+        Some(BreakOpportunity::Allowed) if wrap == Wrap::Word => line_break = Some(index),
+        Some(BreakOpportunity::Allowed) | None if wrap == Wrap::Char => line_break = Some(index),
+        Some(BreakOpportunity::Allowed) | None => (),
+      }
+    }
+
+    let Some(max_width) = max_width else {
+      continue;
+    };
+
+    if index == line_start
+      || ch.is_whitespace()
+      || offsets[index + 1] - offsets[line_start] <= max_width
+    {
+      continue;
+    }
+
+    // Words that cannot fit on a line by themselves are broken between chars
+    let line_end = line_break.take().unwrap_or(index);
+
+    lines.push(Line {
+      chars: line_start..line_end,
+      width: calc_width(line_start..line_end),
+    });
+
+    line_start = line_end;
+  }
+
+  lines.push(Line {
+    chars: line_start..chars.len(),
+    width: calc_width(line_start..chars.len()),
+  });
+
+  // Text ending with a line break still starts a new empty line
+  if chars[line_start..].last().is_some_and(|&ch| {
+    matches!(
+      ch,
+      '\n' | '\u{b}' | '\u{c}' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+  }) {
+    lines.push(Line {
+      chars: chars.len()..chars.len(),
+      width: 0.0,
+    });
+  }
+
+  lines
+}
+
+/// Char ranges to replace with an ellipsis each, so that every line wider than `max_width` fits
+/// next to its ellipsis. Trailing whitespace and line breaks are never elided.
+/// - `text`, `advances`, `wrap`: as in `break_lines`
+/// - `max_width`: width each line has to fit in
+/// - `overflow`: `Overflow::MiddleEllipsis` keeps both ends of a line, anything else keeps its
+///   start
+/// - `ellipsis_widths`: width of the ellipsis standing in for chars elided from each char on
+#[must_use]
+pub fn elide_lines(
+  text: &str,
+  advances: &[f32],
+  max_width: f32,
+  wrap: Wrap,
+  overflow: Overflow,
+  ellipsis_widths: &[f32],
+) -> Vec<Range<usize>> {
+  let chars = text.chars().collect::<Box<[_]>>();
+  let mut elided_ranges = vec![];
+
+  for line in break_lines(text, advances, Some(max_width), wrap) {
+    if line.width <= max_width {
+      continue;
+    }
+
+    let content_end = chars[line.chars.clone()]
+      .iter()
+      .rposition(|ch| !ch.is_whitespace())
+      .map_or(line.chars.start, |index| line.chars.start + index + 1);
+
+    let budget = (max_width - ellipsis_widths[line.chars.start]).max(0.0);
+
+    let prefix_budget = if overflow == Overflow::MiddleEllipsis {
+      budget * 0.5
+    } else {
+      budget
+    };
+
+    let mut prefix_end = line.chars.start;
+    let mut prefix_width = 0.0;
+
+    while prefix_end < content_end && prefix_width + advances[prefix_end] <= prefix_budget {
+      prefix_width += advances[prefix_end];
+      prefix_end += 1;
+    }
+
+    let mut suffix_start = content_end;
+
+    if overflow == Overflow::MiddleEllipsis {
+      let mut suffix_width = 0.0;
+
+      while suffix_start > prefix_end
+        && prefix_width + suffix_width + advances[suffix_start - 1] <= budget
+      {
+        suffix_width += advances[suffix_start - 1];
+        suffix_start -= 1;
+      }
+    }
+
+    if prefix_end < suffix_start {
+      elided_ranges.push(prefix_end..suffix_start);
+    }
+  }
+
+  elided_ranges
+}
+
+/// Maps the metrics of text with ellipses back to the chars of the text they were elided from
+///
+/// Elided chars collapse onto their ellipsis: the first one takes its advance, and the carets of
+/// the rest sit after it.
+/// - `metrics`: metrics of the text with ellipses
+/// - `rtl_chars`: whether each char of the text with ellipses is laid out right to left
+/// - `elided_ranges`: ascending char ranges of the original text, as `elide_lines` returns
+#[must_use]
+pub fn unelide_metrics(
+  metrics: &TextMetrics,
+  rtl_chars: &[bool],
+  elided_ranges: &[Range<usize>],
+) -> (TextMetrics, Box<[bool]>) {
+  let char_count = metrics.advances.len()
+    + elided_ranges
+      .iter()
+      .map(|elided_range| elided_range.len() - 1)
+      .sum::<usize>();
+
+  let mut advances = Vec::with_capacity(char_count);
+  let mut carets = Vec::with_capacity(char_count + 1);
+  let mut unelided_rtl_chars = Vec::with_capacity(char_count);
+  let mut elided_ranges = elided_ranges.iter().peekable();
+  let mut char_index = 0;
+
+  for (elided_index, (&advance, &rtl)) in metrics.advances.iter().zip(rtl_chars).enumerate() {
+    let (caret_x, caret_y) = metrics.carets[elided_index];
+    advances.push(advance);
+    carets.push((caret_x, caret_y));
+    unelided_rtl_chars.push(rtl);
+    char_index += 1;
+
+    let Some(elided_range) =
+      elided_ranges.next_if(|elided_range| elided_range.start + 1 == char_index)
+    else {
+      continue;
+    };
+
+    let trailing_x = if rtl {
+      caret_x - advance
+    } else {
+      caret_x + advance
+    };
+
+    for _elided_char_index in char_index..elided_range.end {
+      advances.push(0.0);
+      carets.push((trailing_x, caret_y));
+      unelided_rtl_chars.push(rtl);
+    }
+
+    char_index = elided_range.end;
+  }
+
+  carets.push(metrics.carets[metrics.advances.len()]);
+
+  (
+    TextMetrics {
+      advances: advances.into_boxed_slice(),
+      carets: carets.into_boxed_slice(),
+      ..*metrics
+    },
+    unelided_rtl_chars.into_boxed_slice(),
+  )
+}
+
+/// Places lines of text at their baselines and orders their chars visually, in the direction of
+/// their paragraph
+/// - `text`, `advances`: as in `break_lines`
+/// - `lines`: lines of `text`, as `break_lines` returns
+/// - `line_ys`: baseline of each line relative to the text position
+/// - `align`: side of the text position lines are aligned to, relative to the direction of their
+///   paragraph
+#[must_use]
+pub fn place_lines(
+  text: &str,
+  advances: &[f32],
+  lines: &[Line],
+  line_ys: &[f32],
+  align: Align,
+) -> PlacedText {
+  let bidi_info = BidiInfo::new(text, None);
+  let chars = text.char_indices().collect::<Box<[_]>>();
+  let mut placed_lines = Vec::with_capacity(lines.len());
+  let mut carets = vec![(0.0, 0.0); chars.len() + 1];
+  let mut rtl_chars = vec![false; chars.len()];
+
+  for (line, &line_y) in lines.iter().zip(line_ys) {
+    let line_bytes = chars
+      .get(line.chars.start)
+      .map_or(text.len(), |&(byte_index, _ch)| byte_index)
+      ..chars
+        .get(line.chars.end)
+        .map_or(text.len(), |&(byte_index, _ch)| byte_index);
+
+    let paragraph = bidi_info
+      .paragraphs
+      .iter()
+      .find(|paragraph| paragraph.range.contains(&line_bytes.start))
+      .or_else(|| bidi_info.paragraphs.last());
+
+    let rtl = paragraph.is_some_and(|paragraph| paragraph.level.is_rtl());
+
+    let line_left = match (align, rtl) {
+      (Align::Left, false) | (Align::Right, true) => 0.0,
+      (Align::Center, _) => line.width * -0.5,
+      (Align::Right, false) | (Align::Left, true) => -line.width,
+    };
+
+    // Trailing whitespace of a right to left line sits at its left end
+    let line_x = if rtl {
+      line_left + line.width - advances[line.chars.clone()].iter().sum::<f32>()
+    } else {
+      line_left
+    };
+
+    carets[line.chars.start] = (line_x, line_y);
+
+    let (levels, visual_runs) = match paragraph {
+      Some(paragraph) if !line_bytes.is_empty() => bidi_info.visual_runs(paragraph, line_bytes),
+      _ => (vec![], vec![]),
+    };
+
+    let mut caret_x = line_x;
+    let mut placed_runs = Vec::with_capacity(visual_runs.len());
+
+    for visual_run in visual_runs {
+      let run_rtl = levels[visual_run.start].is_rtl();
+
+      let run_chars = chars.partition_point(|&(byte_index, _ch)| byte_index < visual_run.start)
+        ..chars.partition_point(|&(byte_index, _ch)| byte_index < visual_run.end);
+
+      let mut run_char_indices = run_chars.clone().collect::<Vec<_>>();
+
+      if run_rtl {
+        run_char_indices.reverse();
+      }
+
+      // Carets sit on the leading edge of each char, which is its right edge in right to left
+      // runs
+      for char_index in run_char_indices {
+        let next_caret_x = caret_x + advances[char_index];
+        let (leading_x, trailing_x) = if run_rtl {
+          (next_caret_x, caret_x)
+        } else {
+          (caret_x, next_caret_x)
+        };
+
+        carets[char_index] = (leading_x, line_y);
+        rtl_chars[char_index] = run_rtl;
+
+        if char_index + 1 == chars.len() {
+          carets[chars.len()] = (trailing_x, line_y);
+        }
+
+        caret_x = next_caret_x;
+      }
+
+      placed_runs.push((run_chars, run_rtl));
+    }
+
+    placed_lines.push(PlacedLine {
+      rtl,
+      x: line_x,
+      y: line_y,
+      visual_runs: placed_runs,
+    });
+  }
+
+  PlacedText {
+    lines: placed_lines,
+    carets: carets.into_boxed_slice(),
+    rtl_chars: rtl_chars.into_boxed_slice(),
+  }
+}
+
+/// Index of the caret position closest to `point`, on the line closest to it
+/// - `metrics`: metrics of laid out text
+/// - `rtl_chars`: whether each char is laid out right to left
+/// - `point`: position relative to the text position
+#[must_use]
+pub fn hit_test(metrics: &TextMetrics, rtl_chars: &[bool], point: (f32, f32)) -> CharIndex {
+  let (point_x, point_y) = point;
+  let line_middle_offset = (metrics.descent - metrics.ascent) * 0.5;
+
+  let Some(line_y) = metrics
+    .carets
+    .iter()
+    .map(|&(_caret_x, caret_y)| caret_y)
+    .min_by(|&caret_y, &other_caret_y| {
+      (point_y - caret_y - line_middle_offset)
+        .abs()
+        .total_cmp(&(point_y - other_caret_y - line_middle_offset).abs())
+    })
+  else {
+    return 0;
+  };
+
+  // Each char spans from its leading edge to its trailing edge, the caret after the last char
+  // spans nothing
+  let char_edges = metrics
+    .carets
+    .iter()
+    .enumerate()
+    .filter(|&(_char_index, &(_caret_x, caret_y))| caret_y == line_y)
+    .map(|(char_index, &(caret_x, _caret_y))| {
+      let trailing_x = match metrics.advances.get(char_index) {
+        Some(&advance) if rtl_chars[char_index] => caret_x - advance,
+        Some(&advance) => caret_x + advance,
+        None => caret_x,
+      };
+
+      (char_index, caret_x, trailing_x)
+    });
+
+  let calc_distance = |leading_x: f32, trailing_x: f32| {
+    (leading_x.min(trailing_x) - point_x)
+      .max(point_x - leading_x.max(trailing_x))
+      .max(0.0)
+  };
+
+  char_edges
+    .min_by(
+      |&(_char_index, leading_x, trailing_x),
+       &(_other_char_index, other_leading_x, other_trailing_x)| {
+        calc_distance(leading_x, trailing_x)
+          .total_cmp(&calc_distance(other_leading_x, other_trailing_x))
+      },
+    )
+    .map_or(0, |(char_index, leading_x, trailing_x)| {
+      if (point_x - leading_x).abs() <= (point_x - trailing_x).abs() {
+        char_index
+      } else {
+        char_index + 1
+      }
+    })
+}
+
+/// Thin rect spanning the line at the caret position before `char_index`, relative to the text
+/// position
+/// - `metrics`: metrics of laid out text
+/// - `char_index`: index of the caret, clamped to the end of the text
+#[must_use]
+pub fn caret_rect(metrics: &TextMetrics, char_index: CharIndex) -> Rect {
+  let (caret_x, caret_y) = metrics.carets[char_index.min(metrics.carets.len() - 1)];
+
+  Rect {
+    position: (caret_x, caret_y - metrics.ascent),
+    size: (CARET_WIDTH, metrics.ascent + metrics.descent),
+  }
+}
+
+/// Rects covering the chars in `char_range`, one for each visually contiguous piece of a line,
+/// relative to the text position
+/// - `metrics`: metrics of laid out text
+/// - `rtl_chars`: whether each char is laid out right to left
+/// - `char_range`: chars to cover, clamped to the end of the text
+#[must_use]
+pub fn selection_rects(
+  metrics: &TextMetrics,
+  rtl_chars: &[bool],
+  char_range: Range<CharIndex>,
+) -> Box<[Rect]> {
+  let char_range = char_range.start..char_range.end.min(metrics.advances.len());
+
+  // Line y, left x and right x of each selected char
+  let mut char_bounds = char_range
+    .map(|char_index| {
+      let (caret_x, caret_y) = metrics.carets[char_index];
+      let advance = metrics.advances[char_index];
+
+      if rtl_chars[char_index] {
+        (caret_y, caret_x - advance, caret_x)
+      } else {
+        (caret_y, caret_x, caret_x + advance)
+      }
+    })
+    .collect::<Vec<_>>();
+
+  char_bounds.sort_unstable_by(
+    |&(line_y, left_x, _right_x), &(other_line_y, other_left_x, _other_right_x)| {
+      line_y
+        .total_cmp(&other_line_y)
+        .then(left_x.total_cmp(&other_left_x))
+    },
+  );
+
+  let mut selection_bounds = Vec::<(f32, f32, f32)>::new();
+
+  for (line_y, left_x, right_x) in char_bounds {
+    match selection_bounds.last_mut() {
+      Some(&mut (selection_line_y, _selection_left_x, ref mut selection_right_x))
+        if selection_line_y == line_y && left_x <= *selection_right_x + SELECTION_GAP =>
+      {
+        *selection_right_x = selection_right_x.max(right_x);
+      }
+      _ => selection_bounds.push((line_y, left_x, right_x)),
+    }
+  }
+
+  selection_bounds
+    .into_iter()
+    .map(|(line_y, left_x, right_x)| Rect {
+      position: (left_x, line_y - metrics.ascent),
+      size: (right_x - left_x, metrics.ascent + metrics.descent),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Line, PlacedLine, PlacedText};
+  use crate::models::{
+    align::Align, overflow::Overflow, rect::Rect, text_metrics::TextMetrics, wrap::Wrap,
+  };
+  use std::ops::Range;
+
+  fn break_lines(text: &str, max_width: Option<f32>, wrap: Wrap) -> Vec<Line> {
+    let advances = text.chars().map(|_ch| 10.0).collect::<Vec<_>>();
+    super::break_lines(text, &advances, max_width, wrap)
+  }
+
+  /// Places each line of text 20 pixels below the one before it
+  fn place_lines(text: &str, align: Align) -> PlacedText {
+    let advances = text.chars().map(|_ch| 10.0).collect::<Vec<_>>();
+    let lines = super::break_lines(text, &advances, None, Wrap::None);
+    let line_ys = (0..lines.len())
+      .map(|line_index| line_index as f32 * 20.0)
+      .collect::<Vec<_>>();
+
+    super::place_lines(text, &advances, &lines, &line_ys, align)
+  }
+
+  /// Metrics of left aligned text, with chars 10 pixels wide and lines 20 pixels apart
+  fn make_metrics(text: &str) -> (TextMetrics, Box<[bool]>) {
+    let PlacedText {
+      carets, rtl_chars, ..
+    } = place_lines(text, Align::Left);
+
+    let metrics = TextMetrics {
+      ascent: 8.0,
+      descent: 2.0,
+      advances: text.chars().map(|_ch| 10.0).collect(),
+      carets,
+      ..Default::default()
+    };
+
+    (metrics, rtl_chars)
+  }
+
+  fn caret_xs(carets: &[(f32, f32)]) -> Vec<f32> {
+    carets.iter().map(|&(caret_x, _caret_y)| caret_x).collect()
+  }
+
+  fn elide_lines(text: &str, max_width: f32, overflow: Overflow) -> Vec<Range<usize>> {
+    let advances = text.chars().map(|_ch| 10.0).collect::<Vec<_>>();
+    super::elide_lines(text, &advances, max_width, Wrap::None, overflow, &advances)
+  }
+
+  #[test]
+  fn break_lines_empty() {
+    assert_eq!(
+      break_lines("", None, Wrap::Word),
+      [Line {
+        chars: 0..0,
+        width: 0.0
+      }]
+    );
+  }
+
+  #[test]
+  fn break_lines_single_line() {
+    assert_eq!(
+      break_lines("hello world", Some(200.0), Wrap::Word),
+      [Line {
+        chars: 0..11,
+        width: 110.0
+      }]
+    );
+  }
+
+  #[test]
+  fn break_lines_newlines() {
+    assert_eq!(
+      break_lines("ab\ncd\n", None, Wrap::None),
+      [
+        Line {
+          chars: 0..3,
+          width: 20.0
+        },
+        Line {
+          chars: 3..6,
+          width: 20.0
+        },
+        Line {
+          chars: 6..6,
+          width: 0.0
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn break_lines_word_wrap() {
+    assert_eq!(
+      break_lines("hello big world", Some(100.0), Wrap::Word),
+      [
+        Line {
+          chars: 0..10,
+          width: 90.0
+        },
+        Line {
+          chars: 10..15,
+          width: 50.0
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn break_lines_word_wrap_long_word() {
+    assert_eq!(
+      break_lines("abcdef gh", Some(40.0), Wrap::Word),
+      [
+        Line {
+          chars: 0..4,
+          width: 40.0
+        },
+        Line {
+          chars: 4..7,
+          width: 20.0
+        },
+        Line {
+          chars: 7..9,
+          width: 20.0
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn break_lines_char_wrap() {
+    assert_eq!(
+      break_lines("hello world", Some(40.0), Wrap::Char),
+      [
+        Line {
+          chars: 0..4,
+          width: 40.0
+        },
+        Line {
+          chars: 4..8,
+          width: 40.0
+        },
+        Line {
+          chars: 8..11,
+          width: 30.0
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn break_lines_no_wrap() {
+    assert_eq!(
+      break_lines("hello world", Some(40.0), Wrap::None),
+      [Line {
+        chars: 0..11,
+        width: 110.0
+      }]
+    );
+  }
+
+  #[test]
+  fn elide_lines_keeps_fitting_lines() {
+    assert_eq!(elide_lines("hello", 60.0, Overflow::Ellipsis), []);
+  }
+
+  #[test]
+  fn elide_lines_end() {
+    assert_eq!(
+      elide_lines("hello world", 60.0, Overflow::Ellipsis),
+      [Range { start: 5, end: 11 }]
+    );
+  }
+
+  #[test]
+  fn elide_lines_middle() {
+    assert_eq!(
+      elide_lines("hello world", 60.0, Overflow::MiddleEllipsis),
+      [Range { start: 2, end: 8 }]
+    );
+  }
+
+  #[test]
+  fn elide_lines_keeps_trailing_whitespace_and_line_breaks() {
+    assert_eq!(
+      elide_lines("hello world  \nbye", 60.0, Overflow::Ellipsis),
+      [Range { start: 5, end: 11 }]
+    );
+  }
+
+  #[test]
+  fn unelide_metrics_collapses_elided_chars_onto_ellipsis() {
+    // "hello…" laid out for "hello world"
+    let metrics = TextMetrics {
+      advances: [10.0; 6].into(),
+      carets: (0..=6_u8)
+        .map(|index| (f32::from(index) * 10.0, 0.0))
+        .collect(),
+      ..Default::default()
+    };
+
+    let (metrics, rtl_chars) =
+      super::unelide_metrics(&metrics, &[false; 6], &[Range { start: 5, end: 11 }]);
+
+    assert_eq!(
+      *metrics.advances,
+      [10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+    );
+
+    assert_eq!(
+      metrics
+        .carets
+        .iter()
+        .map(|&(caret_x, _caret_y)| caret_x)
+        .collect::<Vec<_>>(),
+      [
+        0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 60.0, 60.0, 60.0, 60.0, 60.0
+      ]
+    );
+
+    assert_eq!(*rtl_chars, [false; 11]);
+  }
+
+  #[test]
+  fn unelide_metrics_right_to_left() {
+    let metrics = TextMetrics {
+      advances: [10.0, 10.0].into(),
+      carets: [(20.0, 0.0), (10.0, 0.0), (0.0, 0.0)].into(),
+      ..Default::default()
+    };
+
+    let (metrics, rtl_chars) =
+      super::unelide_metrics(&metrics, &[true, true], &[Range { start: 1, end: 3 }]);
+
+    assert_eq!(*metrics.advances, [10.0, 10.0, 0.0]);
+    assert_eq!(
+      *metrics.carets,
+      [(20.0, 0.0), (10.0, 0.0), (0.0, 0.0), (0.0, 0.0)]
+    );
+    assert_eq!(*rtl_chars, [true; 3]);
+  }
+
+  #[test]
+  fn place_lines_left_to_right() {
+    assert_eq!(
+      place_lines("ab\ncd", Align::Left),
+      PlacedText {
+        lines: vec![
+          PlacedLine {
+            rtl: false,
+            x: 0.0,
+            y: 0.0,
+            visual_runs: vec![(0..3, false)],
+          },
+          PlacedLine {
+            rtl: false,
+            x: 0.0,
+            y: 20.0,
+            visual_runs: vec![(3..5, false)],
+          },
+        ],
+        carets: [
+          (0.0, 0.0),
+          (10.0, 0.0),
+          (20.0, 0.0),
+          (0.0, 20.0),
+          (10.0, 20.0),
+          (20.0, 20.0)
+        ]
+        .into(),
+        rtl_chars: [false; 5].into(),
+      }
+    );
+  }
+
+  #[test]
+  fn place_lines_align() {
+    assert_eq!(
+      caret_xs(&place_lines("ab", Align::Center).carets),
+      [-10.0, 0.0, 10.0]
+    );
+
+    assert_eq!(
+      caret_xs(&place_lines("ab", Align::Right).carets),
+      [-20.0, -10.0, 0.0]
+    );
+  }
+
+  #[test]
+  fn place_lines_right_to_left() {
+    let placed_text = place_lines("שלום", Align::Left);
+
+    // Left alignment starts a right to left paragraph at the text position
+    assert_eq!(
+      caret_xs(&placed_text.carets),
+      [0.0, -10.0, -20.0, -30.0, -40.0]
+    );
+
+    assert_eq!(*placed_text.rtl_chars, [true; 4]);
+    assert_eq!(placed_text.lines[0].visual_runs, [(0..4, true)]);
+  }
+
+  #[test]
+  fn place_lines_mixed_direction() {
+    let placed_text = place_lines("ab שלום cd", Align::Left);
+
+    assert_eq!(
+      placed_text.lines[0].visual_runs,
+      [(0..3, false), (3..7, true), (7..10, false)]
+    );
+
+    assert_eq!(
+      caret_xs(&placed_text.carets),
+      [
+        0.0, 10.0, 20.0, 70.0, 60.0, 50.0, 40.0, 70.0, 80.0, 90.0, 100.0
+      ]
+    );
+  }
+
+  #[test]
+  fn hit_test_finds_closest_caret() {
+    let (metrics, rtl_chars) = make_metrics("ab\ncd");
+
+    assert_eq!(super::hit_test(&metrics, &rtl_chars, (-5.0, 0.0)), 0);
+    assert_eq!(super::hit_test(&metrics, &rtl_chars, (12.0, 0.0)), 1);
+    assert_eq!(super::hit_test(&metrics, &rtl_chars, (18.0, 19.0)), 5);
+  }
+
+  #[test]
+  fn hit_test_right_to_left() {
+    let (metrics, rtl_chars) = make_metrics("שלום");
+
+    assert_eq!(super::hit_test(&metrics, &rtl_chars, (-12.0, 0.0)), 1);
+    assert_eq!(super::hit_test(&metrics, &rtl_chars, (-50.0, 0.0)), 4);
+  }
+
+  #[test]
+  fn caret_rect_spans_line() {
+    let (metrics, _rtl_chars) = make_metrics("ab\ncd");
+
+    assert_eq!(
+      super::caret_rect(&metrics, 4),
+      Rect {
+        position: (10.0, 12.0),
+        size: (1.0, 10.0),
+      }
+    );
+
+    assert_eq!(
+      super::caret_rect(&metrics, 99),
+      super::caret_rect(&metrics, 5)
+    );
+  }
+
+  #[test]
+  fn selection_rects_span_each_line() {
+    let (metrics, rtl_chars) = make_metrics("ab\ncd");
+
+    assert_eq!(
+      *super::selection_rects(&metrics, &rtl_chars, 1..5),
+      [
+        Rect {
+          position: (10.0, -8.0),
+          size: (20.0, 10.0),
+        },
+        Rect {
+          position: (0.0, 12.0),
+          size: (20.0, 10.0),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn selection_rects_join_mixed_direction_chars() {
+    let (metrics, rtl_chars) = make_metrics("ab שלום cd");
+
+    assert_eq!(
+      *super::selection_rects(&metrics, &rtl_chars, 0..10),
+      [Rect {
+        position: (0.0, -8.0),
+        size: (100.0, 10.0),
+      }]
+    );
+  }
+}
@@ -62,3 +62,15 @@ pub const fn lerp_color(
     (alpha_a as f32 + (alpha_b as f32 - alpha_a as f32) * scale) as u8,
   )
 }
+
+#[must_use]
+#[inline]
+pub const fn scale_alpha(color: (u8, u8, u8, u8), opacity: f32) -> (u8, u8, u8, u8) {
+  let (red, green, blue, alpha) = color;
+  (
+    red,
+    green,
+    blue,
+    (alpha as f32 * opacity.clamp(0.0, 1.0)).round() as u8,
+  )
+}
@@ -1,6 +1,9 @@
 use crate::{
   glyph_renderer::{IconId, TextId},
-  models::{align::Align, font_key::FontKey, icon::Icon, round_rect::RoundRect, text::Text},
+  models::{
+    font_key::FontKey, icon::Icon, icon_glyph::IconGlyph, round_rect::RoundRect, text::Text,
+    vertical_anchor::VerticalAnchor,
+  },
   renderer_ref::RendererRef,
   sdf, utils,
   widgets::ripple::Ripple,
@@ -42,11 +45,13 @@ pub struct Button {
   old_position: (f32, f32, f32),
   position: (f32, f32, f32),
   size: (f32, f32),
-  radius: f32,
+  radius: [f32; 4],
   color: (u8, u8, u8, u8),
   text_color: (u8, u8, u8, u8),
-  icon_font_path: Cow<'static, str>,
-  icon_codepoint: u16,
+  old_opacity: f32,
+  opacity: f32,
+  icon_font_key: FontKey,
+  icon_glyph: Option<IconGlyph>,
   old_text: Cow<'static, str>,
   text: Cow<'static, str>,
   on_click: Option<Box<OnClick>>,
@@ -71,11 +76,12 @@ impl Button {
   pub fn new(
     #[optarg_default] position: (f32, f32, f32),
     #[optarg((80.0, 40.0))] size: (f32, f32),
-    #[optarg(8.0)] radius: f32,
+    #[optarg([8.0; 4])] radius: [f32; 4],
     #[optarg((255, 255, 255, 255))] color: (u8, u8, u8, u8),
     #[optarg((0, 0, 0, 255))] text_color: (u8, u8, u8, u8),
-    #[optarg_default] icon_font_path: Cow<'static, str>,
-    #[optarg_default] icon_codepoint: u16,
+    #[optarg(1.0)] opacity: f32,
+    #[optarg_default] icon_font_key: FontKey,
+    #[optarg_default] icon_glyph: Option<IconGlyph>,
     #[optarg_default] text: Cow<'static, str>,
   ) -> Self {
     Self {
@@ -85,8 +91,10 @@ impl Button {
       radius,
       color,
       text_color,
-      icon_font_path,
-      icon_codepoint,
+      old_opacity: opacity,
+      opacity,
+      icon_font_key,
+      icon_glyph,
       old_text: text.clone(),
       text,
       on_click: None,
@@ -134,108 +142,93 @@ impl Button {
   pub fn init(&mut self, renderer: &mut RendererRef<'_>) {
     let (width, height) = self.size;
     let (x, y, z) = self.position;
+    let color = utils::scale_alpha(self.color, self.opacity);
+    let text_color = utils::scale_alpha(self.text_color, self.opacity);
 
     self.round_rect_render_id = renderer.add_model(
       RoundRect {
         position: (x, y, z),
         radius: self.radius,
         size: self.size,
-        color: utils::pack_color(self.color),
+        color: utils::pack_color(color),
+        ..Default::default()
       },
       false,
     );
 
-    let (icon_render_id, icon_width, children_width) = if self.icon_codepoint != 0 {
-      let icon_render_id = renderer.add_icon(
-        Icon {
-          position: (0.0, 0.0, 0.0),
-          color: utils::pack_color(self.text_color),
-          font_size: height * 0.5,
-          font_key: FontKey::Path(self.icon_font_path.clone()),
-          codepoint: self.icon_codepoint,
-        },
-        false,
-      );
+    let icon = self.icon_glyph.clone().map(|glyph| Icon {
+      position: (0.0, 0.0, 0.0),
+      color: utils::pack_color(text_color),
+      font_size: height * 0.5,
+      font_key: self.icon_font_key.clone(),
+      vertical_anchor: VerticalAnchor::Middle,
+      glyph,
+    });
 
-      let (icon_width, _icon_height) = renderer.get_icon_size(&icon_render_id);
-      (Some(icon_render_id), icon_width, icon_width)
-    } else {
-      (None, 0.0, 0.0)
+    let text = Text {
+      position: (0.0, 0.0, 0.0),
+      color: utils::pack_color(text_color),
+      font_size: height * 0.4,
+      font_key: FontKey::Family {
+        font_family: (&[FamilyName::SansSerif]).into(),
+        font_props: Properties {
+          weight: Weight::SEMIBOLD,
+          ..Default::default()
+        },
+      },
+      text: self.text.clone(),
+      ..Default::default()
     };
 
-    let children_width = if self.icon_codepoint != 0 && !self.text.is_empty() {
-      children_width + ICON_MARGIN
+    let icon_width = icon
+      .as_ref()
+      .map_or(0.0, |icon| renderer.measure_icon(icon.clone()).width);
+
+    let children_width = if icon.is_some() && !self.text.is_empty() {
+      icon_width + ICON_MARGIN
     } else {
-      children_width
+      icon_width
     };
 
-    let (text_render_id, children_width) = if self.text.is_empty() {
-      (None, children_width)
+    let children_width = if self.text.is_empty() {
+      children_width
     } else {
-      let text_render_id = renderer.add_text(
-        &Text {
-          position: (0.0, 0.0, 0.0),
-          color: utils::pack_color(self.text_color),
-          font_size: height * 0.4,
-          font_key: FontKey::Family {
-            font_family: (&[FamilyName::SansSerif]).into(),
-            font_props: Properties {
-              weight: Weight::SEMIBOLD,
-              ..Default::default()
-            },
-          },
-          align: Align::Left,
-          text: self.text.clone(),
-        },
-        false,
-      );
-
-      let (text_width, _text_height) = renderer.get_text_size(&text_render_id);
-      (Some(text_render_id), children_width + text_width)
+      children_width + renderer.measure_text(&text).width
     };
 
     let child_x = (width - children_width).mul_add(0.5, x);
 
-    let child_x = if let Some(icon_render_id) = icon_render_id {
-      let final_icon_render_id = renderer.add_icon(
+    let child_x = if let Some(icon) = icon {
+      self.icon_render_id = Some(renderer.add_icon(
         Icon {
-          position: (child_x, height.mul_add(0.75, y), z + TEXT_Z_OFFSET),
-          color: utils::pack_color(self.text_color),
-          font_size: height * 0.5,
-          font_key: FontKey::Path(self.icon_font_path.clone()),
-          codepoint: self.icon_codepoint,
+          position: (child_x, height.mul_add(0.5, y), z + TEXT_Z_OFFSET),
+          ..icon
         },
         false,
-      );
+      ));
 
-      renderer.remove_icon(icon_render_id);
-      self.icon_render_id = Some(final_icon_render_id);
       child_x + icon_width + ICON_MARGIN
     } else {
       child_x
     };
 
-    if let Some(text_render_id) = text_render_id {
-      let final_text_render_id = renderer.add_text(
+    if !self.text.is_empty() {
+      // Capital letters are centered, so that text lines up with icons in any font
+      let cap_height = renderer
+        .get_font_metrics(&text.font_key, text.font_size)
+        .cap_height;
+
+      self.text_render_id = Some(renderer.add_text(
         &Text {
-          position: (child_x, height.mul_add(0.65, y), z + TEXT_Z_OFFSET),
-          color: utils::pack_color(self.text_color),
-          font_size: height * 0.4,
-          font_key: FontKey::Family {
-            font_family: (&[FamilyName::SansSerif]).into(),
-            font_props: Properties {
-              weight: Weight::SEMIBOLD,
-              ..Default::default()
-            },
-          },
-          align: Align::Left,
-          text: self.text.clone(),
+          position: (
+            child_x,
+            cap_height.mul_add(0.5, height.mul_add(0.5, y)),
+            z + TEXT_Z_OFFSET,
+          ),
+          ..text
         },
         false,
-      );
-
-      renderer.remove_text(text_render_id);
-      self.text_render_id = Some(final_text_render_id);
+      ));
     }
   }
 
@@ -244,6 +237,13 @@ impl Button {
     self.position = position;
   }
 
+  /// Scales the alpha of each model of the button, which are blended one by one instead of as a
+  /// group, so a translucent button shows its background through its ripples and text
+  #[inline]
+  pub const fn set_opacity(&mut self, opacity: f32) {
+    self.opacity = opacity;
+  }
+
   #[inline]
   pub fn set_text(&mut self, text: Cow<'static, str>) {
     self.text = text;
@@ -274,8 +274,9 @@ impl Button {
     };
 
     if matches!(self.state, State::Initial) && !matches!(state, State::Initial) {
-      let window = renderer.get_window();
-      window.set_cursor(Cursor::Icon(CursorIcon::Pointer));
+      if let Some(window) = renderer.get_window() {
+        window.set_cursor(Cursor::Icon(CursorIcon::Pointer));
+      }
 
       if let Some(ref mut on_mouse_enter) = self.on_mouse_enter {
         on_mouse_enter();
@@ -289,8 +290,9 @@ impl Button {
     }
 
     if !matches!(self.state, State::Initial) && matches!(state, State::Initial) {
-      let window = renderer.get_window();
-      window.set_cursor(Cursor::Icon(CursorIcon::Default));
+      if let Some(window) = renderer.get_window() {
+        window.set_cursor(Cursor::Icon(CursorIcon::Default));
+      }
 
       if let Some(ref mut on_mouse_leave) = self.on_mouse_leave {
         on_mouse_leave();
@@ -333,6 +335,7 @@ impl Button {
             .clipped(true)
             .call();
 
+          ripple.set_opacity(self.opacity);
           ripple.init(renderer);
           self.ripples.push_back(ripple);
 
@@ -384,10 +387,13 @@ impl Button {
     }
 
     let color = Self::scale_color(self.color, self.color_scale);
+    let round_rect_color = utils::scale_alpha(color, self.opacity);
+    let text_color = utils::scale_alpha(self.text_color, self.opacity);
 
     if self.position != self.old_position
       || self.scale != old_scale
       || self.color_scale != old_color_scale
+      || self.opacity != self.old_opacity
       || self.text != self.old_text
     {
       let (scaled_width, scaled_height) = (width * self.scale, height * self.scale);
@@ -400,76 +406,63 @@ impl Button {
         self.round_rect_render_id,
         RoundRect {
           position: (x, y, z),
-          radius: self.radius * self.scale,
+          radius: self.radius.map(|radius| radius * self.scale),
           size: (scaled_width, scaled_height),
-          color: utils::pack_color(color),
+          color: utils::pack_color(round_rect_color),
+          ..Default::default()
         },
         false,
       );
 
-      let (icon_render_id, icon_width, children_width) =
-        if let Some(icon_render_id) = self.icon_render_id.take() {
-          let final_icon_render_id = renderer.add_icon(
-            Icon {
-              position: (0.0, 0.0, 0.0),
-              color: utils::pack_color(self.text_color),
-              font_size: scaled_height * 0.5,
-              font_key: FontKey::Path(self.icon_font_path.clone()),
-              codepoint: self.icon_codepoint,
-            },
-            false,
-          );
-
-          renderer.remove_icon(icon_render_id);
-          let (icon_width, _icon_height) = renderer.get_icon_size(&final_icon_render_id);
-          (Some(final_icon_render_id), icon_width, icon_width)
-        } else {
-          (None, 0.0, 0.0)
-        };
-
-      let children_width = if self.icon_codepoint != 0 && !self.text.is_empty() {
-        ICON_MARGIN.mul_add(self.scale, children_width)
+      let icon = self.icon_glyph.clone().map(|glyph| Icon {
+        position: (0.0, 0.0, 0.0),
+        color: utils::pack_color(text_color),
+        font_size: scaled_height * 0.5,
+        font_key: self.icon_font_key.clone(),
+        vertical_anchor: VerticalAnchor::Middle,
+        glyph,
+      });
+
+      let text = Text {
+        position: (0.0, 0.0, 0.0),
+        color: utils::pack_color(text_color),
+        font_size: scaled_height * 0.4,
+        font_key: FontKey::Family {
+          font_family: (&[FamilyName::SansSerif]).into(),
+          font_props: Properties {
+            weight: Weight::SEMIBOLD,
+            ..Default::default()
+          },
+        },
+        text: self.text.clone(),
+        ..Default::default()
+      };
+
+      let icon_width = icon
+        .as_ref()
+        .map_or(0.0, |icon| renderer.measure_icon(icon.clone()).width);
+
+      let children_width = if icon.is_some() && !self.text.is_empty() {
+        ICON_MARGIN.mul_add(self.scale, icon_width)
       } else {
-        children_width
+        icon_width
       };
 
-      let (text_render_id, children_width) =
-        if let Some(text_render_id) = self.text_render_id.take() {
-          let final_text_render_id = renderer.add_text(
-            &Text {
-              position: (0.0, 0.0, 0.0),
-              color: utils::pack_color(self.text_color),
-              font_size: scaled_height * 0.4,
-              font_key: FontKey::Family {
-                font_family: (&[FamilyName::SansSerif]).into(),
-                font_props: Properties {
-                  weight: Weight::SEMIBOLD,
-                  ..Default::default()
-                },
-              },
-              align: Align::Left,
-              text: self.text.clone(),
-            },
-            false,
-          );
-
-          renderer.remove_text(text_render_id);
-          let (text_width, _text_height) = renderer.get_text_size(&final_text_render_id);
-          (Some(final_text_render_id), children_width + text_width)
-        } else {
-          (None, children_width)
-        };
+      let children_width = if self.text_render_id.is_some() {
+        children_width + renderer.measure_text(&text).width
+      } else {
+        children_width
+      };
 
       let child_x = (scaled_width - children_width).mul_add(0.5, x);
 
-      let child_x = if let Some(icon_render_id) = icon_render_id {
+      let child_x = if let Some(icon_render_id) = self.icon_render_id.take()
+        && let Some(icon) = icon
+      {
         let final_icon_render_id = renderer.add_icon(
           Icon {
-            position: (child_x, scaled_height.mul_add(0.75, y), z + TEXT_Z_OFFSET),
-            color: utils::pack_color(self.text_color),
-            font_size: scaled_height * 0.5,
-            font_key: FontKey::Path(self.icon_font_path.clone()),
-            codepoint: self.icon_codepoint,
+            position: (child_x, scaled_height.mul_add(0.5, y), z + TEXT_Z_OFFSET),
+            ..icon
           },
           false,
         );
@@ -481,33 +474,29 @@ impl Button {
         child_x
       };
 
-      if let Some(text_render_id) = text_render_id {
-        let final_text_render_id = renderer.add_text(
+      if let Some(ref mut text_render_id) = self.text_render_id {
+        let cap_height = renderer
+          .get_font_metrics(&text.font_key, text.font_size)
+          .cap_height;
+
+        renderer.update_text(
+          text_render_id,
           &Text {
-            position: (child_x, scaled_height.mul_add(0.65, y), z + TEXT_Z_OFFSET),
-            color: utils::pack_color(self.text_color),
-            font_size: scaled_height * 0.4,
-            font_key: FontKey::Family {
-              font_family: (&[FamilyName::SansSerif]).into(),
-              font_props: Properties {
-                weight: Weight::SEMIBOLD,
-                ..Default::default()
-              },
-            },
-            align: Align::Left,
-            text: self.text.clone(),
+            position: (
+              child_x,
+              cap_height.mul_add(0.5, scaled_height.mul_add(0.5, y)),
+              z + TEXT_Z_OFFSET,
+            ),
+            ..text
           },
-          false,
         );
-
-        renderer.remove_text(text_render_id);
-        self.text_render_id = Some(final_text_render_id);
       }
     }
 
     self.ripples.iter_mut().for_each(|ripple| {
       ripple.translate((x - old_x, y - old_y, 0.0));
       ripple.set_end_color(color);
+      ripple.set_opacity(self.opacity);
       ripple.update(dt, renderer);
     });
 
@@ -521,6 +510,7 @@ impl Button {
     }
 
     self.old_position = self.position;
+    self.old_opacity = self.opacity;
     self.old_text = self.text.clone();
     self.old_mouse_position = self.mouse_position;
   }
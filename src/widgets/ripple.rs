@@ -8,6 +8,7 @@ pub struct Ripple {
   end_color: (u8, u8, u8, u8),
   duration: f32,
   clipped: bool,
+  opacity: f32,
   circle_render_id: u32,
   time: f32,
 }
@@ -30,6 +31,7 @@ impl Ripple {
       end_color,
       duration,
       clipped,
+      opacity: 1.0,
       circle_render_id: u32::MAX,
       time: 0.0,
     }
@@ -39,9 +41,9 @@ impl Ripple {
     self.circle_render_id = renderer.add_model(
       RoundRect {
         position: self.position,
-        radius: 0.0,
         size: (0.0, 0.0),
-        color: utils::pack_color(self.start_color),
+        color: utils::pack_color(utils::scale_alpha(self.start_color, self.opacity)),
+        ..Default::default()
       },
       self.clipped,
     );
@@ -59,6 +61,11 @@ impl Ripple {
     self.end_color = end_color;
   }
 
+  #[inline]
+  pub const fn set_opacity(&mut self, opacity: f32) {
+    self.opacity = opacity;
+  }
+
   pub fn update(&mut self, dt: f32, renderer: &mut RendererRef<'_>) {
     self.time = (self.time + dt).min(self.duration);
 
@@ -68,7 +75,10 @@ impl Ripple {
 
     let time_scale = self.time / self.duration;
     let radius = self.end_radius * (time_scale * 4.0).min(1.0);
-    let color = utils::lerp_color(self.start_color, self.end_color, time_scale);
+    let color = utils::scale_alpha(
+      utils::lerp_color(self.start_color, self.end_color, time_scale),
+      self.opacity,
+    );
     let position = (
       self.position.0 - radius,
       self.position.1 - radius,
@@ -80,9 +90,10 @@ impl Ripple {
       self.circle_render_id,
       RoundRect {
         position,
-        radius,
+        radius: [radius; 4],
         size,
         color: utils::pack_color(color),
+        ..Default::default()
       },
       self.clipped,
     );
@@ -80,3 +80,15 @@ fn test_with_capacity() {
   cache.insert(1, 10);
   assert_eq!(cache.evict_one(), Some((1, 10)));
 }
+
+#[test]
+fn test_clear() {
+  let mut cache = LruCache::new();
+  cache.insert(1, 10);
+  cache.insert(2, 20);
+  cache.clear();
+  assert!(cache.evict_one().is_none());
+
+  cache.insert(3, 30);
+  assert_eq!(cache.evict_one(), Some((3, 30)));
+}
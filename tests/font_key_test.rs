@@ -0,0 +1,136 @@
+use flut::{
+  app::App,
+  include_font,
+  models::{font_data::FontData, font_key::FontKey, text::Text},
+};
+use font_kit::{family_name::FamilyName, properties::Properties};
+use rustc_hash::FxBuildHasher;
+use std::{hash::BuildHasher, sync::Arc};
+
+fn make_family_font_key(font_family: FamilyName) -> FontKey {
+  FontKey::Family {
+    font_family: vec![font_family].into(),
+    font_props: Properties::default(),
+  }
+}
+
+#[test]
+fn test_bytes_font_keys_are_told_apart_by_name() {
+  let font_key = FontKey::from_bytes("Icons", vec![0, 1, 2]);
+  let same_name_font_key = FontKey::from_bytes("Icons", Arc::<[u8]>::from([3, 4]));
+  let other_font_key = FontKey::from_bytes("Symbols", vec![0, 1, 2]);
+
+  assert!(font_key == same_name_font_key);
+  assert!(font_key != other_font_key);
+
+  assert_eq!(
+    FxBuildHasher.hash_one(&font_key),
+    FxBuildHasher.hash_one(&same_name_font_key)
+  );
+}
+
+#[test]
+fn test_include_font_embeds_font_file() {
+  let font_key = include_font!(
+    "MaterialSymbolsOutlined",
+    "../assets/void/fonts/MaterialSymbolsOutlined-Regular.ttf"
+  );
+
+  let FontKey::Bytes {
+    ref name,
+    ref font_data,
+  } = font_key
+  else {
+    panic!("Expected an embedded font");
+  };
+
+  assert_eq!(name, "MaterialSymbolsOutlined");
+  assert!(matches!(*font_data, FontData::Static(_)));
+  assert!(!font_data.is_empty());
+}
+
+#[test]
+fn test_static_font_data_is_borrowed() {
+  static FONT_DATA: [u8; 3] = [0, 1, 2];
+  let font_key = FontKey::from_bytes("Icons", &FONT_DATA);
+
+  let FontKey::Bytes { ref font_data, .. } = font_key else {
+    panic!("Expected an embedded font");
+  };
+
+  assert_eq!(font_data.as_ptr(), FONT_DATA.as_ptr());
+}
+
+#[test]
+fn test_fallback_font_keys_are_flattened_in_order() {
+  let serif_font_key = make_family_font_key(FamilyName::Serif);
+  let icon_font_key = FontKey::from_bytes("Icons", vec![0, 1, 2]);
+  let monospace_font_key = make_family_font_key(FamilyName::Monospace);
+
+  let font_key = FontKey::Fallback(
+    vec![
+      serif_font_key.clone(),
+      FontKey::Fallback(
+        vec![
+          icon_font_key.clone(),
+          FontKey::Fallback([].as_slice().into()),
+        ]
+        .into(),
+      ),
+      monospace_font_key.clone(),
+    ]
+    .into(),
+  );
+
+  assert!(font_key.flatten() == [serif_font_key, icon_font_key, monospace_font_key]);
+}
+
+#[test]
+fn test_empty_fallback_flattens_to_default_font() {
+  let font_key = FontKey::Fallback([].as_slice().into());
+
+  assert!(font_key.flatten() == [FontKey::default()]);
+  assert!(FontKey::default().flatten() == [FontKey::default()]);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_fallback_text_measures_like_its_resolving_font() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = Text {
+    text: "Hello, world!".into(),
+    ..Default::default()
+  };
+
+  let fallback_text = Text {
+    font_key: FontKey::Fallback(
+      vec![
+        FontKey::default(),
+        make_family_font_key(FamilyName::Monospace),
+      ]
+      .into(),
+    ),
+    ..text.clone()
+  };
+
+  let empty_fallback_text = Text {
+    font_key: FontKey::Fallback([].as_slice().into()),
+    ..text.clone()
+  };
+
+  let text_metrics = renderer.measure_text(&text);
+  assert_eq!(
+    renderer.measure_text(&fallback_text).width,
+    text_metrics.width
+  );
+  assert_eq!(
+    renderer.measure_text(&empty_fallback_text).width,
+    text_metrics.width
+  );
+
+  let text_id = renderer.add_text(&empty_fallback_text, false);
+  renderer.remove_text(text_id);
+  app.drop();
+}
@@ -0,0 +1,57 @@
+use flut::{
+  app::App,
+  models::{font_key::FontKey, icon::Icon, icon_glyph::IconGlyph, vertical_anchor::VerticalAnchor},
+};
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_icon_name_resolves_to_its_char() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let icon = Icon {
+    position: (16.0, 32.0, 0.5),
+    color: 0x0000_00FF,
+    font_size: 24.0,
+    font_key: FontKey::default(),
+    vertical_anchor: VerticalAnchor::default(),
+    glyph: IconGlyph::Char('A'),
+  };
+
+  let char_metrics = renderer.measure_icon(icon.clone());
+
+  let name_metrics = renderer.measure_icon(Icon {
+    glyph: IconGlyph::from("A"),
+    ..icon
+  });
+
+  assert_eq!(name_metrics, char_metrics);
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_icon_without_ink_can_be_added_and_removed() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let icon = Icon {
+    position: (16.0, 32.0, 0.5),
+    color: 0x0000_00FF,
+    font_size: 24.0,
+    font_key: FontKey::default(),
+    vertical_anchor: VerticalAnchor::default(),
+    glyph: IconGlyph::Char(' '),
+  };
+
+  let icon_metrics = renderer.measure_icon(icon.clone());
+  let icon_id = renderer.add_icon(icon, false);
+
+  assert_eq!(
+    renderer.get_icon_size(&icon_id),
+    (icon_metrics.width, icon_metrics.height)
+  );
+
+  renderer.remove_icon(icon_id);
+  app.drop();
+}
@@ -0,0 +1,156 @@
+use flut::{
+  app::App,
+  models::{line::Line, line_cap::LineCap, line_join::LineJoin, polyline::Polyline},
+};
+use image::Rgba;
+use std::mem;
+
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const LINE_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+fn make_polyline(closed: bool) -> Polyline {
+  Polyline {
+    points: vec![(0.0, 0.0), (30.0, 0.0), (30.0, 40.0)].into(),
+    dash_length: 4.0,
+    gap_length: 2.0,
+    dash_offset: 1.0,
+    closed,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn test_line_matches_shader_layout() {
+  assert_eq!(mem::size_of::<Line>(), 80);
+}
+
+#[test]
+fn test_open_polyline_caps_its_ends() {
+  let lines = make_polyline(false).to_lines();
+
+  assert_eq!(lines.len(), 2);
+  assert_eq!(lines[0].prev_offset, (0.0, 0.0));
+  assert_eq!(lines[0].next_offset, (0.0, 40.0));
+  assert_eq!(lines[1].prev_offset, (-30.0, 0.0));
+  assert_eq!(lines[1].next_offset, (0.0, 0.0));
+}
+
+#[test]
+fn test_closed_polyline_joins_its_ends() {
+  let lines = make_polyline(true).to_lines();
+
+  assert_eq!(lines.len(), 3);
+  assert_eq!(lines[2].start, (30.0, 40.0));
+  assert_eq!(lines[2].end, (0.0, 0.0));
+  assert_eq!(lines[0].prev_offset, (30.0, 40.0));
+  assert_eq!(lines[2].next_offset, (30.0, 0.0));
+}
+
+#[test]
+fn test_polyline_carries_dash_pattern_on() {
+  let dash_offsets = make_polyline(true)
+    .to_lines()
+    .iter()
+    .map(|line| line.dash_offset)
+    .collect::<Vec<_>>();
+
+  assert_eq!(dash_offsets, [1.0, 31.0, 71.0]);
+}
+
+#[test]
+fn test_polyline_needs_two_points() {
+  let polyline = Polyline {
+    points: vec![(0.0, 0.0)].into(),
+    ..Default::default()
+  };
+
+  assert!(polyline.to_lines().is_empty());
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_line_caps() {
+  // Pixel just past the end of a horizontal line that only square and round caps cover
+  for (cap, covered) in [
+    (LineCap::Butt, false),
+    (LineCap::Round, true),
+    (LineCap::Square, true),
+  ] {
+    let mut app = App::new_headless().size((80.0, 80.0)).call();
+
+    app.get_renderer().add_model(
+      Line {
+        start: (20.0, 40.0),
+        end: (60.0, 40.0),
+        z: 0.5,
+        thickness: 8.0,
+        color: 0xFFFF_FFFF,
+        cap,
+        ..Default::default()
+      },
+      false,
+    );
+
+    let image = app.render_to_image().unwrap();
+    assert_eq!(*image.get_pixel(40, 40), LINE_COLOR, "{cap:?}");
+    assert_eq!(*image.get_pixel(61, 40) == LINE_COLOR, covered, "{cap:?}");
+    assert_eq!(*image.get_pixel(40, 46), BACKGROUND_COLOR, "{cap:?}");
+    app.drop();
+  }
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_line_joins() {
+  // Pixel at the outer corner of a right angle that only a miter join covers
+  for (join, covered) in [
+    (LineJoin::Miter, true),
+    (LineJoin::Round, false),
+    (LineJoin::Bevel, false),
+  ] {
+    let mut app = App::new_headless().size((80.0, 80.0)).call();
+
+    let lines = Polyline {
+      points: vec![(10.0, 20.0), (60.0, 20.0), (60.0, 70.0)].into(),
+      z: 0.5,
+      thickness: 10.0,
+      color: 0xFFFF_FFFF,
+      join,
+      ..Default::default()
+    }
+    .to_lines();
+
+    app.get_renderer().bulk_add_models(lines, false);
+
+    let image = app.render_to_image().unwrap();
+    assert_eq!(*image.get_pixel(60, 20), LINE_COLOR, "{join:?}");
+    assert_eq!(*image.get_pixel(64, 16) == LINE_COLOR, covered, "{join:?}");
+    app.drop();
+  }
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_dashed_line_leaves_gaps() {
+  let mut app = App::new_headless().size((80.0, 80.0)).call();
+
+  app.get_renderer().add_model(
+    Line {
+      start: (0.0, 40.0),
+      end: (80.0, 40.0),
+      z: 0.5,
+      thickness: 4.0,
+      color: 0xFFFF_FFFF,
+      dash_length: 10.0,
+      gap_length: 10.0,
+      ..Default::default()
+    },
+    false,
+  );
+
+  let image = app.render_to_image().unwrap();
+  assert_eq!(*image.get_pixel(5, 40), LINE_COLOR);
+  assert_eq!(*image.get_pixel(15, 40), BACKGROUND_COLOR);
+  assert_eq!(*image.get_pixel(25, 40), LINE_COLOR);
+  app.drop();
+}
@@ -1,2 +1,11 @@
 mod collections;
+mod font_key_test;
+mod icon_test;
+mod line_test;
+mod paint_test;
+mod round_rect_test;
+mod sdf_test;
+mod shadow_test;
+mod text;
 mod utils_test;
+mod widgets;
@@ -0,0 +1,164 @@
+use flut::{
+  app::App,
+  models::{paint::Paint, paint_kind::PaintKind, round_rect::RoundRect},
+};
+use std::mem;
+
+fn make_paint(kind: PaintKind) -> Paint {
+  Paint {
+    kind,
+    stop_count: 2,
+    start: (40.0, 40.0),
+    end: (80.0, 40.0),
+    stop_offsets: [0.0, 1.0, 0.0, 0.0],
+    stop_colors: [0xFF00_00FF, 0x0000_FFFF, 0, 0],
+  }
+}
+
+fn render_painted_rect(paint: Paint) -> image::RgbaImage {
+  render_painted_rect_at(paint, (0.0, 0.0))
+}
+
+fn render_painted_rect_at(paint: Paint, position: (f32, f32)) -> image::RgbaImage {
+  let mut app = App::new_headless().size((160.0, 80.0)).call();
+  let mut renderer = app.get_renderer();
+  let paint_id = renderer.add_paint(paint);
+  let (x, y) = position;
+
+  renderer.add_model(
+    RoundRect {
+      position: (x, y, 0.5),
+      size: (80.0, 80.0),
+      color: 0xFFFF_FFFF,
+      paint_id,
+      ..Default::default()
+    },
+    false,
+  );
+
+  let image = app.render_to_image().unwrap();
+  app.drop();
+  image
+}
+
+#[test]
+fn test_paint_matches_shader_layout() {
+  assert_eq!(mem::size_of::<Paint>(), 56);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_linear_gradient_blends_between_stops() {
+  let image = render_painted_rect(Paint {
+    start: (0.0, 0.0),
+    ..make_paint(PaintKind::LinearGradient)
+  });
+
+  let [left_red, _, left_blue, _] = image.get_pixel(0, 40).0;
+  let [middle_red, _, middle_blue, _] = image.get_pixel(40, 40).0;
+  let [right_red, _, right_blue, _] = image.get_pixel(79, 40).0;
+
+  assert!(left_red > 240 && left_blue < 15);
+  assert!((100..156).contains(&middle_red) && (100..156).contains(&middle_blue));
+  assert!(right_red < 15 && right_blue > 240);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_radial_gradient_grows_from_start() {
+  let image = render_painted_rect(make_paint(PaintKind::RadialGradient));
+
+  let [center_red, _, center_blue, _] = image.get_pixel(40, 40).0;
+  let [corner_red, _, corner_blue, _] = image.get_pixel(0, 0).0;
+
+  assert!(center_red > 240 && center_blue < 15);
+  assert!(corner_red < 15 && corner_blue > 240);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_conic_gradient_turns_clockwise_from_end() {
+  let image = render_painted_rect(make_paint(PaintKind::ConicGradient));
+
+  // A quarter turn clockwise from the right points down
+  let [below_red, _, below_blue, _] = image.get_pixel(40, 70).0;
+  let [above_red, _, above_blue, _] = image.get_pixel(40, 10).0;
+
+  assert!(below_red > below_blue);
+  assert!(above_blue > above_red);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_paint_is_placed_relative_to_model() {
+  let paint = Paint {
+    start: (0.0, 0.0),
+    ..make_paint(PaintKind::LinearGradient)
+  };
+
+  let image = render_painted_rect_at(paint, (80.0, 0.0));
+
+  let [left_red, _, left_blue, _] = image.get_pixel(80, 40).0;
+  let [right_red, _, right_blue, _] = image.get_pixel(159, 40).0;
+
+  assert!(left_red > 240 && left_blue < 15);
+  assert!(right_red < 15 && right_blue > 240);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_translucent_paint_blends_over_models_behind() {
+  let mut app = App::new_headless().size((80.0, 80.0)).call();
+  let mut renderer = app.get_renderer();
+
+  let paint_id = renderer.add_paint(Paint {
+    stop_colors: [0xFF00_0080, 0xFF00_0080, 0, 0],
+    ..make_paint(PaintKind::LinearGradient)
+  });
+
+  renderer.add_model(
+    RoundRect {
+      position: (0.0, 0.0, 0.25),
+      size: (80.0, 80.0),
+      color: 0xFFFF_FFFF,
+      paint_id,
+      ..Default::default()
+    },
+    false,
+  );
+
+  renderer.add_model(
+    RoundRect {
+      position: (0.0, 0.0, 0.5),
+      size: (80.0, 80.0),
+      color: 0x0000_FFFF,
+      ..Default::default()
+    },
+    false,
+  );
+
+  let image = app.render_to_image().unwrap();
+  app.drop();
+
+  let [red, _, blue, _] = image.get_pixel(40, 40).0;
+  assert!((100..156).contains(&red) && (100..156).contains(&blue));
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_removed_paint_id_is_reused() {
+  let mut app = App::new_headless().size((80.0, 80.0)).call();
+  let mut renderer = app.get_renderer();
+  let paint_id = renderer.add_paint(make_paint(PaintKind::LinearGradient));
+  let other_paint_id = renderer.add_paint(make_paint(PaintKind::RadialGradient));
+
+  renderer.remove_paint(paint_id);
+
+  assert_ne!(paint_id, 0);
+  assert_ne!(other_paint_id, paint_id);
+  assert_eq!(
+    renderer.add_paint(make_paint(PaintKind::ConicGradient)),
+    paint_id
+  );
+  app.drop();
+}
@@ -0,0 +1,116 @@
+use flut::{
+  app::App,
+  models::{border_align::BorderAlign, round_rect::RoundRect},
+};
+use image::Rgba;
+use std::mem;
+use voracious_radix_sort::Radixable as _;
+
+const FILL_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const BORDER_COLOR: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+fn make_round_rect(border_align: BorderAlign) -> RoundRect {
+  RoundRect {
+    position: (20.0, 20.0, 0.5),
+    size: (40.0, 40.0),
+    color: 0xFF00_00FF,
+    border_width: 8.0,
+    border_color: 0x0000_FFFF,
+    border_align,
+    ..Default::default()
+  }
+}
+
+#[test]
+fn test_round_rect_matches_shader_layout() {
+  assert_eq!(mem::size_of::<RoundRect>(), 64);
+}
+
+#[test]
+fn test_translucent_border_sorts_round_rect_as_translucent() {
+  let round_rect = make_round_rect(BorderAlign::Inner);
+
+  let translucent_round_rect = RoundRect {
+    border_color: 0x0000_FF80,
+    ..round_rect
+  };
+
+  let borderless_round_rect = RoundRect {
+    border_width: 0.0,
+    ..translucent_round_rect
+  };
+
+  assert!(round_rect.key() < 1.0);
+  assert!(translucent_round_rect.key() > 1.0);
+  assert!(borderless_round_rect.key() < 1.0);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_border_alignments() {
+  // Border start x, fill start x and a background x on the middle row of each alignment
+  for (border_align, border_x, fill_x, background_x) in [
+    (BorderAlign::Inner, 22, 30, 18),
+    (BorderAlign::Center, 18, 26, 14),
+    (BorderAlign::Outer, 14, 22, 10),
+  ] {
+    let mut app = App::new_headless().size((80.0, 80.0)).call();
+
+    app
+      .get_renderer()
+      .add_model(make_round_rect(border_align), false);
+
+    let image = app.render_to_image().unwrap();
+
+    assert_eq!(
+      *image.get_pixel(border_x, 40),
+      BORDER_COLOR,
+      "{border_align:?}"
+    );
+    assert_eq!(*image.get_pixel(fill_x, 40), FILL_COLOR, "{border_align:?}");
+    assert_ne!(
+      *image.get_pixel(background_x, 40),
+      BORDER_COLOR,
+      "{border_align:?}"
+    );
+    assert_ne!(
+      *image.get_pixel(background_x, 40),
+      FILL_COLOR,
+      "{border_align:?}"
+    );
+    app.drop();
+  }
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_clipped_model_shows_inside_translucent_host() {
+  let mut app = App::new_headless().size((80.0, 80.0)).call();
+  let mut renderer = app.get_renderer();
+
+  renderer.add_model(
+    RoundRect {
+      position: (0.0, 0.0, 0.5),
+      size: (80.0, 80.0),
+      color: 0xFFFF_FF80,
+      ..Default::default()
+    },
+    false,
+  );
+
+  renderer.add_model(
+    RoundRect {
+      position: (20.0, 20.0, 0.5),
+      size: (80.0, 80.0),
+      color: 0xFF00_00FF,
+      ..Default::default()
+    },
+    true,
+  );
+
+  let image = app.render_to_image().unwrap();
+  app.drop();
+
+  let [red, green, _blue, _alpha] = image.get_pixel(40, 40).0;
+  assert!(red > 240 && green < 15);
+}
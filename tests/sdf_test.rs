@@ -0,0 +1,87 @@
+use flut::sdf;
+
+const WIDTH: usize = 32;
+
+fn square_coverage() -> Vec<u8> {
+  (0..WIDTH * WIDTH)
+    .map(|index| {
+      let (x, y) = (index % WIDTH, index / WIDTH);
+      if (8..24).contains(&x) && (8..24).contains(&y) {
+        255
+      } else {
+        0
+      }
+    })
+    .collect()
+}
+
+#[test]
+fn test_sd_coverage_rises_inwards() {
+  let field = sdf::sd_coverage(&square_coverage(), WIDTH, 4.0);
+
+  assert_eq!(field[0], 0);
+  assert_eq!(field[16 * WIDTH + 16], 255);
+  assert!(field[16 * WIDTH + 6] < field[16 * WIDTH + 7]);
+  assert!(field[16 * WIDTH + 7] < 128);
+  assert!(field[16 * WIDTH + 8] > 128);
+  assert!(field[16 * WIDTH + 8] < field[16 * WIDTH + 9]);
+}
+
+#[test]
+fn test_sd_coverage_is_symmetric() {
+  let field = sdf::sd_coverage(&square_coverage(), WIDTH, 4.0);
+
+  for y in 0..WIDTH {
+    for x in 0..WIDTH {
+      assert_eq!(field[y * WIDTH + x], field[y * WIDTH + WIDTH - 1 - x]);
+      assert_eq!(field[y * WIDTH + x], field[x * WIDTH + y]);
+    }
+  }
+}
+
+#[test]
+fn test_sd_coverage_keeps_partial_coverage_near_edge() {
+  let mut coverage = vec![0; WIDTH * WIDTH];
+  coverage[16 * WIDTH + 16] = 128;
+
+  let field = sdf::sd_coverage(&coverage, WIDTH, 4.0);
+  assert!(field[16 * WIDTH + 16].abs_diff(128) <= 1);
+}
+
+#[test]
+fn test_dilate_coverage_grows_by_radius() {
+  let dilated = sdf::dilate_coverage(&square_coverage(), WIDTH, 1.5);
+
+  assert_eq!(dilated[16 * WIDTH + 16], 255);
+  assert_eq!(dilated[16 * WIDTH + 7], 255);
+  assert!(dilated[16 * WIDTH + 6].abs_diff(128) <= 1);
+  assert_eq!(dilated[16 * WIDTH + 5], 0);
+}
+
+#[test]
+fn test_blur_coverage_keeps_total_coverage() {
+  let coverage = square_coverage();
+  let blurred = sdf::blur_coverage(&coverage, WIDTH, 3.0);
+
+  let total = |pixels: &[u8]| pixels.iter().map(|&pixel| u32::from(pixel)).sum::<u32>();
+  assert!(total(&blurred).abs_diff(total(&coverage)) < 255);
+  assert_eq!(blurred[0], 0);
+  assert!(blurred[16 * WIDTH + 8] < 255);
+  assert!(blurred[16 * WIDTH + 7] > 0);
+  assert_eq!(blurred[16 * WIDTH + 16], 255);
+}
+
+#[test]
+fn test_sd_round_rect_uses_radius_of_each_corner() {
+  let half_size = (10.0, 10.0);
+  let radii = [0.0, 2.0, 4.0, 8.0];
+
+  // The corner points of a square are outside every rounded corner by (√2 - 1) × radius
+  for ((x, y), radius) in [(-10.0, -10.0), (10.0, -10.0), (10.0, 10.0), (-10.0, 10.0)]
+    .into_iter()
+    .zip(radii)
+  {
+    let dist = sdf::sd_round_rect((x, y), half_size, radii);
+    assert!((dist - (2.0_f32.sqrt() - 1.0) * radius).abs() < 1e-4);
+  }
+}
@@ -0,0 +1,87 @@
+use flut::{
+  app::App,
+  models::{round_rect::RoundRect, shadow::Shadow},
+};
+use image::Rgba;
+use std::mem;
+use voracious_radix_sort::Radixable as _;
+
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const SHADOW_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+#[test]
+fn test_shadow_matches_shader_layout() {
+  assert_eq!(mem::size_of::<Shadow>(), 64);
+}
+
+#[test]
+fn test_opaque_shadow_sorts_as_translucent() {
+  let shadow = Shadow {
+    position: (0.0, 0.0, 0.5),
+    color: 0x0000_00FF,
+    ..Default::default()
+  };
+
+  assert!(shadow.key() > 1.0);
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_shadow_fades_out_across_edge() {
+  let mut app = App::new_headless().size((80.0, 80.0)).call();
+
+  app.get_renderer().add_model(
+    Shadow {
+      position: (30.0, 30.0, 0.5),
+      color: 0xFF00_00FF,
+      size: (20.0, 20.0),
+      blur_radius: 8.0,
+      ..Default::default()
+    },
+    false,
+  );
+
+  let image = app.render_to_image().unwrap();
+  let [center_red, ..] = image.get_pixel(40, 40).0;
+  let [edge_red, ..] = image.get_pixel(30, 40).0;
+
+  assert!(center_red > 240, "{center_red}");
+  assert!((100..156).contains(&edge_red), "{edge_red}");
+  assert_eq!(*image.get_pixel(14, 40), BACKGROUND_COLOR);
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_clipped_shadow_stays_within_clip_rect() {
+  let mut app = App::new_headless().size((80.0, 80.0)).call();
+  let mut renderer = app.get_renderer();
+
+  renderer.add_model(
+    RoundRect {
+      position: (20.0, 20.0, 0.5),
+      size: (40.0, 40.0),
+      color: 0xFFFF_FFFF,
+      ..Default::default()
+    },
+    false,
+  );
+
+  renderer.add_model(
+    Shadow {
+      position: (20.0, 20.0, 0.5),
+      color: 0xFF00_00FF,
+      size: (40.0, 40.0),
+      offset: (10.0, 10.0),
+      ..Default::default()
+    },
+    true,
+  );
+
+  let image = app.render_to_image().unwrap();
+
+  assert_eq!(*image.get_pixel(50, 50), SHADOW_COLOR);
+  assert_eq!(*image.get_pixel(25, 25), Rgba([255, 255, 255, 255]));
+  assert_eq!(*image.get_pixel(65, 65), BACKGROUND_COLOR);
+  app.drop();
+}
@@ -0,0 +1,34 @@
+use flut::{app::App, models::text::Text};
+use std::{env, fs};
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_atlas_cache_warm_loads_same_layout() {
+  let path = env::temp_dir().join("flut_atlas_cache_test_warm_load.bin");
+  let _ = fs::remove_file(&path);
+
+  let text = Text {
+    position: (16.0, 32.0, 0.5),
+    text: "Cached glyphs".into(),
+    ..Default::default()
+  };
+
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+  renderer.load_atlas_cache(&path);
+  let text_id = renderer.add_text(&text, false);
+  let cold_metrics = renderer.measure_text(&text);
+  app = app.render();
+  let mut renderer = app.get_renderer();
+  renderer.remove_text(text_id);
+  renderer.save_atlas_cache().unwrap();
+  app.drop();
+
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+  renderer.load_atlas_cache(&path);
+  assert_eq!(renderer.measure_text(&text), cold_metrics);
+  app.drop();
+
+  fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,55 @@
+use super::text_fixture::make_text;
+use flut::{
+  app::App,
+  models::{align::Align, text::Text},
+};
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_rtl_text_carets_run_right_to_left() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = Text {
+    position: (200.0, 32.0, 0.5),
+    text: "שלום".into(),
+    ..Default::default()
+  };
+
+  let text_metrics = renderer.measure_text(&text);
+  let caret_xs = text_metrics
+    .carets
+    .iter()
+    .map(|&(caret_x, _caret_y)| caret_x)
+    .collect::<Vec<_>>();
+
+  assert_eq!(caret_xs.len(), text.text.chars().count() + 1);
+  assert!(caret_xs.is_sorted_by(|caret_x, next_caret_x| caret_x >= next_caret_x));
+
+  // Left alignment starts a right to left paragraph at the text position
+  assert!(caret_xs[0].abs() < 0.001);
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_mixed_direction_text_keeps_ltr_run_order() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = Text {
+    align: Align::Left,
+    ..make_text("abc שלום def")
+  };
+
+  let text_metrics = renderer.measure_text(&text);
+  let (abc_x, _abc_y) = text_metrics.carets[0];
+  let (hebrew_x, _hebrew_y) = text_metrics.carets[4];
+  let (def_x, _def_y) = text_metrics.carets[9];
+
+  assert!(abc_x < hebrew_x && hebrew_x < def_x);
+  assert_eq!(text_metrics.carets.len(), text.text.chars().count() + 1);
+
+  app.drop();
+}
@@ -0,0 +1,20 @@
+use flut::{app::App, models::text::Text};
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_text_bigger_than_glyph_atlas_is_added() {
+  let mut app = App::new_headless().glyph_atlas_size((32, 32)).call();
+  let mut renderer = app.get_renderer();
+
+  let text_id = renderer.add_text(
+    &Text {
+      font_size: 96.0,
+      text: "Big".into(),
+      ..Default::default()
+    },
+    false,
+  );
+
+  renderer.remove_text(text_id);
+  app.drop();
+}
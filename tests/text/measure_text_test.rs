@@ -0,0 +1,27 @@
+use super::text_fixture::make_text;
+use flut::app::App;
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_measure_text_matches_added_text() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = make_text("Hello, world!");
+
+  let text_metrics = renderer.measure_text(&text);
+  let text_id = renderer.add_text(&text, false);
+
+  assert_eq!(
+    renderer.get_text_size(&text_id),
+    (text_metrics.width, text_metrics.height)
+  );
+
+  assert_eq!(text_metrics.advances.len(), text.text.chars().count());
+  assert!(text_metrics.ascent > 0.0);
+  assert!(text_metrics.descent > 0.0);
+  assert!(text_metrics.baseline > 0.0 && text_metrics.baseline <= text_metrics.height);
+
+  renderer.remove_text(text_id);
+  app.drop();
+}
@@ -0,0 +1,11 @@
+mod atlas_cache_test;
+mod bidi_text_test;
+mod glyph_atlas_test;
+mod measure_text_test;
+mod overflow_test;
+mod rich_text_test;
+mod shaping_test;
+mod text_decoration_test;
+mod text_fixture;
+mod text_hit_test_test;
+mod vertical_anchor_test;
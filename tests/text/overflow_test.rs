@@ -0,0 +1,70 @@
+use super::text_fixture::make_text;
+use flut::{
+  app::App,
+  models::{overflow::Overflow, text::Text, wrap::Wrap},
+};
+
+const MAX_WIDTH: f32 = 80.0;
+
+fn make_overflowing_text(overflow: Overflow) -> Text {
+  Text {
+    max_width: Some(MAX_WIDTH),
+    wrap: Wrap::None,
+    overflow,
+    ..make_text("A label far too long for its container")
+  }
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_overflowing_text_fits_max_width() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let visible_width = renderer
+    .measure_text(&make_overflowing_text(Overflow::Visible))
+    .width;
+  assert!(visible_width > MAX_WIDTH);
+
+  for overflow in [
+    Overflow::Clip,
+    Overflow::Ellipsis,
+    Overflow::MiddleEllipsis,
+    Overflow::ShrinkToFit { min_font_size: 1.0 },
+  ] {
+    let text = make_overflowing_text(overflow);
+    let text_id = renderer.add_text(&text, false);
+    let (text_width, _text_height) = renderer.get_text_size(&text_id);
+
+    assert!(text_width <= MAX_WIDTH, "{overflow:?} is {text_width} wide");
+    assert!(text_width > 0.0);
+
+    // Elided chars collapse onto the ellipsis instead of going missing
+    let text_metrics = renderer.measure_text(&text);
+    assert_eq!(text_metrics.advances.len(), text.text.chars().count());
+    assert_eq!(text_metrics.carets.len(), text.text.chars().count() + 1);
+    renderer.remove_text(text_id);
+  }
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_shrink_to_fit_stops_at_min_font_size() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let min_font_size_metrics = renderer.measure_text(&Text {
+    font_size: 12.0,
+    overflow: Overflow::Clip,
+    ..make_overflowing_text(Overflow::Visible)
+  });
+
+  let shrunk_metrics = renderer.measure_text(&make_overflowing_text(Overflow::ShrinkToFit {
+    min_font_size: 12.0,
+  }));
+
+  assert_eq!(shrunk_metrics, min_font_size_metrics);
+  app.drop();
+}
@@ -0,0 +1,72 @@
+use super::text_fixture::make_text;
+use flut::{
+  app::App,
+  models::{rich_text::RichText, text::Text, text_decoration::TextDecoration, text_span::TextSpan},
+};
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_rich_text_with_one_span_matches_text() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = make_text("Hello, world!");
+
+  let text_metrics = renderer.measure_text(&text);
+  let rich_text_metrics = renderer.measure_rich_text(&RichText::from(text));
+  assert_eq!(rich_text_metrics, text_metrics);
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_rich_text_spans_share_one_line() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let rich_text = RichText {
+    position: (16.0, 32.0, 0.5),
+    spans: vec![
+      TextSpan {
+        text: "Hello, ".into(),
+        ..Default::default()
+      },
+      TextSpan {
+        color: 0xFF00_00FF,
+        font_size: 32.0,
+        decoration: TextDecoration {
+          underline: true,
+          ..Default::default()
+        },
+        text: "world!".into(),
+        ..Default::default()
+      },
+    ]
+    .into(),
+    ..Default::default()
+  };
+
+  let small_text_metrics = renderer.measure_text(&Text {
+    text: "Hello, ".into(),
+    ..Default::default()
+  });
+
+  let rich_text_metrics = renderer.measure_rich_text(&rich_text);
+  let text_id = renderer.add_rich_text(&rich_text, false);
+
+  assert_eq!(
+    rich_text_metrics.advances.len(),
+    "Hello, world!".chars().count()
+  );
+  assert!(rich_text_metrics.width > small_text_metrics.width * 2.0);
+  assert!(rich_text_metrics.ascent > small_text_metrics.ascent);
+
+  assert_eq!(
+    renderer.get_text_size(&text_id),
+    (rich_text_metrics.width, rich_text_metrics.height)
+  );
+
+  renderer.remove_text(text_id);
+  app.drop();
+}
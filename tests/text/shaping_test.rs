@@ -0,0 +1,46 @@
+use super::text_fixture::make_text;
+use flut::{app::App, include_font, models::text::Text, renderer_ref::RendererRef};
+
+/// Advance of each char of text in the embedded DejaVu Sans, which kerns "AV" and has an "fi"
+/// ligature
+fn measure_advances(renderer: &mut RendererRef<'_>, text: &'static str) -> Box<[f32]> {
+  let text = Text {
+    font_key: include_font!("DejaVu Sans", "../fonts/DejaVuSans.ttf"),
+    ..make_text(text)
+  };
+
+  renderer.measure_text(&text).advances
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_kerned_pair_advances_less_than_its_chars() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let kerned_advances = measure_advances(&mut renderer, "AV");
+  let a_advances = measure_advances(&mut renderer, "A");
+  let v_advances = measure_advances(&mut renderer, "V");
+
+  assert_eq!(kerned_advances.len(), 2);
+  assert!(kerned_advances[0] < a_advances[0]);
+  assert!(kerned_advances.iter().sum::<f32>() < a_advances[0] + v_advances[0]);
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_ligature_shapes_to_one_glyph() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let ligature_advances = measure_advances(&mut renderer, "fi");
+
+  // The ligature glyph belongs to its first char, so the second char advances by nothing
+  assert_eq!(ligature_advances.len(), 2);
+  assert!(ligature_advances[0] > 0.0);
+  assert!(ligature_advances[1].abs() < f32::EPSILON);
+
+  app.drop();
+}
@@ -0,0 +1,107 @@
+use super::text_fixture::make_text;
+use flut::{
+  app::App,
+  models::{
+    text::Text, text_decoration::TextDecoration, text_outline::TextOutline, text_shadow::TextShadow,
+  },
+};
+
+fn make_decorated_text(decoration: TextDecoration) -> Text {
+  Text {
+    decoration,
+    ..make_text("Decorated")
+  }
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_outline_and_shadow_keep_text_metrics() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let plain_metrics = renderer.measure_text(&make_decorated_text(TextDecoration::default()));
+
+  let decorated_metrics = renderer.measure_text(&make_decorated_text(TextDecoration {
+    outline: Some(TextOutline {
+      width: 2.0,
+      color: 0xFFFF_FFFF,
+    }),
+    shadow: Some(TextShadow {
+      offset: (2.0, 2.0),
+      blur_radius: 4.0,
+      color: 0x0000_0080,
+    }),
+    ..Default::default()
+  }));
+
+  assert_eq!(decorated_metrics, plain_metrics);
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_decorated_text_is_clipped() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  for clipped in [false, true] {
+    let text_id = renderer.add_text(
+      &make_decorated_text(TextDecoration {
+        underline: true,
+        strikethrough: true,
+        outline: Some(TextOutline {
+          width: 1.0,
+          color: 0xFFFF_FFFF,
+        }),
+        shadow: Some(TextShadow {
+          offset: (1.0, 1.0),
+          blur_radius: 2.0,
+          color: 0x0000_0080,
+        }),
+      }),
+      clipped,
+    );
+
+    let (text_width, text_height) = renderer.get_text_size(&text_id);
+    assert!(text_width > 0.0);
+    assert!(text_height > 0.0);
+    renderer.remove_text(text_id);
+  }
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_decorated_text_is_removed_after_scale_factor_change() {
+  let mut app = App::new_headless().call();
+
+  let text_id = app.get_renderer().add_text(
+    &make_decorated_text(TextDecoration {
+      underline: true,
+      outline: Some(TextOutline {
+        width: 1.0,
+        color: 0xFFFF_FFFF,
+      }),
+      shadow: Some(TextShadow {
+        offset: (1.0, 1.0),
+        blur_radius: 2.0,
+        color: 0x0000_0080,
+      }),
+      ..Default::default()
+    }),
+    false,
+  );
+
+  let mut app = app.set_scale_factor(2.0);
+  let mut renderer = app.get_renderer();
+  let (text_width, text_height) = renderer.get_text_size(&text_id);
+  assert!(text_width > 0.0);
+  assert!(text_height > 0.0);
+  renderer.remove_text(text_id);
+
+  // Glyphs released by the removed text are the ones a new text at the new scale references
+  let text_id = renderer.add_text(&make_decorated_text(TextDecoration::default()), false);
+  renderer.remove_text(text_id);
+  app.drop();
+}
@@ -0,0 +1,10 @@
+use flut::models::text::Text;
+
+/// Text at the position every text test lays out at
+pub fn make_text(text: &'static str) -> Text {
+  Text {
+    position: (16.0, 32.0, 0.5),
+    text: text.into(),
+    ..Default::default()
+  }
+}
@@ -0,0 +1,52 @@
+use super::text_fixture::make_text;
+use flut::app::App;
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_hit_test_finds_caret_under_point() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = make_text("Hello, world!");
+
+  let text_id = renderer.add_text(&text, false);
+
+  for char_index in 0..=text.text.chars().count() {
+    let caret_rect = renderer.caret_rect(&text_id, char_index);
+    let (caret_x, caret_y) = caret_rect.position;
+    let (_caret_width, caret_height) = caret_rect.size;
+
+    assert_eq!(
+      renderer.text_hit_test(&text_id, (caret_x, caret_height.mul_add(0.5, caret_y))),
+      char_index
+    );
+  }
+
+  renderer.remove_text(text_id);
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_selection_rects_span_selected_chars() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let text = make_text("Hello, world!\nBye");
+
+  let text_id = renderer.add_text(&text, false);
+  let selection_rects = renderer.selection_rects(&text_id, 7..16);
+
+  assert_eq!(selection_rects.len(), 2);
+
+  let (first_x, first_y) = selection_rects[0].position;
+  let (second_x, second_y) = selection_rects[1].position;
+  let (start_caret_x, _start_caret_y) = renderer.caret_rect(&text_id, 7).position;
+
+  assert!((first_x - start_caret_x).abs() < 0.001);
+  assert!(second_x < first_x);
+  assert!(second_y > first_y);
+
+  renderer.remove_text(text_id);
+  app.drop();
+}
@@ -0,0 +1,60 @@
+use super::text_fixture::make_text;
+use flut::{
+  app::App,
+  models::{font_key::FontKey, text::Text, vertical_anchor::VerticalAnchor},
+  renderer_ref::RendererRef,
+};
+
+fn make_anchored_text(vertical_anchor: VerticalAnchor) -> Text {
+  Text {
+    vertical_anchor,
+    ..make_text("Line one\nLine two")
+  }
+}
+
+/// First and last baseline of the anchored text, relative to its position
+fn measure_baselines(
+  renderer: &mut RendererRef<'_>,
+  vertical_anchor: VerticalAnchor,
+) -> (f32, f32) {
+  let metrics = renderer.measure_text(&make_anchored_text(vertical_anchor));
+  let (_first_caret_x, first_baseline_y) = metrics.carets[0];
+  let (_last_caret_x, last_baseline_y) = metrics.carets[metrics.carets.len() - 1];
+  (first_baseline_y, last_baseline_y)
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_vertical_anchor_moves_baselines() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+  let metrics = renderer.measure_text(&make_anchored_text(VerticalAnchor::Baseline));
+
+  let (baseline_y, _last_baseline_y) = measure_baselines(&mut renderer, VerticalAnchor::Baseline);
+  let (top_y, _last_top_y) = measure_baselines(&mut renderer, VerticalAnchor::Top);
+  let (_bottom_y, last_bottom_y) = measure_baselines(&mut renderer, VerticalAnchor::Bottom);
+  let (middle_y, last_middle_y) = measure_baselines(&mut renderer, VerticalAnchor::Middle);
+
+  assert!(baseline_y.abs() < 0.001);
+  assert!((top_y - metrics.ascent).abs() < 0.001);
+  assert!((last_bottom_y + metrics.descent).abs() < 0.001);
+  assert!((middle_y - metrics.ascent + last_middle_y + metrics.descent).abs() < 0.001);
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_font_metrics_scale_with_font_size() {
+  let mut app = App::new_headless().call();
+  let mut renderer = app.get_renderer();
+
+  let font_metrics = renderer.get_font_metrics(&FontKey::default(), 16.0);
+  let double_font_metrics = renderer.get_font_metrics(&FontKey::default(), 32.0);
+
+  assert!(font_metrics.ascent > font_metrics.cap_height);
+  assert!(font_metrics.cap_height > font_metrics.x_height);
+  assert!(font_metrics.x_height > 0.0);
+  assert!(font_metrics.descent > 0.0);
+  assert!((double_font_metrics.cap_height - font_metrics.cap_height * 2.0).abs() < 0.001);
+  app.drop();
+}
@@ -1,5 +1,5 @@
 use flut::models::range::Range;
-use flut::utils::coalesce_ranges;
+use flut::utils::{coalesce_ranges, scale_alpha};
 
 fn assert_range_eq(actual: Range, start: u32, end: u32) {
   assert!(
@@ -139,3 +139,15 @@ fn test_coalesce_ranges_edge_cases() {
   assert_eq!(ranges.len(), 1);
   assert_range_eq(ranges[0], 10, 15);
 }
+
+#[test]
+fn test_scale_alpha() {
+  assert_eq!(scale_alpha((10, 20, 30, 255), 0.5), (10, 20, 30, 128));
+  assert_eq!(scale_alpha((10, 20, 30, 128), 1.0), (10, 20, 30, 128));
+}
+
+#[test]
+fn test_scale_alpha_out_of_range() {
+  assert_eq!(scale_alpha((10, 20, 30, 255), -1.0), (10, 20, 30, 0));
+  assert_eq!(scale_alpha((10, 20, 30, 255), 2.0), (10, 20, 30, 255));
+}
@@ -0,0 +1,97 @@
+use super::golden;
+use flut::{app::App, widgets::button::Button};
+use std::path::Path;
+use winit::event::{ElementState, MouseButton};
+
+const TOLERANCE: u8 = 2;
+
+fn new_button() -> Button {
+  Button::new()
+    .position((16.0, 16.0, 0.5))
+    .size((128.0, 48.0))
+    .color((0, 0, 255, 255))
+    .call()
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_button_initial() {
+  let mut app = App::new_headless().size((160.0, 80.0)).call();
+  let mut button = new_button();
+  button.init(&mut app.get_renderer());
+  let image = app.render_to_image().unwrap();
+
+  golden::assert_matches(
+    &image,
+    Path::new("tests/goldens/button_initial.png"),
+    TOLERANCE,
+  );
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_button_clicked() {
+  let mut app = App::new_headless().size((160.0, 80.0)).call();
+  let mut button = new_button();
+  let mut renderer = app.get_renderer();
+  button.init(&mut renderer);
+  button.on_mouse_moved((80.0, 40.0), &mut renderer);
+  button.on_mouse_moved((80.0, 40.0), &mut renderer);
+  button.on_mouse_input(ElementState::Pressed, MouseButton::Left, &mut renderer);
+  button.on_mouse_input(ElementState::Released, MouseButton::Left, &mut renderer);
+  button.update(0.125, &mut renderer);
+  let image = app.render_to_image().unwrap();
+
+  golden::assert_matches(
+    &image,
+    Path::new("tests/goldens/button_clicked.png"),
+    TOLERANCE,
+  );
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_button_half_opacity() {
+  let mut app = App::new_headless().size((160.0, 80.0)).call();
+
+  let mut button = Button::new()
+    .position((16.0, 16.0, 0.5))
+    .size((128.0, 48.0))
+    .color((0, 0, 255, 255))
+    .opacity(0.5)
+    .call();
+
+  button.init(&mut app.get_renderer());
+  let image = app.render_to_image().unwrap();
+
+  golden::assert_matches(
+    &image,
+    Path::new("tests/goldens/button_half_opacity.png"),
+    TOLERANCE,
+  );
+
+  app.drop();
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_button_scale_factor_changed() {
+  let mut app = App::new_headless().size((160.0, 80.0)).call();
+  let mut button = new_button();
+  button.init(&mut app.get_renderer());
+  app.render_to_image().unwrap();
+  let mut app = app.set_scale_factor(2.0);
+  let image = app.render_to_image().unwrap();
+
+  golden::assert_matches(
+    &image,
+    Path::new("tests/goldens/button_scale_factor_changed.png"),
+    TOLERANCE,
+  );
+
+  app.drop();
+}
@@ -0,0 +1,93 @@
+use image::RgbaImage;
+use std::{env, fs, path::Path};
+
+const UPDATE_ENV_VAR: &str = "FLUT_UPDATE_GOLDENS";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Diff {
+  pub mismatched_pixel_count: u32,
+  pub max_channel_delta: u8,
+}
+
+impl Diff {
+  #[must_use]
+  #[inline]
+  pub const fn is_match(&self) -> bool {
+    self.mismatched_pixel_count == 0
+  }
+}
+
+#[must_use]
+pub fn compare(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Option<Diff> {
+  if actual.dimensions() != expected.dimensions() {
+    return None;
+  }
+
+  let (mismatched_pixel_count, max_channel_delta) = actual
+    .pixels()
+    .zip(expected.pixels())
+    .map(|(actual_pixel, expected_pixel)| {
+      actual_pixel
+        .0
+        .iter()
+        .zip(expected_pixel.0)
+        .map(|(&actual_channel, expected_channel)| actual_channel.abs_diff(expected_channel))
+        .max()
+        .unwrap_or_default()
+    })
+    .fold(
+      (0, 0),
+      |(mismatched_pixel_count, max_channel_delta), channel_delta| {
+        (
+          mismatched_pixel_count + u32::from(channel_delta > tolerance),
+          max_channel_delta.max(channel_delta),
+        )
+      },
+    );
+
+  Some(Diff {
+    mismatched_pixel_count,
+    max_channel_delta,
+  })
+}
+
+pub fn assert_matches(actual: &RgbaImage, path: &Path, tolerance: u8) {
+  if env::var_os(UPDATE_ENV_VAR).is_some() {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent).unwrap();
+    }
+
+    actual.save(path).unwrap();
+    return;
+  }
+
+  let Ok(expected) = image::open(path) else {
+    panic!(
+      "Failed to open {path}, rerun with {UPDATE_ENV_VAR}=1 to generate it",
+      path = path.display()
+    );
+  };
+
+  let expected = expected.into_rgba8();
+
+  let Some(diff) = compare(actual, &expected, tolerance) else {
+    panic!(
+      "Expected {expected_width}x{expected_height} image from {path}, but got \
+       {actual_width}x{actual_height}",
+      expected_width = expected.width(),
+      expected_height = expected.height(),
+      path = path.display(),
+      actual_width = actual.width(),
+      actual_height = actual.height()
+    );
+  };
+
+  assert!(
+    diff.is_match(),
+    "{mismatched_pixel_count} pixels differ from {path} by up to {max_channel_delta}, rerun with \
+     {UPDATE_ENV_VAR}=1 to update it",
+    mismatched_pixel_count = diff.mismatched_pixel_count,
+    path = path.display(),
+    max_channel_delta = diff.max_channel_delta
+  );
+}
@@ -0,0 +1,43 @@
+use super::golden::{self, Diff};
+use image::{Rgba, RgbaImage};
+
+#[test]
+fn test_compare_identical() {
+  let image = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+
+  assert_eq!(
+    golden::compare(&image, &image, 0),
+    Some(Diff {
+      mismatched_pixel_count: 0,
+      max_channel_delta: 0,
+    })
+  );
+}
+
+#[test]
+fn test_compare_within_tolerance() {
+  let actual = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+  let expected = RgbaImage::from_pixel(4, 4, Rgba([253, 2, 0, 255]));
+  let diff = golden::compare(&actual, &expected, 2).unwrap();
+  assert!(diff.is_match());
+  assert_eq!(diff.max_channel_delta, 2);
+}
+
+#[test]
+fn test_compare_outside_tolerance() {
+  let actual = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255]));
+  let mut expected = actual.clone();
+  expected.put_pixel(1, 2, Rgba([255, 0, 0, 128]));
+  expected.put_pixel(3, 0, Rgba([0, 0, 0, 255]));
+  let diff = golden::compare(&actual, &expected, 2).unwrap();
+  assert!(!diff.is_match());
+  assert_eq!(diff.mismatched_pixel_count, 2);
+  assert_eq!(diff.max_channel_delta, 255);
+}
+
+#[test]
+fn test_compare_size_mismatch() {
+  let actual = RgbaImage::new(4, 4);
+  let expected = RgbaImage::new(4, 8);
+  assert_eq!(golden::compare(&actual, &expected, 255), None);
+}
@@ -0,0 +1,4 @@
+mod button_test;
+mod golden;
+mod golden_test;
+mod ripple_test;
@@ -0,0 +1,41 @@
+use super::golden;
+use flut::{app::App, widgets::ripple::Ripple};
+use std::path::Path;
+
+const TOLERANCE: u8 = 2;
+
+fn render_ripple(time: f32) -> image::RgbaImage {
+  let mut app = App::new_headless().size((128.0, 128.0)).call();
+
+  let mut ripple = Ripple::new()
+    .position((64.0, 64.0, 0.5))
+    .end_radius(48.0)
+    .call();
+
+  let mut renderer = app.get_renderer();
+  ripple.init(&mut renderer);
+  ripple.update(time, &mut renderer);
+  let image = app.render_to_image().unwrap();
+  app.drop();
+  image
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_ripple_expanding() {
+  golden::assert_matches(
+    &render_ripple(0.125),
+    Path::new("tests/goldens/ripple_expanding.png"),
+    TOLERANCE,
+  );
+}
+
+#[test]
+#[cfg_attr(not(feature = "gpu-tests"), ignore = "requires a Vulkan driver")]
+fn test_ripple_fading() {
+  golden::assert_matches(
+    &render_ripple(0.75),
+    Path::new("tests/goldens/ripple_fading.png"),
+    TOLERANCE,
+  );
+}